@@ -1,11 +1,13 @@
-use crate::app::constants::DEFAULT_FONT;
+use crate::app::constants::{DEFAULT_FONT, SCENARIO_PATH};
 use crate::app_impl::asset_dir;
-use crate::scenario::{Current, ScenarioData};
+use crate::scenario::ScenarioData;
+use crate::scenario_asset::{ScenarioAsset, ScenarioHandle};
 use crate::states::AppState;
 use crate::ui_impl::GameFont;
-use anyhow;
+use crate::validation::ScenarioError;
+use bevy::asset::{AssetLoadError, LoadState};
 use bevy::prelude::*;
-use std::fs;
+use thiserror::Error;
 
 #[cfg(feature = "rain_bgm")]
 use crate::app::constants::RAIN_AUDIO_PATH;
@@ -14,14 +16,89 @@ use crate::audio::RainAudioHandle;
 
 #[derive(Resource, Default)]
 pub struct LoadingResources {
-    pub scenario_json: Option<String>,
+    pub scenario_handle: Option<Handle<ScenarioAsset>>,
+    /// 再試行で読み直しを依頼し、その結果をまだ受け取っていない
+    pub scenario_reloading: bool,
     pub font_handle: Option<Handle<Font>>,
     #[cfg(feature = "rain_bgm")]
     pub rain_handle: Option<Handle<AudioSource>>,
 }
 
+/// 起動時の読み込みで失敗した段階と、その詳細
+#[derive(Debug, Clone, Error)]
+pub enum BootFailure {
+    #[error("failed to read scenario: {0}")]
+    ScenarioRead(String),
+    #[error("failed to parse scenario: {0}")]
+    ScenarioParse(String),
+    #[error("scenario validation failed:\n{0}")]
+    ScenarioValidation(String),
+    #[error("failed to load font: {0}")]
+    FontLoad(String),
+    #[cfg(feature = "rain_bgm")]
+    #[error("failed to load rain audio: {0}")]
+    AudioLoad(String),
+}
+
+impl BootFailure {
+    /// エラー画面の見出し（日本語フォントが使えない場合は英語）
+    pub fn heading(&self, japanese: bool) -> &'static str {
+        match (self, japanese) {
+            (BootFailure::ScenarioRead(_), true) => "シナリオを読み込めませんでした",
+            (BootFailure::ScenarioRead(_), false) => "Could not read the scenario",
+            (BootFailure::ScenarioParse(_), true) => "シナリオの書式が正しくありません",
+            (BootFailure::ScenarioParse(_), false) => "The scenario file is malformed",
+            (BootFailure::ScenarioValidation(_), true) => "シナリオに誤りがあります",
+            (BootFailure::ScenarioValidation(_), false) => "The scenario has errors",
+            (BootFailure::FontLoad(_), true) => "フォントを読み込めませんでした",
+            (BootFailure::FontLoad(_), false) => "Could not load the font",
+            #[cfg(feature = "rain_bgm")]
+            (BootFailure::AudioLoad(_), true) => "雨音を読み込めませんでした",
+            #[cfg(feature = "rain_bgm")]
+            (BootFailure::AudioLoad(_), false) => "Could not load the rain audio",
+        }
+    }
+
+    pub fn detail(&self) -> &str {
+        match self {
+            BootFailure::ScenarioRead(detail)
+            | BootFailure::ScenarioParse(detail)
+            | BootFailure::ScenarioValidation(detail)
+            | BootFailure::FontLoad(detail) => detail,
+            #[cfg(feature = "rain_bgm")]
+            BootFailure::AudioLoad(detail) => detail,
+        }
+    }
+}
+
+impl From<&AssetLoadError> for BootFailure {
+    fn from(e: &AssetLoadError) -> Self {
+        match e {
+            // ファイルは読めたがローダーが受け付けなかった
+            AssetLoadError::AssetLoaderError(_) => BootFailure::ScenarioParse(e.to_string()),
+            _ => BootFailure::ScenarioRead(e.to_string()),
+        }
+    }
+}
+
+impl From<ScenarioError> for BootFailure {
+    fn from(e: ScenarioError) -> Self {
+        match e {
+            ScenarioError::Parse(e) => BootFailure::ScenarioParse(e.to_string()),
+            ScenarioError::Invalid(report) => BootFailure::ScenarioValidation(report.to_string()),
+        }
+    }
+}
+
 #[derive(Resource)]
-pub struct BootError(pub Option<String>);
+pub struct BootError(pub BootFailure);
+
+/// 失敗を記録してエラー画面へ遷移する
+fn fail_boot(commands: &mut Commands, next_state: &mut NextState<AppState>, failure: BootFailure) {
+    error!(key = "boot.failed", "{}", failure);
+    commands.insert_resource(BootError(failure));
+    next_state.set(AppState::Error);
+}
 
 #[derive(Resource, Default)]
 pub struct ResourceReadiness {
@@ -45,33 +122,33 @@ impl ResourceReadiness {
     }
 }
 
-fn read_scenario_text() -> anyhow::Result<String> {
-    let base = asset_dir();
-    let p = base.join("scenario.json");
-    let txt = fs::read_to_string(&p).map_err(|e| anyhow::anyhow!("read {:?} failed: {}", p, e))?;
-    Ok(txt)
+/// シナリオが参照するアセットを確認し、問題があればログに出す
+///
+/// 欠けた背景は空のスプライトになるだけで進行はできるため、ここでは起動を止めない。
+fn report_asset_issues(scenario_data: &ScenarioData) {
+    let report = scenario_data.validate_assets(&asset_dir());
+    if report.is_empty() {
+        debug!("All scenario assets found");
+        return;
+    }
+    for issue in &report.issues {
+        error!(key = "boot.asset_invalid", "{}", issue);
+    }
 }
 
-/// Boot ステートでリソースのロードを開始
+/// Boot ステートに入るたびにリソースのロードを開始（エラー画面からの再試行を含む）
 pub fn start_resource_loading(mut commands: Commands, asset_server: Res<AssetServer>) {
     info!("Starting resource loading...");
 
-    // シナリオファイルを同期的に読み込み（将来的に非同期化予定）
-    let scenario_json = match read_scenario_text() {
-        Ok(content) => content,
-        Err(e) => {
-            error!(
-                key = "boot.scenario_read_failed",
-                error = %e,
-                "Failed to read scenario file"
-            );
-            // ここで「エラー状態」に落とす
-            commands.insert_resource(BootError(Some(e.to_string())));
-            return;
-        }
-    };
+    // シナリオは ScenarioLoader で非同期に読み込み、検証まで済ませる
+    let scenario_handle: Handle<ScenarioAsset> = asset_server.load(SCENARIO_PATH);
+    // 読み込み済みのまま再試行した場合は、書き換えられているかもしれないので読み直す
+    let scenario_reloading = asset_server.is_loaded_with_dependencies(&scenario_handle);
+    if scenario_reloading {
+        asset_server.reload(SCENARIO_PATH);
+    }
 
-    // フォントファイルを非同期ロード
+    // フォントファイルを非同期ロード（前回失敗していれば読み込み直される）
     let font_handle: Handle<Font> = asset_server.load(DEFAULT_FONT);
 
     // 雨音ファイルを非同期ロード（rain_bgm feature が有効の場合）
@@ -79,7 +156,8 @@ pub fn start_resource_loading(mut commands: Commands, asset_server: Res<AssetSer
     let rain_handle: Handle<AudioSource> = asset_server.load(RAIN_AUDIO_PATH);
 
     commands.insert_resource(LoadingResources {
-        scenario_json: Some(scenario_json),
+        scenario_handle: Some(scenario_handle.clone()),
+        scenario_reloading,
         font_handle: Some(font_handle.clone()),
         #[cfg(feature = "rain_bgm")]
         rain_handle: Some(rain_handle.clone()),
@@ -87,52 +165,88 @@ pub fn start_resource_loading(mut commands: Commands, asset_server: Res<AssetSer
 
     commands.insert_resource(ResourceReadiness::default());
     commands.insert_resource(GameFont(font_handle));
+    commands.insert_resource(ScenarioHandle(scenario_handle));
 
     #[cfg(feature = "rain_bgm")]
     commands.insert_resource(RainAudioHandle(rain_handle));
 }
 
 /// リソースの準備状況をチェックし、準備完了時にTitleステートに遷移
+#[allow(clippy::too_many_arguments)]
 pub fn check_resources_loaded(
     mut commands: Commands,
     mut next_state: ResMut<NextState<AppState>>,
-    loading_resources: Option<Res<LoadingResources>>,
+    loading_resources: Option<ResMut<LoadingResources>>,
     resource_readiness: Option<ResMut<ResourceReadiness>>,
     asset_server: Res<AssetServer>,
+    scenarios: Res<Assets<ScenarioAsset>>,
+    mut scenario_events: EventReader<AssetEvent<ScenarioAsset>>,
     boot_err: Option<Res<BootError>>,
 ) {
-    if let Some(err) = boot_err.as_ref().and_then(|e| e.0.as_ref()) {
-        // エラー画面へ誘導するならここで AppState::Error に遷移
-        error!("boot failed: {err}");
-        // とりあえずTitleに遷移させる（Error状態は未実装のため）
-        next_state.set(AppState::Title);
+    // 失敗済みならエラー画面への遷移を待つ
+    if boot_err.is_some() {
         return;
     }
 
-    let Some(loading_resources) = loading_resources else {
+    let Some(mut loading_resources) = loading_resources else {
         return;
     }; // ロード完了前は何もしない
     let Some(mut resource_readiness) = resource_readiness else {
         return;
     };
+    // 読み直しを依頼した場合は、その結果が届くまで古い版を見ない
+    if loading_resources.scenario_reloading {
+        let reloaded = loading_resources
+            .scenario_handle
+            .as_ref()
+            .is_some_and(|handle| {
+                // イベントは読み捨てて次のフレームに持ち越さない
+                let modified = scenario_events
+                    .read()
+                    .filter(|event| event.is_modified(handle.id()))
+                    .count();
+                modified > 0 || matches!(asset_server.load_state(handle), LoadState::Failed(_))
+            });
+        if !reloaded {
+            return;
+        }
+        loading_resources.scenario_reloading = false;
+    }
+
     // シナリオファイルの読み込み状況をチェック
     if !resource_readiness.scenario_loaded {
-        if let Some(json_content) = &loading_resources.scenario_json {
-            match ScenarioData::load_from_json(json_content) {
-                Ok(scenario_data) => {
+        if let Some(scenario_handle) = &loading_resources.scenario_handle {
+            match asset_server.load_state(scenario_handle) {
+                LoadState::Loaded => {
+                    let Some(asset) = scenarios.get(scenario_handle) else {
+                        return;
+                    };
+                    if asset.report.has_errors() {
+                        let failure = ScenarioError::Invalid(asset.report.clone()).into();
+                        fail_boot(&mut commands, &mut next_state, failure);
+                        return;
+                    }
+                    for issue in asset.report.warnings() {
+                        warn!("{}", issue);
+                    }
+
+                    let scenario_data = asset.data.clone();
+                    info!(
+                        "Scenario loaded successfully ({} endings)",
+                        scenario_data.ending_count()
+                    );
+                    report_asset_issues(&scenario_data);
+                    commands.insert_resource(scenario_data.initial_current());
                     commands.insert_resource(scenario_data);
-                    commands.insert_resource(Current::default());
                     resource_readiness.scenario_loaded = true;
-                    info!("Scenario loaded successfully");
                 }
-                Err(e) => {
-                    error!(
-                        key = "boot.scenario_parse_failed",
-                        error = %e,
-                        "Failed to parse scenario"
-                    );
+                LoadState::Failed(e) => {
+                    fail_boot(&mut commands, &mut next_state, e.as_ref().into());
                     return;
                 }
+                _ => {
+                    // まだ読み込み中
+                }
             }
         }
     }
@@ -141,12 +255,16 @@ pub fn check_resources_loaded(
     if !resource_readiness.font_loaded {
         if let Some(font_handle) = &loading_resources.font_handle {
             match asset_server.load_state(font_handle) {
-                bevy::asset::LoadState::Loaded => {
+                LoadState::Loaded => {
                     resource_readiness.font_loaded = true;
                     info!("Font loaded successfully");
                 }
-                bevy::asset::LoadState::Failed(_) => {
-                    error!("Failed to load font");
+                LoadState::Failed(e) => {
+                    fail_boot(
+                        &mut commands,
+                        &mut next_state,
+                        BootFailure::FontLoad(e.to_string()),
+                    );
                     return;
                 }
                 _ => {
@@ -161,12 +279,16 @@ pub fn check_resources_loaded(
     if !resource_readiness.rain_loaded {
         if let Some(rain_handle) = &loading_resources.rain_handle {
             match asset_server.load_state(rain_handle) {
-                bevy::asset::LoadState::Loaded => {
+                LoadState::Loaded => {
                     resource_readiness.rain_loaded = true;
                     info!("Rain audio loaded successfully");
                 }
-                bevy::asset::LoadState::Failed(_) => {
-                    error!("Failed to load rain audio");
+                LoadState::Failed(e) => {
+                    fail_boot(
+                        &mut commands,
+                        &mut next_state,
+                        BootFailure::AudioLoad(e.to_string()),
+                    );
                     return;
                 }
                 _ => {
@@ -8,6 +8,9 @@ pub const FONT_CANDIDATES: &[&str] = &[
 /// デフォルトフォント
 pub const DEFAULT_FONT: &str = FONT_CANDIDATES[0];
 
+/// シナリオファイルのパス（アセットディレクトリからの相対）
+pub const SCENARIO_PATH: &str = "scenario.json";
+
 /// 雨音ファイルのパス
 #[cfg(feature = "rain_bgm")]
 pub const RAIN_AUDIO_PATH: &str = "audio/rain.ogg";
@@ -26,6 +29,9 @@ pub mod ui {
     pub const BUTTON_PRESSED_COLOR: Color = Color::srgb(0.6, 0.6, 0.6);
     pub const BUTTON_DISABLED_COLOR: Color = Color::srgb(0.1, 0.1, 0.1);
 
+    /// キーボードでフォーカスしているボタンの枠の色
+    pub const FOCUS_OUTLINE_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+
     /// テキストの色
     pub const TEXT_NORMAL_COLOR: Color = Color::WHITE;
     pub const TEXT_DISABLED_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
@@ -36,6 +42,7 @@ pub mod ui {
     pub const GAME_TEXT_FONT_SIZE: f32 = 24.0;
     pub const CHOICE_FONT_SIZE: f32 = 18.0;
     pub const RESTART_FONT_SIZE: f32 = 20.0;
+    pub const PAGE_INDICATOR_FONT_SIZE: f32 = 14.0;
 }
 
 #[cfg(test)]
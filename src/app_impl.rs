@@ -1,8 +1,15 @@
 use crate::app::boot::*;
 use crate::audio::*;
+use crate::bindings::*;
+use crate::focus::*;
+use crate::gamepad::*;
+use crate::profile::*;
 use crate::save::*;
 use crate::scenario::*;
+use crate::scenario_asset::*;
+use crate::settings::*;
 use crate::states::*;
+use crate::typewriter::*;
 use crate::ui_impl::setup_background_if_needed;
 use crate::ui_impl::*;
 use bevy::asset::AssetPlugin;
@@ -42,6 +49,9 @@ pub fn create_app() -> App {
             .set(AssetPlugin {
                 // ここを上書きすることで AssetServer の基準が CWD 非依存になる
                 file_path: assets_path.to_string_lossy().into_owned(),
+                // ファイル監視は開発ビルドのみ（リリースビルドでは feature が有効でも監視しない）
+                #[cfg(feature = "hot_reload")]
+                watch_for_changes_override: Some(cfg!(debug_assertions)),
                 ..Default::default()
             })
             .set(WindowPlugin {
@@ -57,16 +67,59 @@ pub fn create_app() -> App {
 
     app.init_state::<AppState>();
 
+    app.init_asset::<ScenarioAsset>();
+    app.init_asset_loader::<ScenarioLoader>();
+
     app.add_event::<BeginNewGame>();
     app.add_event::<ContinueGame>();
     app.add_event::<MakeChoice>();
     app.add_event::<RestartGame>();
+    app.add_event::<RetryBoot>();
+    app.add_event::<QuitGame>();
+    app.add_event::<OpenSlotMenu>();
+    app.add_event::<SelectSlot>();
+    app.add_event::<DeleteSlot>();
+    app.add_event::<CloseSlotMenu>();
+    app.add_event::<ShowNotice>();
+    app.add_event::<EndingReached>();
+    app.add_event::<OpenGallery>();
+    app.add_event::<CloseGallery>();
+    app.add_event::<ReplayEnding>();
+    app.add_event::<OpenBacklog>();
+    app.add_event::<CloseBacklog>();
+    app.add_event::<RewindTo>();
+    app.add_event::<OpenRouteMap>();
+    app.add_event::<CloseRouteMap>();
+    app.add_event::<JumpToRoute>();
+    app.add_event::<ReturnToTitle>();
+    app.add_event::<NavigateFocus>();
+    app.add_event::<OpenSettings>();
+    app.add_event::<CloseSettings>();
+    app.add_event::<StartRebind>();
+    app.add_event::<ResetBindings>();
+    app.add_event::<CycleTextSpeed>();
+
+    app.init_resource::<PlayTime>();
+    app.init_resource::<Focus>();
+    app.init_resource::<LastInputDevice>();
+    app.init_resource::<ActionInput>();
+    app.init_resource::<AutoRead>();
 
     app.add_systems(
         Startup,
-        (setup_camera, start_resource_loading, setup_save_manager).chain(),
+        (
+            setup_camera,
+            setup_save_manager,
+            setup_profile,
+            setup_player_settings,
+            setup_bindings,
+        )
+            .chain(),
     );
 
+    // 初回起動とエラー画面からの再試行の両方で読み込みを行う
+    app.add_systems(OnEnter(AppState::Boot), start_resource_loading);
+
     app.add_systems(
         Update,
         check_resources_loaded.run_if(in_state(AppState::Boot)),
@@ -79,9 +132,11 @@ pub fn create_app() -> App {
             cleanup_ui::<EndingUI>,
             setup_background_if_needed,
             start_rain_loop,
+            check_story_update,
             setup_title_ui,
         )
-            .chain(),
+            .chain()
+            .run_if(resource_exists::<ScenarioData>),
     );
 
     app.add_systems(
@@ -94,26 +149,246 @@ pub fn create_app() -> App {
             .chain(),
     );
 
+    app.add_systems(
+        OnExit(AppState::Playing),
+        (
+            cleanup_ui::<BacklogUI>,
+            cleanup_ui::<TitleConfirmUI>,
+            cleanup_ui::<HiddenUIOverlay>,
+            clear_text_reveal,
+        ),
+    );
+    app.add_systems(
+        OnExit(AppState::Ending),
+        (cleanup_ui::<BacklogUI>, cleanup_ui::<HiddenUIOverlay>),
+    );
+
     app.add_systems(
         OnEnter(AppState::Ending),
         (cleanup_ui::<PlayingUI>, setup_ending_ui).chain(),
     );
 
+    app.add_systems(
+        OnEnter(AppState::SlotMenu),
+        (
+            cleanup_ui::<TitleUI>,
+            cleanup_ui::<PlayingUI>,
+            setup_slot_menu_ui,
+        )
+            .chain(),
+    );
+    app.add_systems(OnExit(AppState::SlotMenu), cleanup_ui::<SlotUI>);
+
+    app.add_systems(
+        OnEnter(AppState::Gallery),
+        (
+            cleanup_ui::<TitleUI>,
+            cleanup_ui::<EndingUI>,
+            setup_gallery_ui,
+        )
+            .chain(),
+    );
+    app.add_systems(OnExit(AppState::Gallery), cleanup_ui::<GalleryUI>);
+
+    app.add_systems(
+        OnEnter(AppState::RouteMap),
+        (
+            cleanup_ui::<PlayingUI>,
+            cleanup_ui::<EndingUI>,
+            cleanup_ui::<BacklogUI>,
+            setup_route_map_ui,
+        )
+            .chain(),
+    );
+    app.add_systems(OnExit(AppState::RouteMap), cleanup_ui::<RouteMapUI>);
+
+    app.add_systems(
+        OnEnter(AppState::Settings),
+        (cleanup_ui::<TitleUI>, setup_settings_ui).chain(),
+    );
+    app.add_systems(OnExit(AppState::Settings), cleanup_ui::<SettingsUI>);
+
+    app.add_systems(OnEnter(AppState::Error), setup_error_ui);
+    app.add_systems(OnExit(AppState::Error), cleanup_ui::<ErrorUI>);
+
     app.add_systems(
         Update,
         (
             title_button_system.run_if(in_state(AppState::Title)),
             playing_button_system.run_if(in_state(AppState::Playing)),
+            report_stuck_node.run_if(in_state(AppState::Playing)),
             ending_button_system.run_if(in_state(AppState::Ending)),
+            error_button_system.run_if(in_state(AppState::Error)),
             handle_begin_or_continue.run_if(resource_exists::<Current>),
             handle_make_choice.run_if(resource_exists::<Current>),
+            refresh_playing_ui
+                .after(handle_make_choice)
+                .run_if(in_state(AppState::Playing)),
             handle_restart,
-            auto_save_system.run_if(in_state(AppState::Playing)),
+            handle_retry,
+            handle_quit,
+            auto_save_system
+                .after(handle_rewind)
+                .run_if(in_state(AppState::Playing)),
+            tick_play_time.run_if(in_state(AppState::Playing)),
             button_interaction_system,
+            apply_scenario_reload
+                .before(refresh_playing_ui)
+                .run_if(resource_exists::<ScenarioData>)
+                .run_if(resource_exists::<Current>),
             update_background.run_if(resource_exists::<ScenarioData>),
         ),
     );
 
+    // セーブ・ロード画面とお知らせ
+    app.add_systems(
+        Update,
+        (
+            slot_menu_button_system,
+            handle_open_slot_menu,
+            handle_select_slot.run_if(resource_exists::<SlotMenu>),
+            handle_delete_slot.run_if(resource_exists::<SlotMenu>),
+            handle_close_slot_menu.run_if(resource_exists::<SlotMenu>),
+            refresh_slot_menu_ui
+                .after(handle_select_slot)
+                .after(handle_delete_slot)
+                .run_if(in_state(AppState::SlotMenu)),
+            show_notices
+                .after(handle_begin_or_continue)
+                .after(handle_select_slot)
+                .after(handle_delete_slot),
+            expire_notices,
+            record_reached_endings
+                .after(handle_make_choice)
+                .run_if(resource_exists::<ScenarioData>),
+        ),
+    );
+
+    // エンディングのギャラリー
+    app.add_systems(
+        Update,
+        (
+            gallery_button_system,
+            handle_open_gallery,
+            handle_close_gallery,
+            handle_replay_ending.run_if(resource_exists::<Current>),
+            update_gallery_detail.run_if(in_state(AppState::Gallery)),
+        ),
+    );
+
+    // バックログと巻き戻し（エンディング画面からも使える）
+    app.add_systems(
+        Update,
+        (
+            scroll_backlog,
+            backlog_input_system,
+            rewind_button_system,
+            handle_backlog.run_if(resource_exists::<Current>),
+            handle_rewind.run_if(resource_exists::<Current>),
+        )
+            .chain()
+            .run_if(playing_or_ending),
+    );
+
+    // ルートマップと訪れた場面の記録
+    app.add_systems(
+        Update,
+        (
+            route_map_button_system,
+            handle_open_route_map.run_if(playing_or_ending),
+            handle_close_route_map.run_if(resource_exists::<RouteMap>),
+            handle_jump_to_route.run_if(resource_exists::<Current>),
+            scroll_route_map.run_if(in_state(AppState::RouteMap)),
+            record_visited_routes
+                .after(handle_make_choice)
+                .after(handle_rewind)
+                .after(handle_jump_to_route)
+                .run_if(playing_or_ending)
+                .run_if(resource_exists::<Current>),
+        ),
+    );
+
+    // 本文を1文字ずつ出し、ページを送る
+    app.add_systems(
+        Update,
+        (
+            tick_text_reveal,
+            toggle_auto_read,
+            auto_advance_page,
+            advance_page,
+            complete_text_reveal,
+            show_choices_after_reveal,
+        )
+            .chain()
+            .after(refresh_playing_ui)
+            .run_if(in_state(AppState::Playing))
+            .run_if(resource_exists::<TextReveal>),
+    );
+
+    // 操作の読み替えとキーボードのフォーカス
+    // （ui_focus_system の後に押した状態を作り、同じフレームの Update で処理させる）
+    app.add_systems(
+        PreUpdate,
+        (
+            update_action_input,
+            release_focus_press,
+            track_input_device,
+            (
+                keyboard_focus_input,
+                gamepad_focus_input,
+                // 本文を読んでいる途中の「決定」は残りを出す・ページを送るのに使う
+                confirm_focus_input.run_if(not(reading_text)),
+            )
+                .run_if(not(capturing_binding)),
+            apply_focus_navigation,
+            follow_hover_focus,
+            update_focus_outline,
+        )
+            .chain()
+            .after(bevy::ui::UiSystem::Focus),
+    );
+
+    app.add_systems(
+        Update,
+        (
+            shortcut_system.before(backlog_input_system),
+            // 開いたフレームの Esc で閉じないよう、確認の処理を先に行う
+            title_confirm_system.run_if(in_state(AppState::Playing)),
+            handle_return_to_title.run_if(playing_or_ending),
+            update_choice_glyphs,
+        )
+            .chain(),
+    );
+
+    // 操作設定、クイックセーブ、文章を隠す
+    app.add_systems(
+        Update,
+        (
+            settings_button_system,
+            handle_open_settings.run_if(in_state(AppState::Title)),
+            handle_start_rebind.run_if(resource_exists::<SettingsMenu>),
+            capture_binding
+                .after(handle_start_rebind)
+                .run_if(resource_exists::<SettingsMenu>),
+            handle_reset_bindings.run_if(resource_exists::<SettingsMenu>),
+            handle_cycle_text_speed.run_if(resource_exists::<SettingsMenu>),
+            handle_close_settings.run_if(resource_exists::<SettingsMenu>),
+            refresh_settings_ui
+                .after(capture_binding)
+                .after(handle_reset_bindings)
+                .after(handle_cycle_text_speed)
+                .run_if(resource_exists::<SettingsMenu>),
+            quick_save_system
+                .after(shortcut_system)
+                .run_if(in_state(AppState::Playing))
+                .run_if(resource_exists::<Current>),
+            hide_ui_system
+                .after(shortcut_system)
+                .after(backlog_input_system)
+                .run_if(playing_or_ending),
+        ),
+    );
+
     app
 }
 
@@ -180,12 +455,18 @@ type ChoiceButtonQuery<'w, 's> = Query<
     'w,
     's,
     (&'static Interaction, &'static ChoiceButton),
-    (Changed<Interaction>, With<Button>),
+    (
+        Changed<Interaction>,
+        With<Button>,
+        Without<crate::ui::components::Disabled>,
+    ),
 >;
 
 fn playing_button_system(
     mut choice_events: EventWriter<MakeChoice>,
+    mut title_events: EventWriter<ReturnToTitle>,
     mut button_query: ChoiceButtonQuery,
+    title_query: Query<&Interaction, (Changed<Interaction>, With<StuckTitleButton>)>,
 ) {
     for (interaction, choice_button) in button_query.iter_mut() {
         if *interaction == Interaction::Pressed {
@@ -194,6 +475,32 @@ fn playing_button_system(
             });
         }
     }
+    if title_query.iter().any(|i| *i == Interaction::Pressed) {
+        title_events.send(ReturnToTitle);
+    }
+}
+
+/// どの選択肢も選べない場面に着いたら、場面ごとに1回だけ記録する
+///
+/// ページ送りなどで PlayingUI を作り直すたびに出さないよう、最後に記録した場面を覚えておく。
+fn report_stuck_node(
+    scenario_data: Res<ScenarioData>,
+    current: Res<Current>,
+    mut reported: Local<Option<String>>,
+) {
+    if !current.is_changed() && !scenario_data.is_changed() {
+        return;
+    }
+    if scenario_data.has_available_choice(&current) {
+        *reported = None;
+    } else if reported.as_deref() != Some(current.id.as_str()) {
+        error!(
+            key = "scenario.no_available_choice",
+            id = %current.id,
+            "No choice is available at this node"
+        );
+        *reported = Some(current.id.clone());
+    }
 }
 
 fn ending_button_system(
@@ -207,76 +514,1492 @@ fn ending_button_system(
     }
 }
 
-fn handle_begin_or_continue(
-    mut begin_new_events: EventReader<BeginNewGame>,
-    mut continue_events: EventReader<ContinueGame>,
-    mut current: ResMut<Current>,
-    mut next_state: ResMut<NextState<AppState>>,
-    save_manager: Res<SaveManager>,
+fn error_button_system(
+    mut retry_events: EventWriter<RetryBoot>,
+    mut quit_events: EventWriter<QuitGame>,
+    retry_query: Query<&Interaction, (Changed<Interaction>, With<RetryButton>)>,
+    quit_query: Query<&Interaction, (Changed<Interaction>, With<QuitButton>)>,
 ) {
-    for _event in begin_new_events.read() {
-        *current = Current::default();
-        next_state.set(AppState::Playing);
-        info!("Starting new game");
+    for interaction in retry_query.iter() {
+        if *interaction == Interaction::Pressed {
+            retry_events.send(RetryBoot);
+            return;
+        }
     }
 
-    for _event in continue_events.read() {
-        if let Ok(Some(saved_current)) = save_manager.load() {
-            *current = saved_current;
-            next_state.set(AppState::Playing);
-            info!("Continuing from save");
+    for interaction in quit_query.iter() {
+        if *interaction == Interaction::Pressed {
+            quit_events.send(QuitGame);
+            return;
         }
     }
 }
 
-fn handle_make_choice(
-    mut choice_events: EventReader<MakeChoice>,
-    mut current: ResMut<Current>,
-    mut next_state: ResMut<NextState<AppState>>,
+type OpenSlotMenuButtonQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Interaction, &'static OpenSlotMenuButton),
+    (
+        Changed<Interaction>,
+        Without<crate::ui::components::Disabled>,
+    ),
+>;
+type SlotButtonQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Interaction, &'static SlotButton),
+    (
+        Changed<Interaction>,
+        Without<crate::ui::components::Disabled>,
+    ),
+>;
+
+#[allow(clippy::too_many_arguments)]
+fn slot_menu_button_system(
+    mut open_events: EventWriter<OpenSlotMenu>,
+    mut select_events: EventWriter<SelectSlot>,
+    mut delete_events: EventWriter<DeleteSlot>,
+    mut close_events: EventWriter<CloseSlotMenu>,
+    open_query: OpenSlotMenuButtonQuery,
+    slot_query: SlotButtonQuery,
+    delete_query: Query<(&Interaction, &DeleteSlotButton), Changed<Interaction>>,
+    back_query: Query<&Interaction, (Changed<Interaction>, With<SlotBackButton>)>,
+) {
+    for (interaction, button) in open_query.iter() {
+        if *interaction == Interaction::Pressed {
+            open_events.send(OpenSlotMenu(button.0));
+            return;
+        }
+    }
+
+    for (interaction, button) in slot_query.iter() {
+        if *interaction == Interaction::Pressed {
+            select_events.send(SelectSlot(button.0));
+            return;
+        }
+    }
+
+    for (interaction, button) in delete_query.iter() {
+        if *interaction == Interaction::Pressed {
+            delete_events.send(DeleteSlot(button.0));
+            return;
+        }
+    }
+
+    for interaction in back_query.iter() {
+        if *interaction == Interaction::Pressed {
+            close_events.send(CloseSlotMenu);
+            return;
+        }
+    }
+}
+
+type ButtonPressQuery<'w, 's, T> =
+    Query<'w, 's, &'static Interaction, (Changed<Interaction>, With<T>)>;
+
+fn pressed<T: Component>(query: &ButtonPressQuery<T>) -> bool {
+    query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+}
+
+/// 「バックログ」の割り当て・ホイールを上へ・「ログ」ボタンで開き、
+/// 「バックログ」「戻る」の割り当て・右クリック・「閉じる」で閉じる
+///
+/// 確認ダイアログなど他の覆いが開いている間は開かない。
+#[allow(clippy::too_many_arguments)]
+fn backlog_input_system(
+    actions: Res<ActionInput>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut wheel_events: EventReader<bevy::input::mouse::MouseWheel>,
+    mut open_events: EventWriter<OpenBacklog>,
+    mut close_events: EventWriter<CloseBacklog>,
+    open_query: ButtonPressQuery<OpenBacklogButton>,
+    close_query: ButtonPressQuery<CloseBacklogButton>,
+    backlog_query: Query<(), With<BacklogUI>>,
+    scope_query: Query<(), With<FocusScope>>,
+) {
+    let wheel_up = wheel_events.read().filter(|e| e.y > 0.0).count() > 0;
+    let toggle = actions.just_pressed(Action::OpenBacklog);
+    if backlog_query.is_empty() {
+        if !scope_query.is_empty() {
+            return;
+        }
+        if toggle || wheel_up || pressed(&open_query) {
+            open_events.send(OpenBacklog);
+        }
+    } else if toggle
+        || actions.just_pressed(Action::Back)
+        || mouse_buttons.just_pressed(MouseButton::Right)
+        || pressed(&close_query)
+    {
+        close_events.send(CloseBacklog);
+    }
+}
+
+/// 「選択肢n」の割り当てで選択肢を直接選び、「戻る」の割り当てでタイトル
+/// （セーブ画面などでは元の画面）へ戻る
+///
+/// バックログや確認ダイアログが開いている間はそちらが「戻る」を受け取る。
+#[allow(clippy::too_many_arguments)]
+fn shortcut_system(
+    actions: Res<ActionInput>,
+    state: Res<State<AppState>>,
+    reveal: Option<Res<TextReveal>>,
+    scope_query: Query<(), With<FocusScope>>,
+    choice_query: Query<(&ChoiceButton, Has<crate::ui::components::Disabled>)>,
+    mut choice_events: EventWriter<MakeChoice>,
+    mut title_events: EventWriter<ReturnToTitle>,
+    mut close_slot_events: EventWriter<CloseSlotMenu>,
+    mut close_gallery_events: EventWriter<CloseGallery>,
+    mut close_route_map_events: EventWriter<CloseRouteMap>,
+    mut close_settings_events: EventWriter<CloseSettings>,
+) {
+    if !scope_query.is_empty() {
+        return;
+    }
+
+    // 本文を読んでいる途中は選択肢が隠れているので選ばない
+    let reading = reading_text(reveal);
+    if let (AppState::Playing, Some(number), false) = (state.get(), actions.chosen(), reading) {
+        // 番号は画面に並んでいる順（隠れた選択肢は数えない）
+        let mut choices: Vec<_> = choice_query.iter().collect();
+        choices.sort_by_key(|(button, _)| button.choice_index);
+        if let Some((button, false)) = choices.get(number as usize - 1) {
+            choice_events.send(MakeChoice {
+                choice_index: button.choice_index,
+            });
+            return;
+        }
+    }
+
+    if !actions.just_pressed(Action::Back) {
+        return;
+    }
+    match state.get() {
+        AppState::Playing | AppState::Ending => {
+            title_events.send(ReturnToTitle);
+        }
+        AppState::SlotMenu => {
+            close_slot_events.send(CloseSlotMenu);
+        }
+        AppState::Gallery => {
+            close_gallery_events.send(CloseGallery);
+        }
+        AppState::RouteMap => {
+            close_route_map_events.send(CloseRouteMap);
+        }
+        AppState::Settings => {
+            close_settings_events.send(CloseSettings);
+        }
+        _ => {}
+    }
+}
+
+/// プレイ中は確認を出し、エンディング画面からはそのまま戻る
+fn handle_return_to_title(
+    mut commands: Commands,
+    mut title_events: EventReader<ReturnToTitle>,
+    mut restart_events: EventWriter<RestartGame>,
+    state: Res<State<AppState>>,
+    font: Res<GameFont>,
+    confirm_query: Query<(), With<TitleConfirmUI>>,
+) {
+    if title_events.read().count() == 0 {
+        return;
+    }
+    if *state.get() != AppState::Playing {
+        restart_events.send(RestartGame);
+    } else if confirm_query.is_empty() {
+        spawn_title_confirm_ui(&mut commands, &font);
+    }
+}
+
+/// 「戻る」でタイトルへ、「続ける」か「戻る」の割り当てで確認を閉じる
+fn title_confirm_system(
+    mut commands: Commands,
+    actions: Res<ActionInput>,
+    mut restart_events: EventWriter<RestartGame>,
+    yes_query: ButtonPressQuery<ConfirmYesButton>,
+    no_query: ButtonPressQuery<ConfirmNoButton>,
+    confirm_query: Query<Entity, With<TitleConfirmUI>>,
+) {
+    if confirm_query.is_empty() {
+        return;
+    }
+    if pressed(&yes_query) {
+        restart_events.send(RestartGame);
+    } else if pressed(&no_query) || actions.just_pressed(Action::Back) {
+        for entity in confirm_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn playing_or_ending(state: Res<State<AppState>>) -> bool {
+    matches!(state.get(), AppState::Playing | AppState::Ending)
+}
+
+/// クイックセーブのスロットに今の場面を書き込む（覆いが開いている間は何もしない）
+fn quick_save_system(
+    actions: Res<ActionInput>,
+    current: Res<Current>,
+    play_time: Res<PlayTime>,
+    save_manager: Res<SaveManager>,
     scenario_data: Res<ScenarioData>,
+    scope_query: Query<(), With<FocusScope>>,
+    mut notices: EventWriter<ShowNotice>,
 ) {
-    for choice_event in choice_events.read() {
-        match scenario_data.transition(&current, choice_event.choice_index) {
-            Ok(new_current) => {
-                *current = new_current;
+    if !actions.just_pressed(Action::QuickSave) || !scope_query.is_empty() {
+        return;
+    }
+    let save_data = SaveData::capture(&current, &scenario_data, play_time.0);
+    let slot = SaveSlot::Quick;
+    match save_manager.save(slot, &save_data) {
+        Ok(()) => {
+            info!("Quick saved at {}", current.id);
+            notices.send(ShowNotice(format!("{}しました", slot.label())));
+        }
+        Err(e) => {
+            error!("Failed to save to {:?}: {:#}", slot, e);
+            notices.send(ShowNotice(format!(
+                "{}に保存できませんでした",
+                slot.label()
+            )));
+        }
+    }
+}
 
-                if scenario_data.is_ending(&current) {
-                    next_state.set(AppState::Ending);
-                    info!("Reached ending: {}", current.id);
-                } else {
-                    info!("Transitioned to: {}", current.id);
-                }
-            }
-            Err(e) => {
-                error!("Failed to make choice: {}", e);
-            }
+type HideableUIQuery<'w, 's> =
+    Query<'w, 's, &'static mut Visibility, Or<(With<PlayingUI>, With<EndingUI>)>>;
+
+/// 「文章を隠す」で背景だけを見せ、もう一度押すか「戻る」「決定」・クリックで元に戻す
+#[allow(clippy::too_many_arguments)]
+fn hide_ui_system(
+    mut commands: Commands,
+    actions: Res<ActionInput>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    overlay_query: Query<Entity, With<HiddenUIOverlay>>,
+    scope_query: Query<(), With<FocusScope>>,
+    mut ui_query: HideableUIQuery,
+) {
+    let hide = actions.just_pressed(Action::HideUI);
+    if overlay_query.is_empty() {
+        if !hide || !scope_query.is_empty() {
+            return;
+        }
+        spawn_hidden_ui_overlay(&mut commands);
+        for mut visibility in ui_query.iter_mut() {
+            *visibility = Visibility::Hidden;
+        }
+    } else if hide
+        || actions.just_pressed(Action::Back)
+        || actions.just_pressed(Action::Confirm)
+        || mouse_buttons.any_just_pressed([MouseButton::Left, MouseButton::Right])
+    {
+        for entity in overlay_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        for mut visibility in ui_query.iter_mut() {
+            *visibility = Visibility::Inherited;
         }
     }
 }
 
-fn handle_restart(
-    mut restart_events: EventReader<RestartGame>,
+type BindingButtonQuery<'w, 's> =
+    Query<'w, 's, (&'static Interaction, &'static BindingButton), Changed<Interaction>>;
+
+#[allow(clippy::too_many_arguments)]
+fn settings_button_system(
+    mut open_events: EventWriter<OpenSettings>,
+    mut rebind_events: EventWriter<StartRebind>,
+    mut reset_events: EventWriter<ResetBindings>,
+    mut close_events: EventWriter<CloseSettings>,
+    mut text_speed_events: EventWriter<CycleTextSpeed>,
+    open_query: ButtonPressQuery<OpenSettingsButton>,
+    text_speed_query: ButtonPressQuery<TextSpeedButton>,
+    binding_query: BindingButtonQuery,
+    reset_query: ButtonPressQuery<ResetBindingsButton>,
+    back_query: ButtonPressQuery<SettingsBackButton>,
+) {
+    if pressed(&open_query) {
+        open_events.send(OpenSettings);
+    }
+    if pressed(&text_speed_query) {
+        text_speed_events.send(CycleTextSpeed);
+    }
+    for (interaction, button) in binding_query.iter() {
+        if *interaction == Interaction::Pressed {
+            rebind_events.send(StartRebind {
+                action: button.action,
+                device: button.device,
+            });
+            return;
+        }
+    }
+    if pressed(&reset_query) {
+        reset_events.send(ResetBindings);
+    } else if pressed(&back_query) {
+        close_events.send(CloseSettings);
+    }
+}
+
+fn handle_open_settings(
+    mut commands: Commands,
+    mut open_events: EventReader<OpenSettings>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
-    for _event in restart_events.read() {
-        next_state.set(AppState::Title);
-        info!("Restarting game");
+    if open_events.read().count() == 0 {
+        return;
     }
+    commands.insert_resource(SettingsMenu::default());
+    next_state.set(AppState::Settings);
+    info!("Opening input settings");
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn handle_close_settings(
+    mut commands: Commands,
+    mut close_events: EventReader<CloseSettings>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if close_events.read().count() == 0 {
+        return;
+    }
+    commands.remove_resource::<SettingsMenu>();
+    next_state.set(AppState::Title);
+}
 
-    #[test]
-    fn test_asset_dir_logic() {
-        // asset_dir関数のロジックをテスト（EventLoopは使わない）
-        let assets_path = asset_dir();
+/// 押したボタンの入力待ちにする（同じボタンをもう一度押すと取り消し）
+fn handle_start_rebind(
+    mut rebind_events: EventReader<StartRebind>,
+    mut menu: ResMut<SettingsMenu>,
+) {
+    for event in rebind_events.read() {
+        let target = (event.action, event.device);
+        menu.capture = (menu.capture != Some(target)).then_some(target);
+    }
+}
 
-        // パスが何らかの値を持つことを確認
-        assert!(!assets_path.to_string_lossy().is_empty());
+/// 入力待ちの間に押された最初のキー（ボタン）を割り当てて保存する。右クリックで取り消し
+///
+/// 入力待ちにしたフレームの入力（「決定」で押した Enter など）は拾わない。
+fn capture_binding(
+    mut menu: ResMut<SettingsMenu>,
+    mut bindings: ResMut<InputBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    pad_buttons: Res<ButtonInput<GamepadButton>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    save_manager: Res<SaveManager>,
+    mut notices: EventWriter<ShowNotice>,
+) {
+    let Some((action, device)) = menu.capture else {
+        return;
+    };
+    if menu.is_changed() {
+        return;
+    }
+    if mouse_buttons.just_pressed(MouseButton::Right) {
+        menu.capture = None;
+        return;
+    }
 
-        // "assets"という名前が含まれることを確認
-        assert!(assets_path.to_string_lossy().contains("assets"));
+    let input = match device {
+        BindingDevice::Keyboard => keys
+            .get_just_pressed()
+            .find(|key| BINDABLE_KEYS.contains(key))
+            .map(|key| InputKey::Key(*key)),
+        BindingDevice::Gamepad => pad_buttons
+            .get_just_pressed()
+            .find(|button| BINDABLE_PAD_BUTTONS.contains(&button.button_type))
+            .map(|button| InputKey::Pad(button.button_type)),
+    };
+    let Some(input) = input else {
+        return;
+    };
+
+    bindings.rebind(action, input);
+    menu.capture = None;
+    info!("Bound {:?} to {:?}", input, action);
+    if let Err(e) = save_manager.save_bindings(&bindings) {
+        error!("Failed to save input bindings: {:#}", e);
+        notices.send(ShowNotice("操作設定を保存できませんでした".to_string()));
+    }
+}
+
+fn handle_cycle_text_speed(
+    mut cycle_events: EventReader<CycleTextSpeed>,
+    mut settings: ResMut<PlayerSettings>,
+    save_manager: Res<SaveManager>,
+    mut notices: EventWriter<ShowNotice>,
+) {
+    if cycle_events.read().count() == 0 {
+        return;
+    }
+    settings.text_speed = settings.text_speed.next();
+    if let Err(e) = save_manager.save_settings(&settings) {
+        error!("Failed to save player settings: {:#}", e);
+        notices.send(ShowNotice("設定を保存できませんでした".to_string()));
+    }
+}
+
+fn handle_reset_bindings(
+    mut reset_events: EventReader<ResetBindings>,
+    mut menu: ResMut<SettingsMenu>,
+    mut bindings: ResMut<InputBindings>,
+    save_manager: Res<SaveManager>,
+    mut notices: EventWriter<ShowNotice>,
+) {
+    if reset_events.read().count() == 0 {
+        return;
+    }
+    *bindings = InputBindings::default();
+    menu.capture = None;
+    if let Err(e) = save_manager.save_bindings(&bindings) {
+        error!("Failed to save input bindings: {:#}", e);
+        notices.send(ShowNotice("操作設定を保存できませんでした".to_string()));
+    }
+}
+
+type RewindButtonQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Interaction, &'static RewindButton),
+    (
+        Changed<Interaction>,
+        Without<crate::ui::components::Disabled>,
+    ),
+>;
+
+fn rewind_button_system(mut rewind_events: EventWriter<RewindTo>, button_query: RewindButtonQuery) {
+    for (interaction, button) in button_query.iter() {
+        if *interaction == Interaction::Pressed {
+            rewind_events.send(RewindTo(button.0));
+            return;
+        }
+    }
+}
+
+/// 指定した分岐点まで戻して Playing で選び直させる
+///
+/// バックログは閉じ、ギャラリーからの見返し中だった場合は通常のプレイに切り替える。
+#[allow(clippy::too_many_arguments)]
+fn handle_rewind(
+    mut commands: Commands,
+    mut rewind_events: EventReader<RewindTo>,
+    mut current: ResMut<Current>,
+    mut next_state: ResMut<NextState<AppState>>,
+    state: Res<State<AppState>>,
+    scenario_data: Res<ScenarioData>,
+    backlog_query: Query<Entity, With<BacklogUI>>,
+    mut notices: EventWriter<ShowNotice>,
+) {
+    let Some(target_depth) = rewind_events.read().last().map(|e| e.0) else {
+        return;
+    };
+
+    let steps = current.depth.saturating_sub(target_depth);
+    match scenario_data.rewind(&current, steps) {
+        Ok(rewound) => {
+            info!("Rewound {} step(s) to {}", steps, rewound.id);
+            *current = rewound;
+            for entity in backlog_query.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            commands.remove_resource::<GalleryReplay>();
+            if *state.get() != AppState::Playing {
+                next_state.set(AppState::Playing);
+            }
+        }
+        Err(e) => {
+            warn!("Failed to rewind: {}", e);
+            notices.send(ShowNotice(
+                "シナリオが変わったため、この場面には戻れません".to_string(),
+            ));
+        }
+    }
+}
+
+type RouteMapNodeQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Interaction, &'static RouteMapNodeButton),
+    (
+        Changed<Interaction>,
+        Without<crate::ui::components::Disabled>,
+    ),
+>;
+
+fn route_map_button_system(
+    mut open_events: EventWriter<OpenRouteMap>,
+    mut close_events: EventWriter<CloseRouteMap>,
+    mut jump_events: EventWriter<JumpToRoute>,
+    open_query: Query<&Interaction, (Changed<Interaction>, With<OpenRouteMapButton>)>,
+    node_query: RouteMapNodeQuery,
+    back_query: Query<&Interaction, (Changed<Interaction>, With<RouteMapBackButton>)>,
+) {
+    for interaction in open_query.iter() {
+        if *interaction == Interaction::Pressed {
+            open_events.send(OpenRouteMap);
+            return;
+        }
+    }
+
+    for (interaction, button) in node_query.iter() {
+        if *interaction == Interaction::Pressed {
+            jump_events.send(JumpToRoute(button.0.clone()));
+            return;
+        }
+    }
+
+    for interaction in back_query.iter() {
+        if *interaction == Interaction::Pressed {
+            close_events.send(CloseRouteMap);
+            return;
+        }
+    }
+}
+
+fn handle_open_route_map(
+    mut commands: Commands,
+    mut open_events: EventReader<OpenRouteMap>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if open_events.read().next().is_some() {
+        open_events.clear();
+        commands.insert_resource(RouteMap {
+            back_to: *state.get(),
+        });
+        next_state.set(AppState::RouteMap);
+    }
+}
+
+fn handle_close_route_map(
+    mut close_events: EventReader<CloseRouteMap>,
+    route_map: Res<RouteMap>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if close_events.read().next().is_some() {
+        close_events.clear();
+        next_state.set(route_map.back_to);
+    }
+}
+
+/// ルートマップで選んだ場面まで開始ノードから辿り直した Current にする
+///
+/// エンディングの場面ならエンディング画面へ、それ以外は Playing で続きを選ばせる。
+fn handle_jump_to_route(
+    mut commands: Commands,
+    mut jump_events: EventReader<JumpToRoute>,
+    mut current: ResMut<Current>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut notices: EventWriter<ShowNotice>,
+    scenario_data: Res<ScenarioData>,
+) {
+    let Some(route) = jump_events.read().last().map(|e| e.0.clone()) else {
+        return;
+    };
+
+    match scenario_data.current_for_route(&route) {
+        Some(jumped) => {
+            info!("Jumped to {} ({})", route, jumped.id);
+            let is_ending = scenario_data.is_ending(&jumped);
+            *current = jumped;
+            commands.remove_resource::<GalleryReplay>();
+            next_state.set(if is_ending {
+                AppState::Ending
+            } else {
+                AppState::Playing
+            });
+        }
+        None => {
+            warn!("Cannot jump to route {}", route);
+            notices.send(ShowNotice(
+                "シナリオが変わったため、この場面には移動できません".to_string(),
+            ));
+        }
+    }
+}
+
+/// バックログを開閉する（Current には触れないので閉じればそのまま続きを遊べる）
+fn handle_backlog(
+    mut commands: Commands,
+    mut open_events: EventReader<OpenBacklog>,
+    mut close_events: EventReader<CloseBacklog>,
+    font: Res<GameFont>,
+    scenario_data: Res<ScenarioData>,
+    current: Res<Current>,
+    backlog_query: Query<Entity, With<BacklogUI>>,
+) {
+    let open = open_events.read().count() > 0;
+    let close = close_events.read().count() > 0;
+
+    if close {
+        for entity in backlog_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    } else if open && backlog_query.is_empty() {
+        spawn_backlog_ui(&mut commands, &font, &scenario_data, &current);
+    }
+}
+
+fn gallery_button_system(
+    mut open_events: EventWriter<OpenGallery>,
+    mut close_events: EventWriter<CloseGallery>,
+    mut replay_events: EventWriter<ReplayEnding>,
+    open_query: Query<&Interaction, (Changed<Interaction>, With<OpenGalleryButton>)>,
+    entry_query: GalleryEntryQuery,
+    back_query: Query<&Interaction, (Changed<Interaction>, With<GalleryBackButton>)>,
+) {
+    for interaction in open_query.iter() {
+        if *interaction == Interaction::Pressed {
+            open_events.send(OpenGallery);
+            return;
+        }
+    }
+
+    for (interaction, entry) in entry_query.iter() {
+        if *interaction == Interaction::Pressed {
+            replay_events.send(ReplayEnding(entry.0.clone()));
+            return;
+        }
+    }
+
+    for interaction in back_query.iter() {
+        if *interaction == Interaction::Pressed {
+            close_events.send(CloseGallery);
+            return;
+        }
+    }
+}
+
+fn handle_open_gallery(
+    mut open_events: EventReader<OpenGallery>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if open_events.read().next().is_some() {
+        open_events.clear();
+        next_state.set(AppState::Gallery);
+    }
+}
+
+fn handle_close_gallery(
+    mut close_events: EventReader<CloseGallery>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if close_events.read().next().is_some() {
+        close_events.clear();
+        next_state.set(AppState::Title);
+    }
+}
+
+/// ギャラリーで選んだエンディングの最後の場面をエンディング画面で見せる
+///
+/// 見返しは到達として数えず、オートセーブも更新しない（Playing を経由しないため）。
+fn handle_replay_ending(
+    mut commands: Commands,
+    mut replay_events: EventReader<ReplayEnding>,
+    mut current: ResMut<Current>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut notices: EventWriter<ShowNotice>,
+    scenario_data: Res<ScenarioData>,
+    profile: Res<Profile>,
+) {
+    let Some(route) = replay_events.read().last().map(|e| e.0.clone()) else {
+        return;
+    };
+
+    let replayed = scenario_data
+        .current_for_route(&route)
+        .filter(|replayed| scenario_data.is_ending(replayed));
+    match replayed {
+        Some(replayed) if profile.is_unlocked(&route) => {
+            *current = replayed;
+            commands.insert_resource(GalleryReplay);
+            next_state.set(AppState::Ending);
+            info!("Replaying ending: {}", route);
+        }
+        _ => {
+            warn!("Cannot replay ending {}", route);
+            notices.send(ShowNotice(
+                "このエンディングは今のシナリオでは見返せません".to_string(),
+            ));
+        }
+    }
+}
+
+/// 読み込んだセーブから再開する（エンディングで保存されていればエンディング画面へ）
+///
+/// セーブした後にシナリオが変わっていれば今のシナリオで辿り直し、辿れないところは取り消す。
+/// バックアップから復元した場合や巻き戻した場合はその旨をプレイヤーに知らせる。
+fn resume_from_save(
+    loaded: &LoadedSave,
+    current: &mut Current,
+    play_time: &mut PlayTime,
+    next_state: &mut NextState<AppState>,
+    notices: &mut EventWriter<ShowNotice>,
+    scenario_data: &ScenarioData,
+) {
+    let plan =
+        scenario_data.plan_resume(&loaded.data.to_current(), &loaded.data.scenario_fingerprint);
+    match &plan {
+        ResumePlan::Resume(_) => {}
+        ResumePlan::Replayed(reconciled) | ResumePlan::Restart(reconciled) => info!(
+            "Scenario changed since save, replayed trail ({} step(s) rolled back to {})",
+            reconciled.rolled_back, reconciled.current.id
+        ),
+    }
+
+    let messages: Vec<_> = loaded
+        .recovery
+        .as_ref()
+        .map(|recovery| recovery.message())
+        .into_iter()
+        .chain(plan.message())
+        .collect();
+    if !messages.is_empty() {
+        notices.send(ShowNotice(messages.join("\n")));
+    }
+
+    *current = plan.current().clone();
+    play_time.0 = loaded.data.play_time();
+    if scenario_data.is_ending(current) {
+        next_state.set(AppState::Ending);
+    } else {
+        next_state.set(AppState::Playing);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_begin_or_continue(
+    mut begin_new_events: EventReader<BeginNewGame>,
+    mut continue_events: EventReader<ContinueGame>,
+    mut current: ResMut<Current>,
+    mut play_time: ResMut<PlayTime>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut notices: EventWriter<ShowNotice>,
+    save_manager: Res<SaveManager>,
+    scenario_data: Res<ScenarioData>,
+) {
+    for _event in begin_new_events.read() {
+        *current = scenario_data.initial_current();
+        *play_time = PlayTime::default();
+        next_state.set(AppState::Playing);
+        info!("Starting new game");
+    }
+
+    for _event in continue_events.read() {
+        // オートセーブと手動セーブのうち、いちばん新しいものから再開する
+        if let Some((slot, loaded)) = save_manager.newest() {
+            resume_from_save(
+                &loaded,
+                &mut current,
+                &mut play_time,
+                &mut next_state,
+                &mut notices,
+                &scenario_data,
+            );
+            info!("Continuing from {:?}", slot);
+        }
+    }
+}
+
+fn handle_open_slot_menu(
+    mut commands: Commands,
+    mut open_events: EventReader<OpenSlotMenu>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(mode) = open_events.read().next().map(|e| e.0) else {
+        return;
+    };
+    open_events.clear();
+
+    commands.insert_resource(SlotMenu {
+        mode,
+        back_to: *state.get(),
+        pending_delete: None,
+    });
+    next_state.set(AppState::SlotMenu);
+    info!("Opening slot menu ({:?})", mode);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_select_slot(
+    mut select_events: EventReader<SelectSlot>,
+    mut slot_menu: ResMut<SlotMenu>,
+    mut current: ResMut<Current>,
+    mut play_time: ResMut<PlayTime>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut notices: EventWriter<ShowNotice>,
+    save_manager: Res<SaveManager>,
+    scenario_data: Res<ScenarioData>,
+) {
+    let Some(slot) = select_events.read().next().map(|e| e.0) else {
+        return;
+    };
+    select_events.clear();
+    slot_menu.pending_delete = None;
+
+    match slot_menu.mode {
+        SlotMode::Save => {
+            let save_data = SaveData::capture(&current, &scenario_data, play_time.0);
+            match save_manager.save(slot, &save_data) {
+                // 一覧を描き直して保存内容を見せる
+                Ok(()) => slot_menu.set_changed(),
+                Err(e) => {
+                    error!("Failed to save to {:?}: {:#}", slot, e);
+                    notices.send(ShowNotice(format!(
+                        "{}に保存できませんでした",
+                        slot.label()
+                    )));
+                }
+            }
+        }
+        SlotMode::Load => match save_manager.load(slot) {
+            Ok(Some(loaded)) => {
+                resume_from_save(
+                    &loaded,
+                    &mut current,
+                    &mut play_time,
+                    &mut next_state,
+                    &mut notices,
+                    &scenario_data,
+                );
+                info!("Loaded from {:?}", slot);
+            }
+            Ok(None) => warn!("{:?} is empty", slot),
+            Err(e) => {
+                error!("Failed to load {:?}: {:#}", slot, e);
+                notices.send(ShowNotice(load_error_message(slot, &e)));
+            }
+        },
+    }
+}
+
+/// 1回目の「削除」は確認待ちにし、同じスロットでもう一度押されたら消す
+fn handle_delete_slot(
+    mut delete_events: EventReader<DeleteSlot>,
+    mut slot_menu: ResMut<SlotMenu>,
+    mut notices: EventWriter<ShowNotice>,
+    save_manager: Res<SaveManager>,
+) {
+    let Some(slot) = delete_events.read().next().map(|e| e.0) else {
+        return;
+    };
+    delete_events.clear();
+
+    if slot_menu.pending_delete != Some(slot) {
+        slot_menu.pending_delete = Some(slot);
+        return;
+    }
+    slot_menu.pending_delete = None;
+    match save_manager.delete(slot) {
+        Ok(()) => notices.send(ShowNotice(format!("{}を削除しました", slot.label()))),
+        Err(e) => {
+            error!("Failed to delete {:?}: {:#}", slot, e);
+            notices.send(ShowNotice(format!(
+                "{}を削除できませんでした",
+                slot.label()
+            )))
+        }
+    };
+}
+
+fn handle_close_slot_menu(
+    mut close_events: EventReader<CloseSlotMenu>,
+    slot_menu: Res<SlotMenu>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if close_events.read().next().is_some() {
+        close_events.clear();
+        next_state.set(slot_menu.back_to);
+    }
+}
+
+fn handle_make_choice(
+    mut choice_events: EventReader<MakeChoice>,
+    mut current: ResMut<Current>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut reached_events: EventWriter<EndingReached>,
+    scenario_data: Res<ScenarioData>,
+) {
+    // 同一フレームに複数のクリックが届いても、表示中のノードに対する最初の1件だけを採用する
+    // （残りは古い選択肢ボタンからのものなので二重に進まないよう破棄する）
+    let Some(choice_index) = choice_events.read().next().map(|e| e.choice_index) else {
+        return;
+    };
+    choice_events.clear();
+
+    match scenario_data.transition(&current, choice_index) {
+        Ok(new_current) => {
+            *current = new_current;
+
+            if scenario_data.is_ending(&current) {
+                next_state.set(AppState::Ending);
+                reached_events.send(EndingReached {
+                    route_id: scenario_data
+                        .route_id_of(&current)
+                        .unwrap_or_else(|| current.id.clone()),
+                    node_id: current.id.clone(),
+                });
+                info!("Reached ending: {}", current.id);
+            } else {
+                info!("Transitioned to: {}", current.id);
+            }
+        }
+        Err(e) => {
+            error!("Failed to make choice: {}", e);
+        }
+    }
+}
+
+fn handle_restart(
+    mut commands: Commands,
+    mut restart_events: EventReader<RestartGame>,
+    mut next_state: ResMut<NextState<AppState>>,
+    replay: Option<Res<GalleryReplay>>,
+) {
+    for _event in restart_events.read() {
+        // ギャラリーから見返していた場合はギャラリーへ戻る
+        if replay.is_some() {
+            commands.remove_resource::<GalleryReplay>();
+            next_state.set(AppState::Gallery);
+        } else {
+            next_state.set(AppState::Title);
+            info!("Restarting game");
+        }
+    }
+}
+
+fn handle_retry(
+    mut commands: Commands,
+    mut retry_events: EventReader<RetryBoot>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if retry_events.read().next().is_some() {
+        retry_events.clear();
+        commands.remove_resource::<BootError>();
+        next_state.set(AppState::Boot);
+        info!("Retrying resource loading");
+    }
+}
+
+fn handle_quit(mut quit_events: EventReader<QuitGame>, mut app_exit: EventWriter<AppExit>) {
+    if quit_events.read().next().is_some() {
+        quit_events.clear();
+        info!("Quitting from error screen");
+        app_exit.send(AppExit::Success);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_dir_logic() {
+        // asset_dir関数のロジックをテスト（EventLoopは使わない）
+        let assets_path = asset_dir();
+
+        // パスが何らかの値を持つことを確認
+        assert!(!assets_path.to_string_lossy().is_empty());
+
+        // "assets"という名前が含まれることを確認
+        assert!(assets_path.to_string_lossy().contains("assets"));
+    }
+
+    fn two_level_scenario() -> ScenarioData {
+        ScenarioData::load_from_json(
+            r#"{
+              "meta": {"title": "Test", "depth": 2, "default_background": "", "rain_bgm": "", "font": ""},
+              "nodes": [
+                {"id": "R", "text": "root", "choices": [{"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}]},
+                {"id": "R1", "text": "r1", "choices": [{"label": "a", "to": "R11"}, {"label": "b", "to": "R10"}]},
+                {"id": "R0", "text": "r0", "choices": [{"label": "a", "to": "R01"}, {"label": "b", "to": "R00"}]},
+                {"id": "R11", "text": "r11", "ending": {"tag": "A"}},
+                {"id": "R10", "text": "r10", "ending": {"tag": "B"}},
+                {"id": "R01", "text": "r01", "ending": {"tag": "C"}},
+                {"id": "R00", "text": "r00", "ending": {"tag": "D"}}
+              ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_make_choice_advances_once_per_frame() {
+        let mut app = App::new();
+        app.add_event::<MakeChoice>();
+        app.add_event::<EndingReached>();
+        app.init_resource::<NextState<AppState>>();
+        app.insert_resource(two_level_scenario());
+        app.insert_resource(Current::default());
+        app.add_systems(Update, handle_make_choice);
+
+        // 同じフレームに2回クリックが届いても1段だけ進む
+        app.world_mut().send_event(MakeChoice { choice_index: 0 });
+        app.world_mut().send_event(MakeChoice { choice_index: 1 });
+        app.update();
+
+        let current = app.world().resource::<Current>();
+        assert_eq!(current.id, "R1");
+        assert_eq!(current.trail, vec!["R", "R1"]);
+
+        // 破棄したイベントが次のフレームに持ち越されないこと
+        app.update();
+        assert_eq!(app.world().resource::<Current>().id, "R1");
+    }
+
+    #[test]
+    fn test_save_then_load_slot() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = App::new();
+        app.add_event::<SelectSlot>();
+        app.add_event::<ShowNotice>();
+        app.init_resource::<NextState<AppState>>();
+        app.init_resource::<PlayTime>();
+        app.insert_resource(SaveManager::with_dir(temp_dir.path().to_path_buf()));
+        let scenario_data = two_level_scenario();
+        let r1 = scenario_data
+            .transition(&scenario_data.initial_current(), 0)
+            .unwrap();
+        app.insert_resource(r1);
+        app.insert_resource(scenario_data);
+        app.insert_resource(SlotMenu {
+            mode: SlotMode::Save,
+            back_to: AppState::Playing,
+            pending_delete: None,
+        });
+        app.add_systems(Update, handle_select_slot);
+
+        app.world_mut().send_event(SelectSlot(SaveSlot::Manual(2)));
+        app.update();
+        let saved = app.world().resource::<SaveManager>().newest().unwrap();
+        assert_eq!(saved.0, SaveSlot::Manual(2));
+        assert_eq!(saved.1.data.excerpt, "r1");
+
+        // 別の場所まで進めてからロードすると保存時点に戻る
+        *app.world_mut().resource_mut::<Current>() = Current::default();
+        app.world_mut().resource_mut::<SlotMenu>().mode = SlotMode::Load;
+        app.world_mut().send_event(SelectSlot(SaveSlot::Manual(2)));
+        app.update();
+
+        assert_eq!(app.world().resource::<Current>().id, "R1");
+        assert!(matches!(
+            *app.world().resource::<NextState<AppState>>(),
+            NextState::Pending(AppState::Playing)
+        ));
+    }
+
+    #[test]
+    fn test_load_stale_save_rolls_back() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let save_manager = SaveManager::with_dir(temp_dir.path().to_path_buf());
+        let scenario_data = two_level_scenario();
+
+        // 今のシナリオには無い R1X まで進んだセーブ
+        let mut save_data =
+            SaveData::capture(&Current::default(), &scenario_data, Default::default());
+        save_data.current = "R1X".to_string();
+        save_data.depth = 2;
+        save_data.trail = vec!["R".to_string(), "R1".to_string(), "R1X".to_string()];
+        save_manager.save(SaveSlot::Manual(1), &save_data).unwrap();
+
+        let mut app = App::new();
+        app.add_event::<SelectSlot>();
+        app.add_event::<ShowNotice>();
+        app.init_resource::<NextState<AppState>>();
+        app.init_resource::<PlayTime>();
+        app.insert_resource(save_manager);
+        app.insert_resource(scenario_data);
+        app.insert_resource(Current::default());
+        app.insert_resource(SlotMenu {
+            mode: SlotMode::Load,
+            back_to: AppState::Title,
+            pending_delete: None,
+        });
+        app.add_systems(Update, handle_select_slot);
+
+        app.world_mut().send_event(SelectSlot(SaveSlot::Manual(1)));
+        app.update();
+
+        let current = app.world().resource::<Current>();
+        assert_eq!(current.id, "R1");
+        assert_eq!(current.trail, vec!["R", "R1"]);
+
+        let notices = app.world().resource::<Events<ShowNotice>>();
+        let mut reader = notices.get_reader();
+        let message = &reader.read(notices).next().unwrap().0;
+        assert!(message.contains("1つ前"));
+    }
+
+    #[test]
+    fn test_delete_slot_asks_before_deleting() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let save_manager = SaveManager::with_dir(temp_dir.path().to_path_buf());
+        let scenario_data = two_level_scenario();
+        let save_data = SaveData::capture(&Current::default(), &scenario_data, Default::default());
+        save_manager.save(SaveSlot::Manual(1), &save_data).unwrap();
+
+        let mut app = App::new();
+        app.add_event::<DeleteSlot>();
+        app.add_event::<ShowNotice>();
+        app.insert_resource(save_manager);
+        app.insert_resource(SlotMenu {
+            mode: SlotMode::Load,
+            back_to: AppState::Title,
+            pending_delete: None,
+        });
+        app.add_systems(Update, handle_delete_slot);
+
+        // 1回目は確認待ちにするだけ
+        app.world_mut().send_event(DeleteSlot(SaveSlot::Manual(1)));
+        app.update();
+        let slot_menu = app.world().resource::<SlotMenu>();
+        assert_eq!(slot_menu.pending_delete, Some(SaveSlot::Manual(1)));
+        assert!(app.world().resource::<SaveManager>().has_save());
+
+        app.world_mut().send_event(DeleteSlot(SaveSlot::Manual(1)));
+        app.update();
+        let slot_menu = app.world().resource::<SlotMenu>();
+        assert_eq!(slot_menu.pending_delete, None);
+        assert!(!app.world().resource::<SaveManager>().has_save());
+    }
+
+    #[test]
+    fn test_gallery_replay_returns_to_gallery() {
+        let mut app = App::new();
+        app.add_event::<ReplayEnding>();
+        app.add_event::<RestartGame>();
+        app.add_event::<ShowNotice>();
+        app.init_resource::<NextState<AppState>>();
+        app.insert_resource(two_level_scenario());
+        app.insert_resource(Current::default());
+        let mut profile = Profile::default();
+        profile.record_ending("R01", "C", 1);
+        app.insert_resource(profile);
+        app.add_systems(Update, (handle_replay_ending, handle_restart).chain());
+
+        // 未到達のエンディングは見返せない
+        app.world_mut().send_event(ReplayEnding("R10".to_string()));
+        app.update();
+        assert_eq!(app.world().resource::<Current>().id, "R");
+        assert!(!app.world().contains_resource::<GalleryReplay>());
+
+        app.world_mut().send_event(ReplayEnding("R01".to_string()));
+        app.update();
+        let current = app.world().resource::<Current>();
+        assert_eq!(current.id, "R01");
+        assert_eq!(current.trail, vec!["R", "R0", "R01"]);
+        assert!(matches!(
+            *app.world().resource::<NextState<AppState>>(),
+            NextState::Pending(AppState::Ending)
+        ));
+
+        app.world_mut().send_event(RestartGame);
+        app.update();
+        assert!(!app.world().contains_resource::<GalleryReplay>());
+        assert!(matches!(
+            *app.world().resource::<NextState<AppState>>(),
+            NextState::Pending(AppState::Gallery)
+        ));
+    }
+
+    fn tap(app: &mut App, key: KeyCode) {
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.press(key);
+        app.update();
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.release(key);
+        keys.clear();
+    }
+
+    fn drain_choices(app: &mut App) -> Vec<usize> {
+        app.world_mut()
+            .resource_mut::<Events<MakeChoice>>()
+            .drain()
+            .map(|e| e.choice_index)
+            .collect()
+    }
+
+    fn confirm_count(app: &mut App) -> usize {
+        app.world_mut()
+            .query_filtered::<(), With<TitleConfirmUI>>()
+            .iter(app.world())
+            .count()
+    }
+
+    #[test]
+    fn test_keyboard_shortcuts_during_play() {
+        let mut app = App::new();
+        app.add_event::<MakeChoice>();
+        app.add_event::<ReturnToTitle>();
+        app.add_event::<RestartGame>();
+        app.add_event::<CloseSlotMenu>();
+        app.add_event::<CloseGallery>();
+        app.add_event::<CloseRouteMap>();
+        app.add_event::<CloseSettings>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.init_resource::<ButtonInput<GamepadButton>>();
+        app.init_resource::<ActionInput>();
+        app.init_resource::<InputBindings>();
+        app.init_resource::<NextState<AppState>>();
+        app.insert_resource(State::new(AppState::Playing));
+        app.insert_resource(GameFont(Handle::default()));
+        app.add_systems(
+            Update,
+            (
+                update_action_input,
+                shortcut_system,
+                title_confirm_system,
+                handle_return_to_title,
+                handle_restart,
+            )
+                .chain(),
+        );
+        app.world_mut().spawn((
+            ChoiceButton { choice_index: 0 },
+            crate::ui::components::Disabled,
+        ));
+        app.world_mut().spawn(ChoiceButton { choice_index: 1 });
+
+        // 1 は押せない選択肢なので何も起きず、2 で2つ目を選ぶ
+        tap(&mut app, KeyCode::Digit1);
+        tap(&mut app, KeyCode::Numpad2);
+        assert_eq!(drain_choices(&mut app), vec![1]);
+
+        // コントローラーでは West が1つ目、North が2つ目
+        for button_type in [GamepadButtonType::West, GamepadButtonType::North] {
+            let button = GamepadButton::new(Gamepad::new(0), button_type);
+            let mut pad_buttons = app.world_mut().resource_mut::<ButtonInput<GamepadButton>>();
+            pad_buttons.press(button);
+            app.update();
+            app.world_mut()
+                .resource_mut::<ButtonInput<GamepadButton>>()
+                .reset_all();
+        }
+        assert_eq!(drain_choices(&mut app), vec![1]);
+
+        // 割り当てを変えたら新しいキーで選ぶ
+        app.world_mut()
+            .resource_mut::<InputBindings>()
+            .rebind(Action::Choice(2), InputKey::Key(KeyCode::KeyQ));
+        tap(&mut app, KeyCode::Digit2);
+        tap(&mut app, KeyCode::KeyQ);
+        assert_eq!(drain_choices(&mut app), vec![1]);
+
+        // Esc は確認を出し、もう一度 Esc で閉じる
+        tap(&mut app, KeyCode::Escape);
+        assert_eq!(confirm_count(&mut app), 1);
+        tap(&mut app, KeyCode::Escape);
+        assert_eq!(confirm_count(&mut app), 0);
+        assert!(matches!(
+            *app.world().resource::<NextState<AppState>>(),
+            NextState::Unchanged
+        ));
+
+        // 確認中は数字キーで選ばない
+        tap(&mut app, KeyCode::Escape);
+        tap(&mut app, KeyCode::Digit2);
+        assert!(app.world().resource::<Events<MakeChoice>>().is_empty());
+
+        let yes = app
+            .world_mut()
+            .query_filtered::<Entity, With<ConfirmYesButton>>()
+            .single(app.world());
+        app.world_mut().entity_mut(yes).insert(Interaction::Pressed);
+        app.update();
+        assert!(matches!(
+            *app.world().resource::<NextState<AppState>>(),
+            NextState::Pending(AppState::Title)
+        ));
+    }
+
+    #[test]
+    fn test_rebind_captures_next_key_and_saves() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = App::new();
+        app.add_event::<StartRebind>();
+        app.add_event::<ShowNotice>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.init_resource::<ButtonInput<GamepadButton>>();
+        app.init_resource::<ButtonInput<MouseButton>>();
+        app.init_resource::<InputBindings>();
+        app.init_resource::<SettingsMenu>();
+        app.insert_resource(SaveManager::with_dir(temp_dir.path().to_path_buf()));
+        app.add_systems(Update, (handle_start_rebind, capture_binding).chain());
+
+        // 入力待ちにしたフレームの Enter は拾わない
+        app.world_mut().send_event(StartRebind {
+            action: Action::QuickSave,
+            device: BindingDevice::Keyboard,
+        });
+        tap(&mut app, KeyCode::Enter);
+        assert_eq!(
+            app.world().resource::<SettingsMenu>().capture,
+            Some((Action::QuickSave, BindingDevice::Keyboard))
+        );
+
+        tap(&mut app, KeyCode::F9);
+        assert_eq!(app.world().resource::<SettingsMenu>().capture, None);
+        let stored = app
+            .world()
+            .resource::<SaveManager>()
+            .load_bindings()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            stored.get(Action::QuickSave).unwrap().keys,
+            vec![KeyCode::F9]
+        );
+        assert_eq!(&stored, app.world().resource::<InputBindings>());
+    }
+
+    #[test]
+    fn test_backlog_after_continue() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let save_manager = SaveManager::with_dir(temp_dir.path().to_path_buf());
+        let scenario_data = two_level_scenario();
+        let r1 = scenario_data.current_for_route("R1").unwrap();
+        let save_data = SaveData::capture(&r1, &scenario_data, Default::default());
+        save_manager.save(SaveSlot::Auto, &save_data).unwrap();
+
+        // セーブから復元した trail でもバックログが作れる
+        let loaded = save_manager.load(SaveSlot::Auto).unwrap().unwrap();
+        let mut app = App::new();
+        app.add_event::<OpenBacklog>();
+        app.add_event::<CloseBacklog>();
+        app.insert_resource(GameFont(Handle::default()));
+        app.insert_resource(loaded.data.to_current());
+        app.insert_resource(scenario_data);
+        app.add_systems(Update, handle_backlog);
+
+        app.world_mut().send_event(OpenBacklog);
+        app.update();
+        let texts: Vec<String> = app
+            .world_mut()
+            .query::<&Text>()
+            .iter(app.world())
+            .map(|text| text.sections[0].value.clone())
+            .collect();
+        assert!(texts.contains(&"root".to_string()));
+        assert!(texts.contains(&"▶ a".to_string()));
+        assert!(texts.contains(&"r1".to_string()));
+
+        app.world_mut().send_event(CloseBacklog);
+        app.update();
+        let open = app
+            .world_mut()
+            .query_filtered::<(), With<BacklogUI>>()
+            .iter(app.world())
+            .count();
+        assert_eq!(open, 0);
+        assert_eq!(app.world().resource::<Current>().trail, vec!["R", "R1"]);
+    }
+
+    /// ボタンと同じく Update の中で RewindTo を送る
+    #[derive(Resource)]
+    struct PressRewind(Option<usize>);
+
+    fn press_rewind(mut press: ResMut<PressRewind>, mut rewind_events: EventWriter<RewindTo>) {
+        if let Some(depth) = press.0.take() {
+            rewind_events.send(RewindTo(depth));
+        }
+    }
+
+    #[test]
+    fn test_rewind_from_ending_updates_autosave() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = App::new();
+        app.add_plugins(bevy::state::app::StatesPlugin);
+        app.init_state::<AppState>();
+        app.add_event::<RewindTo>();
+        app.add_event::<JumpToRoute>();
+        app.add_event::<ShowNotice>();
+        app.init_resource::<PlayTime>();
+        app.insert_resource(SaveManager::with_dir(temp_dir.path().to_path_buf()));
+        let scenario_data = two_level_scenario();
+        app.insert_resource(scenario_data.current_for_route("R10").unwrap());
+        app.insert_resource(scenario_data);
+        app.insert_resource(PressRewind(None));
+        app.add_systems(
+            Update,
+            (
+                press_rewind,
+                handle_rewind,
+                auto_save_system.run_if(in_state(AppState::Playing)),
+            )
+                .chain(),
+        );
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Ending);
+        app.update();
+
+        // エンディングから最初の分岐点まで戻る（Playing に入るのは次のフレーム）
+        app.world_mut().resource_mut::<PressRewind>().0 = Some(0);
+        app.update();
+        app.update();
+
+        assert_eq!(
+            *app.world().resource::<State<AppState>>().get(),
+            AppState::Playing
+        );
+        let current = app.world().resource::<Current>();
+        assert_eq!(current.trail, vec!["R"]);
+        let saved = app
+            .world()
+            .resource::<SaveManager>()
+            .load(SaveSlot::Auto)
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.data.current, "R");
+    }
+
+    #[test]
+    fn test_jump_to_route_rebuilds_trail() {
+        let mut app = App::new();
+        app.add_event::<JumpToRoute>();
+        app.add_event::<ShowNotice>();
+        app.init_resource::<NextState<AppState>>();
+        let scenario_data = two_level_scenario();
+        app.insert_resource(scenario_data.current_for_route("R11").unwrap());
+        app.insert_resource(scenario_data);
+        app.add_systems(Update, handle_jump_to_route);
+
+        app.world_mut().send_event(JumpToRoute("R0".to_string()));
+        app.update();
+
+        let current = app.world().resource::<Current>();
+        assert_eq!(current.trail, vec!["R", "R0"]);
+        assert_eq!(current.depth, 1);
+        assert!(matches!(
+            *app.world().resource::<NextState<AppState>>(),
+            NextState::Pending(AppState::Playing)
+        ));
+
+        // 今のシナリオに無い場面には移らない
+        app.world_mut().send_event(JumpToRoute("R2".to_string()));
+        app.update();
+        assert_eq!(app.world().resource::<Current>().id, "R0");
+    }
+
+    #[test]
+    fn test_retry_clears_boot_error() {
+        let mut app = App::new();
+        app.add_event::<RetryBoot>();
+        app.init_resource::<NextState<AppState>>();
+        app.insert_resource(BootError(BootFailure::ScenarioRead("missing".to_string())));
+        app.add_systems(Update, handle_retry);
+
+        app.world_mut().send_event(RetryBoot);
+        app.update();
+
+        assert!(!app.world().contains_resource::<BootError>());
+        assert!(matches!(
+            *app.world().resource::<NextState<AppState>>(),
+            NextState::Pending(AppState::Boot)
+        ));
     }
 }
@@ -0,0 +1,685 @@
+use crate::save::SaveManager;
+use anyhow::{Context, Result};
+use bevy::input::gamepad::GamepadButtonType;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 操作設定の形式のバージョン
+pub const BINDINGS_VERSION: u8 = 1;
+
+/// 数字で直接選べる選択肢の数
+pub const CHOICE_ACTION_COUNT: u8 = 9;
+
+/// 割り当てを変えられる操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    Confirm,
+    Back,
+    /// 画面に並んだ順で n 番目（1 始まり）の選択肢を選ぶ
+    Choice(u8),
+    OpenBacklog,
+    QuickSave,
+    ToggleAuto,
+    Skip,
+    HideUI,
+}
+
+impl Action {
+    /// 設定画面に並べる順
+    pub fn all() -> Vec<Action> {
+        let mut actions = vec![Action::Confirm, Action::Back];
+        actions.extend((1..=CHOICE_ACTION_COUNT).map(Action::Choice));
+        actions.extend([
+            Action::OpenBacklog,
+            Action::QuickSave,
+            Action::ToggleAuto,
+            Action::Skip,
+            Action::HideUI,
+        ]);
+        actions
+    }
+
+    /// 設定ファイルでの名前
+    pub fn name(&self) -> String {
+        match self {
+            Action::Confirm => "confirm".to_string(),
+            Action::Back => "back".to_string(),
+            Action::Choice(n) => format!("choice{n}"),
+            Action::OpenBacklog => "open_backlog".to_string(),
+            Action::QuickSave => "quick_save".to_string(),
+            Action::ToggleAuto => "toggle_auto".to_string(),
+            Action::Skip => "skip".to_string(),
+            Action::HideUI => "hide_ui".to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::all()
+            .into_iter()
+            .find(|action| action.name() == name)
+    }
+
+    /// 設定画面に出す名前
+    pub fn label(&self) -> String {
+        match self {
+            Action::Confirm => "決定".to_string(),
+            Action::Back => "戻る".to_string(),
+            Action::Choice(n) => format!("選択肢{n}"),
+            Action::OpenBacklog => "バックログ".to_string(),
+            Action::QuickSave => "クイックセーブ".to_string(),
+            Action::ToggleAuto => "オート".to_string(),
+            Action::Skip => "スキップ".to_string(),
+            Action::HideUI => "文章を隠す".to_string(),
+        }
+    }
+}
+
+/// 割り当ての対象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindingDevice {
+    Keyboard,
+    Gamepad,
+}
+
+/// 割り当てられる入力の1つ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputKey {
+    Key(KeyCode),
+    Pad(GamepadButtonType),
+}
+
+/// 割り当てられるキー（設定ファイルにはこの Debug 表記で書く）
+pub const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadEnter,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Enter,
+    KeyCode::Space,
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::Delete,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Backslash,
+    KeyCode::Backquote,
+    KeyCode::Tab,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+];
+
+/// 割り当てられるコントローラーのボタン（South などは位置を表す）
+pub const BINDABLE_PAD_BUTTONS: &[GamepadButtonType] = &[
+    GamepadButtonType::South,
+    GamepadButtonType::East,
+    GamepadButtonType::West,
+    GamepadButtonType::North,
+    GamepadButtonType::C,
+    GamepadButtonType::Z,
+    GamepadButtonType::LeftTrigger,
+    GamepadButtonType::RightTrigger,
+    GamepadButtonType::LeftTrigger2,
+    GamepadButtonType::RightTrigger2,
+    GamepadButtonType::Select,
+    GamepadButtonType::Start,
+    GamepadButtonType::Mode,
+    GamepadButtonType::LeftThumb,
+    GamepadButtonType::RightThumb,
+    GamepadButtonType::DPadUp,
+    GamepadButtonType::DPadDown,
+    GamepadButtonType::DPadLeft,
+    GamepadButtonType::DPadRight,
+];
+
+/// フォーカスの移動に使うので割り当てると重なる入力
+pub const NAVIGATION_INPUTS: &[InputKey] = &[
+    InputKey::Key(KeyCode::ArrowUp),
+    InputKey::Key(KeyCode::ArrowDown),
+    InputKey::Key(KeyCode::ArrowLeft),
+    InputKey::Key(KeyCode::ArrowRight),
+    InputKey::Key(KeyCode::Tab),
+    InputKey::Pad(GamepadButtonType::DPadUp),
+    InputKey::Pad(GamepadButtonType::DPadDown),
+    InputKey::Pad(GamepadButtonType::DPadLeft),
+    InputKey::Pad(GamepadButtonType::DPadRight),
+];
+
+pub fn key_name(key: KeyCode) -> String {
+    format!("{key:?}")
+}
+
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|key| key_name(*key) == name)
+}
+
+pub fn pad_button_name(button: GamepadButtonType) -> String {
+    format!("{button:?}")
+}
+
+pub fn parse_pad_button(name: &str) -> Option<GamepadButtonType> {
+    BINDABLE_PAD_BUTTONS
+        .iter()
+        .copied()
+        .find(|button| pad_button_name(*button) == name)
+}
+
+/// 設定画面に出すキーの表記（"KeyA" は "A"、"Digit1" は "1"）
+pub fn key_label(key: KeyCode) -> String {
+    let name = key_name(key);
+    match name
+        .strip_prefix("Key")
+        .or_else(|| name.strip_prefix("Digit"))
+    {
+        Some(short) => short.to_string(),
+        None => name,
+    }
+}
+
+/// 1つの操作に割り当てたキーとボタン
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Binding {
+    pub keys: Vec<KeyCode>,
+    pub buttons: Vec<GamepadButtonType>,
+}
+
+impl Binding {
+    fn new(keys: &[KeyCode], buttons: &[GamepadButtonType]) -> Self {
+        Self {
+            keys: keys.to_vec(),
+            buttons: buttons.to_vec(),
+        }
+    }
+
+    fn inputs(&self) -> impl Iterator<Item = InputKey> + '_ {
+        self.keys
+            .iter()
+            .map(|key| InputKey::Key(*key))
+            .chain(self.buttons.iter().map(|button| InputKey::Pad(*button)))
+    }
+}
+
+/// 同じ入力が複数の操作（またはフォーカスの移動）に割り当てられている
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub input: InputKey,
+    pub actions: Vec<Action>,
+    /// フォーカスの移動と重なっている
+    pub navigation: bool,
+}
+
+/// 操作ごとのキーとコントローラーのボタンの割り当て（bindings.json）
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct InputBindings {
+    pub bindings: BTreeMap<Action, Binding>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use GamepadButtonType::*;
+        let choice_keys = [
+            [KeyCode::Digit1, KeyCode::Numpad1],
+            [KeyCode::Digit2, KeyCode::Numpad2],
+            [KeyCode::Digit3, KeyCode::Numpad3],
+            [KeyCode::Digit4, KeyCode::Numpad4],
+            [KeyCode::Digit5, KeyCode::Numpad5],
+            [KeyCode::Digit6, KeyCode::Numpad6],
+            [KeyCode::Digit7, KeyCode::Numpad7],
+            [KeyCode::Digit8, KeyCode::Numpad8],
+            [KeyCode::Digit9, KeyCode::Numpad9],
+        ];
+        // A・B は決定と戻るに使うので、選択肢には残りの面ボタンと LB・RB を当てる
+        let choice_buttons = [West, North, LeftTrigger, RightTrigger];
+
+        let mut bindings = BTreeMap::new();
+        bindings.insert(
+            Action::Confirm,
+            Binding::new(
+                &[KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space],
+                &[South],
+            ),
+        );
+        bindings.insert(Action::Back, Binding::new(&[KeyCode::Escape], &[East]));
+        for (i, keys) in choice_keys.iter().enumerate() {
+            let buttons = choice_buttons.get(i).map(std::slice::from_ref);
+            bindings.insert(
+                Action::Choice(i as u8 + 1),
+                Binding::new(keys, buttons.unwrap_or_default()),
+            );
+        }
+        bindings.insert(
+            Action::OpenBacklog,
+            Binding::new(&[KeyCode::KeyB], &[Select]),
+        );
+        bindings.insert(Action::QuickSave, Binding::new(&[KeyCode::F5], &[]));
+        bindings.insert(
+            Action::ToggleAuto,
+            Binding::new(&[KeyCode::KeyA], &[LeftTrigger2]),
+        );
+        bindings.insert(
+            Action::Skip,
+            Binding::new(
+                &[KeyCode::ControlLeft, KeyCode::ControlRight],
+                &[RightTrigger2],
+            ),
+        );
+        bindings.insert(
+            Action::HideUI,
+            Binding::new(&[KeyCode::KeyH], &[RightThumb]),
+        );
+        Self { bindings }
+    }
+}
+
+/// bindings.json の中身（名前で書くので手で直せる）
+#[derive(Serialize, Deserialize)]
+struct BindingsFile {
+    version: u8,
+    actions: BTreeMap<String, BindingNames>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct BindingNames {
+    #[serde(default)]
+    keys: Vec<String>,
+    #[serde(default)]
+    gamepad: Vec<String>,
+}
+
+impl InputBindings {
+    /// 設定ファイルを読む
+    ///
+    /// 書かれていない操作は初期設定のまま。知らない操作やキーの名前は読み飛ばす。
+    pub fn from_json(json: &str) -> Result<Self> {
+        let file: BindingsFile =
+            serde_json::from_str(json).context("Failed to deserialize input bindings")?;
+
+        let mut bindings = Self::default();
+        for (name, names) in file.actions {
+            let Some(action) = Action::from_name(&name) else {
+                warn!("Ignoring unknown action in bindings: {}", name);
+                continue;
+            };
+            let keys = names
+                .keys
+                .iter()
+                .filter_map(|key| {
+                    let parsed = parse_key(key);
+                    if parsed.is_none() {
+                        warn!("Ignoring unknown key '{}' for {}", key, name);
+                    }
+                    parsed
+                })
+                .collect();
+            let buttons = names
+                .gamepad
+                .iter()
+                .filter_map(|button| {
+                    let parsed = parse_pad_button(button);
+                    if parsed.is_none() {
+                        warn!("Ignoring unknown gamepad button '{}' for {}", button, name);
+                    }
+                    parsed
+                })
+                .collect();
+            bindings.bindings.insert(action, Binding { keys, buttons });
+        }
+        Ok(bindings)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        let actions = self
+            .bindings
+            .iter()
+            .map(|(action, binding)| {
+                let names = BindingNames {
+                    keys: binding.keys.iter().map(|key| key_name(*key)).collect(),
+                    gamepad: binding
+                        .buttons
+                        .iter()
+                        .map(|button| pad_button_name(*button))
+                        .collect(),
+                };
+                (action.name(), names)
+            })
+            .collect();
+        let file = BindingsFile {
+            version: BINDINGS_VERSION,
+            actions,
+        };
+        serde_json::to_string_pretty(&file).context("Failed to serialize input bindings")
+    }
+
+    pub fn get(&self, action: Action) -> Option<&Binding> {
+        self.bindings.get(&action)
+    }
+
+    /// 指定した機器の割り当てを1つの入力に置き換える
+    pub fn rebind(&mut self, action: Action, input: InputKey) {
+        let binding = self.bindings.entry(action).or_default();
+        match input {
+            InputKey::Key(key) => binding.keys = vec![key],
+            InputKey::Pad(button) => binding.buttons = vec![button],
+        }
+    }
+
+    /// その操作のどれかの入力がこのフレームで押された
+    pub fn just_pressed(
+        &self,
+        action: Action,
+        keys: &ButtonInput<KeyCode>,
+        pad_buttons: &ButtonInput<GamepadButton>,
+    ) -> bool {
+        let Some(binding) = self.get(action) else {
+            return false;
+        };
+        keys.any_just_pressed(binding.keys.iter().copied())
+            || pad_buttons
+                .get_just_pressed()
+                .any(|button| binding.buttons.contains(&button.button_type))
+    }
+
+    /// その操作のどれかの入力が押されたままになっている
+    pub fn pressed(
+        &self,
+        action: Action,
+        keys: &ButtonInput<KeyCode>,
+        pad_buttons: &ButtonInput<GamepadButton>,
+    ) -> bool {
+        let Some(binding) = self.get(action) else {
+            return false;
+        };
+        keys.any_pressed(binding.keys.iter().copied())
+            || pad_buttons
+                .get_pressed()
+                .any(|button| binding.buttons.contains(&button.button_type))
+    }
+
+    /// 重なっている割り当てを入力ごとに並べる
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut users: BTreeMap<String, (InputKey, BTreeSet<Action>)> = BTreeMap::new();
+        for (action, binding) in &self.bindings {
+            for input in binding.inputs() {
+                let key = format!("{input:?}");
+                users
+                    .entry(key)
+                    .or_insert_with(|| (input, BTreeSet::new()))
+                    .1
+                    .insert(*action);
+            }
+        }
+
+        users
+            .into_values()
+            .filter_map(|(input, actions)| {
+                let navigation = NAVIGATION_INPUTS.contains(&input);
+                (actions.len() > 1 || navigation).then(|| Conflict {
+                    input,
+                    actions: actions.into_iter().collect(),
+                    navigation,
+                })
+            })
+            .collect()
+    }
+
+    /// その操作が重なりに含まれるか（設定画面で目立たせる）
+    pub fn is_conflicting(&self, action: Action) -> bool {
+        self.conflicts()
+            .iter()
+            .any(|conflict| conflict.actions.contains(&action))
+    }
+}
+
+/// このフレームで押された操作と、押されたままの操作（割り当てを読み替えた結果）
+#[derive(Resource, Debug, Default)]
+pub struct ActionInput {
+    pressed: BTreeSet<Action>,
+    held: BTreeSet<Action>,
+}
+
+impl ActionInput {
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// 押している間ずっと続く操作（スキップ）に使う
+    pub fn pressed(&self, action: Action) -> bool {
+        self.held.contains(&action)
+    }
+
+    /// 押された選択肢の番号（1 始まり）
+    pub fn chosen(&self) -> Option<u8> {
+        self.pressed.iter().find_map(|action| match action {
+            Action::Choice(n) => Some(*n),
+            _ => None,
+        })
+    }
+}
+
+/// 操作設定の画面で割り当てを待っている操作
+#[derive(Resource, Debug, Default)]
+pub struct SettingsMenu {
+    pub capture: Option<(Action, BindingDevice)>,
+}
+
+/// 割り当ての入力待ちの間は、押したキーを操作として扱わない
+pub fn capturing_binding(menu: Option<Res<SettingsMenu>>) -> bool {
+    menu.is_some_and(|menu| menu.capture.is_some())
+}
+
+/// キーとボタンの入力を操作に読み替える
+pub fn update_action_input(
+    mut actions: ResMut<ActionInput>,
+    bindings: Res<InputBindings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    pad_buttons: Res<ButtonInput<GamepadButton>>,
+    menu: Option<Res<SettingsMenu>>,
+) {
+    actions.pressed.clear();
+    actions.held.clear();
+    if capturing_binding(menu) {
+        return;
+    }
+    for action in bindings.bindings.keys() {
+        if bindings.just_pressed(*action, &keys, &pad_buttons) {
+            actions.pressed.insert(*action);
+        }
+        if bindings.pressed(*action, &keys, &pad_buttons) {
+            actions.held.insert(*action);
+        }
+    }
+}
+
+/// 起動時に操作設定を読み込む（無い・読めない場合は初期設定）
+pub fn setup_bindings(mut commands: Commands, save_manager: Res<SaveManager>) {
+    let bindings = match save_manager.load_bindings() {
+        Ok(Some(bindings)) => {
+            info!("Input bindings loaded");
+            bindings
+        }
+        Ok(None) => InputBindings::default(),
+        Err(e) => {
+            error!(key = "bindings.load_failed", "{:#}", e);
+            InputBindings::default()
+        }
+    };
+    for conflict in bindings.conflicts() {
+        warn!("Conflicting input binding: {:?}", conflict);
+    }
+    commands.insert_resource(bindings);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_bindings_have_no_conflicts() {
+        let bindings = InputBindings::default();
+        assert_eq!(bindings.conflicts(), vec![]);
+        for action in Action::all() {
+            assert!(bindings.get(action).is_some(), "{action:?}");
+            assert_eq!(Action::from_name(&action.name()), Some(action));
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip_and_partial_file() {
+        let mut bindings = InputBindings::default();
+        bindings.rebind(Action::QuickSave, InputKey::Key(KeyCode::F9));
+        bindings.rebind(Action::Skip, InputKey::Pad(GamepadButtonType::Start));
+        let json = bindings.to_json().unwrap();
+        assert_eq!(InputBindings::from_json(&json).unwrap(), bindings);
+
+        // 書かれていない操作は初期設定、知らない名前は読み飛ばす
+        let partial = InputBindings::from_json(
+            r#"{"version": 1, "actions": {
+              "back": {"keys": ["Backspace", "NoSuchKey"]},
+              "teleport": {"keys": ["KeyT"]}
+            }}"#,
+        )
+        .unwrap();
+        let back = partial.get(Action::Back).unwrap();
+        assert_eq!(back.keys, vec![KeyCode::Backspace]);
+        assert!(back.buttons.is_empty());
+        assert_eq!(
+            partial.get(Action::Confirm),
+            InputBindings::default().get(Action::Confirm)
+        );
+    }
+
+    #[test]
+    fn test_conflicts_are_detected() {
+        let mut bindings = InputBindings::default();
+        bindings.rebind(Action::QuickSave, InputKey::Key(KeyCode::KeyB));
+        bindings.rebind(Action::HideUI, InputKey::Pad(GamepadButtonType::DPadUp));
+
+        let conflicts = bindings.conflicts();
+        assert_eq!(conflicts.len(), 2);
+        assert!(conflicts.contains(&Conflict {
+            input: InputKey::Key(KeyCode::KeyB),
+            actions: vec![Action::OpenBacklog, Action::QuickSave],
+            navigation: false,
+        }));
+        assert!(conflicts.contains(&Conflict {
+            input: InputKey::Pad(GamepadButtonType::DPadUp),
+            actions: vec![Action::HideUI],
+            navigation: true,
+        }));
+        assert!(bindings.is_conflicting(Action::OpenBacklog));
+        assert!(!bindings.is_conflicting(Action::Confirm));
+    }
+
+    #[test]
+    fn test_action_input_follows_bindings() {
+        let mut app = App::new();
+        app.init_resource::<ActionInput>();
+        app.init_resource::<InputBindings>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.init_resource::<ButtonInput<GamepadButton>>();
+        app.add_systems(Update, update_action_input);
+
+        app.world_mut()
+            .resource_mut::<InputBindings>()
+            .rebind(Action::Choice(1), InputKey::Key(KeyCode::KeyQ));
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyQ);
+        app.update();
+        assert_eq!(app.world().resource::<ActionInput>().chosen(), Some(1));
+
+        // 押したままの間は pressed だけが続く
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .clear();
+        app.update();
+        let actions = app.world().resource::<ActionInput>();
+        assert_eq!(actions.chosen(), None);
+        assert!(actions.pressed(Action::Choice(1)));
+
+        // 割り当ての入力待ちの間は読み替えない
+        app.insert_resource(SettingsMenu {
+            capture: Some((Action::Back, BindingDevice::Keyboard)),
+        });
+        app.update();
+        assert_eq!(app.world().resource::<ActionInput>().chosen(), None);
+    }
+}
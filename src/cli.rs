@@ -0,0 +1,340 @@
+use crate::app::constants::SCENARIO_PATH;
+use crate::app_impl::asset_dir;
+use crate::scenario::ScenarioData;
+use crate::validation::{ScenarioError, ValidationReport};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// 検証に問題がなかった（警告のみを含む）
+pub const EXIT_OK: i32 = 0;
+/// シナリオにエラーがあった
+pub const EXIT_INVALID: i32 = 1;
+/// 引数の誤りやファイルが読めないなど、検証まで進めなかった
+pub const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "usage: routes64 validate [--json] [--assets DIR] [SCENARIO]";
+
+/// コマンドライン引数がサブコマンドなら実行して終了コードを返す
+///
+/// サブコマンドでなければ None を返し、通常どおりゲームを起動する。
+pub fn run_from_args(args: impl IntoIterator<Item = String>) -> Option<i32> {
+    let mut args = args.into_iter();
+    match args.next().as_deref() {
+        Some("validate") => {
+            let stdout = std::io::stdout();
+            let stderr = std::io::stderr();
+            Some(run_validate(args, &mut stdout.lock(), &mut stderr.lock()))
+        }
+        _ => None,
+    }
+}
+
+struct ValidateOptions {
+    json: bool,
+    scenario: PathBuf,
+    assets: PathBuf,
+}
+
+fn parse_validate_args(args: impl Iterator<Item = String>) -> Result<ValidateOptions, String> {
+    let mut json = false;
+    let mut scenario = None;
+    let mut assets = None;
+
+    let mut args = args;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--assets" => {
+                let dir = args.next().ok_or("--assets requires a directory")?;
+                assets = Some(PathBuf::from(dir));
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {arg}")),
+            _ if scenario.is_none() => scenario = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument: {arg}")),
+        }
+    }
+
+    // アセットはシナリオと同じディレクトリを基準にするのが既定
+    let scenario = scenario.unwrap_or_else(|| asset_dir().join(SCENARIO_PATH));
+    let assets = assets.unwrap_or_else(|| match scenario.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => asset_dir(),
+    });
+
+    Ok(ValidateOptions {
+        json,
+        scenario,
+        assets,
+    })
+}
+
+/// `routes64 validate` の本体
+pub fn run_validate(
+    args: impl Iterator<Item = String>,
+    out: &mut impl Write,
+    err: &mut impl Write,
+) -> i32 {
+    let options = match parse_validate_args(args) {
+        Ok(options) => options,
+        Err(message) => {
+            let _ = writeln!(err, "error: {message}\n{USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+
+    let json_content = match std::fs::read_to_string(&options.scenario) {
+        Ok(content) => content,
+        Err(e) => {
+            let _ = writeln!(
+                err,
+                "error: failed to read {}: {e}",
+                options.scenario.display()
+            );
+            return EXIT_USAGE;
+        }
+    };
+
+    let scenario_data = match ScenarioData::parse_json(&json_content) {
+        Ok(scenario_data) => scenario_data,
+        // --json を読むツールにも、読めなかったことを同じ形で伝える
+        Err(e) if options.json => {
+            let report = ValidationReport::from_parse_error(&e);
+            return finish(out, err, &options, &report, None);
+        }
+        Err(e) => {
+            let e = ScenarioError::Parse(e);
+            let _ = writeln!(err, "error: {}: {e}", options.scenario.display());
+            return EXIT_INVALID;
+        }
+    };
+
+    // 構造に問題があっても、素材の欠けはまとめて報告する（エンディング数は構造が正しいときだけ）
+    let mut report = scenario_data.validate();
+    let ending_count = (!report.has_errors()).then(|| scenario_data.ending_count());
+    report.merge(scenario_data.validate_assets(&options.assets));
+    finish(out, err, &options, &report, ending_count)
+}
+
+/// 結果を書き出し、終了コードを返す
+fn finish(
+    out: &mut impl Write,
+    err: &mut impl Write,
+    options: &ValidateOptions,
+    report: &ValidationReport,
+    ending_count: Option<usize>,
+) -> i32 {
+    let written = if options.json {
+        write_json(out, options, report, ending_count)
+    } else {
+        write_human(out, options, report, ending_count)
+    };
+    if let Err(e) = written {
+        let _ = writeln!(err, "error: failed to write report: {e}");
+    }
+
+    if report.has_errors() {
+        EXIT_INVALID
+    } else {
+        EXIT_OK
+    }
+}
+
+fn write_human(
+    out: &mut impl Write,
+    options: &ValidateOptions,
+    report: &ValidationReport,
+    ending_count: Option<usize>,
+) -> std::io::Result<()> {
+    writeln!(out, "{}", options.scenario.display())?;
+    for issue in &report.issues {
+        writeln!(out, "  {issue}")?;
+    }
+
+    let errors = report.errors().count();
+    let warnings = report.warnings().count();
+    match ending_count {
+        Some(endings) if errors == 0 => {
+            writeln!(out, "ok: {endings} endings, {warnings} warning(s)")
+        }
+        _ => writeln!(out, "failed: {errors} error(s), {warnings} warning(s)"),
+    }
+}
+
+fn write_json(
+    out: &mut impl Write,
+    options: &ValidateOptions,
+    report: &ValidationReport,
+    ending_count: Option<usize>,
+) -> std::io::Result<()> {
+    let issues: Vec<_> = report
+        .issues
+        .iter()
+        .map(|issue| {
+            let mut value = serde_json::to_value(issue).unwrap_or_default();
+            value["message"] = issue.kind.to_string().into();
+            value
+        })
+        .collect();
+
+    let json = serde_json::json!({
+        "scenario": options.scenario.display().to_string(),
+        "valid": !report.has_errors(),
+        "errors": report.errors().count(),
+        "warnings": report.warnings().count(),
+        "endings": ending_count,
+        "issues": issues,
+    });
+    writeln!(out, "{}", serde_json::to_string_pretty(&json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn run(args: &[&str]) -> (i32, String, String) {
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+        let code = run_validate(args.iter().map(|a| a.to_string()), &mut out, &mut err);
+        (
+            code,
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(err).unwrap(),
+        )
+    }
+
+    fn write_scenario(dir: &std::path::Path, json: &str) -> String {
+        let path = dir.join("scenario.json");
+        fs::write(&path, json).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    const VALID: &str = r#"{
+      "meta": {"title": "Test", "depth": 1, "default_background": "bg.png", "rain_bgm": "", "font": ""},
+      "nodes": [
+        {"id": "R", "text": "root", "choices": [{"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}]},
+        {"id": "R1", "text": "e", "ending": {"tag": "A"}},
+        {"id": "R0", "text": "e", "bg": "missing.png", "ending": {"tag": "B"}}
+      ]
+    }"#;
+
+    #[test]
+    fn test_not_a_subcommand() {
+        assert_eq!(run_from_args(Vec::new()), None);
+        assert_eq!(run_from_args(vec!["--help-me".to_string()]), None);
+    }
+
+    #[test]
+    fn test_validate_reports_missing_asset() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("bg.png"), PNG_HEADER).unwrap();
+        let scenario = write_scenario(dir.path(), VALID);
+
+        let (code, out, _) = run(&[&scenario]);
+        assert_eq!(code, EXIT_INVALID);
+        assert!(out.contains("[error] node 'R0': bg 'missing.png' does not exist"));
+
+        fs::write(dir.path().join("missing.png"), PNG_HEADER).unwrap();
+        let (code, out, _) = run(&[&scenario]);
+        assert_eq!(code, EXIT_OK);
+        assert!(out.contains("ok: 2 endings, 0 warning(s)"));
+    }
+
+    #[test]
+    fn test_validate_json_output() {
+        let dir = tempdir().unwrap();
+        let scenario = write_scenario(
+            dir.path(),
+            r#"{
+              "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+              "nodes": [
+                {"id": "R", "text": "root", "choices": [{"label": "a", "to": "NOPE"}]}
+              ]
+            }"#,
+        );
+
+        let (code, out, _) = run(&["--json", &scenario]);
+        assert_eq!(code, EXIT_INVALID);
+
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["valid"], false);
+        assert_eq!(json["errors"], 1);
+        assert_eq!(json["endings"], serde_json::Value::Null);
+        let issue = &json["issues"][0];
+        assert_eq!(issue["severity"], "error");
+        assert_eq!(issue["kind"], "dangling_reference");
+        assert_eq!(issue["node"], "R");
+        assert_eq!(issue["choice"], 0);
+        assert_eq!(issue["to"], "NOPE");
+    }
+
+    #[test]
+    fn test_validate_reports_structure_and_assets_together() {
+        let dir = tempdir().unwrap();
+        let scenario = write_scenario(
+            dir.path(),
+            r#"{
+              "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+              "nodes": [
+                {"id": "R", "text": "root", "bg": "missing.png", "choices": [{"label": "a", "to": "NOPE"}]}
+              ]
+            }"#,
+        );
+
+        let (code, out, _) = run(&["--json", &scenario]);
+        assert_eq!(code, EXIT_INVALID);
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["errors"], 2);
+        assert_eq!(json["endings"], serde_json::Value::Null);
+        let kinds: Vec<_> = json["issues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|issue| issue["kind"].as_str().unwrap())
+            .collect();
+        assert!(kinds.contains(&"dangling_reference"), "{kinds:?}");
+        assert!(kinds.contains(&"missing_asset"), "{kinds:?}");
+
+        let (code, out, _) = run(&[&scenario]);
+        assert_eq!(code, EXIT_INVALID);
+        assert!(out.contains("NOPE"));
+        assert!(out.contains("bg 'missing.png' does not exist"));
+    }
+
+    #[test]
+    fn test_validate_usage_errors() {
+        let (code, _, err) = run(&["--bogus"]);
+        assert_eq!(code, EXIT_USAGE);
+        assert!(err.contains("unknown option"));
+
+        let (code, _, err) = run(&["/nonexistent/scenario.json"]);
+        assert_eq!(code, EXIT_USAGE);
+        assert!(err.contains("failed to read"));
+    }
+
+    #[test]
+    fn test_validate_parse_error() {
+        let dir = tempdir().unwrap();
+        let scenario = write_scenario(dir.path(), "{ not json");
+
+        let (code, _, err) = run(&[&scenario]);
+        assert_eq!(code, EXIT_INVALID);
+        assert!(err.contains("Failed to parse scenario JSON"));
+
+        let (code, out, _) = run(&["--json", &scenario]);
+        assert_eq!(code, EXIT_INVALID);
+        let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json["valid"], false);
+        assert_eq!(json["errors"], 1);
+        let issue = &json["issues"][0];
+        assert_eq!(issue["kind"], "parse_error");
+        assert_eq!(issue["line"], 1);
+        assert!(issue["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("failed to parse scenario JSON"));
+    }
+}
@@ -0,0 +1,287 @@
+use crate::app::constants::ui::FOCUS_OUTLINE_COLOR;
+use crate::bindings::{Action, ActionInput};
+use crate::ui::components::Disabled;
+use bevy::prelude::*;
+
+/// キーボードで選んでいるボタン（マウスを重ねたボタンにも追従する）
+#[derive(Resource, Default, Debug)]
+pub struct Focus(pub Option<Entity>);
+
+/// 開いている間は中のボタンにしかフォーカスが移らない領域（バックログや確認ダイアログ）
+#[derive(Component)]
+pub struct FocusScope;
+
+/// フォーカスから押したボタン（次のフレームで Interaction を戻す）
+#[derive(Component)]
+pub struct FocusPressed;
+
+/// フォーカスの操作（キーボード以外の入力もこれに変換して送る）
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigateFocus {
+    Previous,
+    Next,
+    Activate,
+}
+
+type FocusCandidateQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GlobalTransform,
+        Has<Disabled>,
+        Option<&'static InheritedVisibility>,
+    ),
+    With<Button>,
+>;
+
+/// フォーカスできるボタンを画面の上から下、左から右の順に並べる
+///
+/// Disabled のボタンと隠れているボタンは飛ばす。FocusScope が開いていればその中のボタンだけを返す。
+fn focus_order(
+    candidates: &FocusCandidateQuery,
+    scopes: &Query<(), With<FocusScope>>,
+    parents: &Query<&Parent>,
+) -> Vec<Entity> {
+    let in_scope = |entity: Entity| {
+        scopes.is_empty()
+            || parents
+                .iter_ancestors(entity)
+                .any(|ancestor| scopes.contains(ancestor))
+    };
+
+    let mut buttons: Vec<_> = candidates
+        .iter()
+        .filter(|(entity, _, disabled, visibility)| {
+            !disabled && visibility.is_none_or(|v| v.get()) && in_scope(*entity)
+        })
+        .map(|(entity, transform, _, _)| {
+            let position = transform.translation();
+            (
+                (position.y.round() as i32, position.x.round() as i32),
+                entity,
+            )
+        })
+        .collect();
+    buttons.sort();
+    buttons.into_iter().map(|(_, entity)| entity).collect()
+}
+
+/// 矢印キー・Tab でフォーカスを動かす
+pub fn keyboard_focus_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut navigate_events: EventWriter<NavigateFocus>,
+) {
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if keys.any_just_pressed([KeyCode::ArrowLeft, KeyCode::ArrowUp])
+        || (shift && keys.just_pressed(KeyCode::Tab))
+    {
+        navigate_events.send(NavigateFocus::Previous);
+    } else if keys.any_just_pressed([KeyCode::ArrowRight, KeyCode::ArrowDown, KeyCode::Tab]) {
+        navigate_events.send(NavigateFocus::Next);
+    }
+}
+
+/// 「決定」に割り当てたキー・ボタンでフォーカスしているボタンを押す
+pub fn confirm_focus_input(
+    actions: Res<ActionInput>,
+    mut navigate_events: EventWriter<NavigateFocus>,
+) {
+    if actions.just_pressed(Action::Confirm) {
+        navigate_events.send(NavigateFocus::Activate);
+    }
+}
+
+/// フォーカスを動かす・押す
+///
+/// 押すときは Interaction を Pressed にするだけなので、各画面のボタンの処理はクリックと同じものが動く。
+/// 何もフォーカスしていないときの Activate は、まず先頭のボタンにフォーカスするだけにする。
+pub fn apply_focus_navigation(
+    mut commands: Commands,
+    mut navigate_events: EventReader<NavigateFocus>,
+    mut focus: ResMut<Focus>,
+    candidates: FocusCandidateQuery,
+    scopes: Query<(), With<FocusScope>>,
+    parents: Query<&Parent>,
+    mut interactions: Query<&mut Interaction>,
+) {
+    let order = focus_order(&candidates, &scopes, &parents);
+    // 消えた・無効になったボタンからはフォーカスを外す
+    let position = focus
+        .0
+        .and_then(|focused| order.iter().position(|e| *e == focused));
+    if position.is_none() && focus.0.is_some() {
+        focus.0 = None;
+    }
+
+    for event in navigate_events.read() {
+        if order.is_empty() {
+            continue;
+        }
+        let position = focus
+            .0
+            .and_then(|focused| order.iter().position(|e| *e == focused));
+        match (event, position) {
+            (NavigateFocus::Next, Some(i)) => focus.0 = Some(order[(i + 1) % order.len()]),
+            (NavigateFocus::Previous, Some(i)) => {
+                focus.0 = Some(order[(i + order.len() - 1) % order.len()])
+            }
+            (NavigateFocus::Previous, None) => focus.0 = order.last().copied(),
+            (NavigateFocus::Next | NavigateFocus::Activate, None) => {
+                focus.0 = order.first().copied()
+            }
+            (NavigateFocus::Activate, Some(i)) => {
+                if let Ok(mut interaction) = interactions.get_mut(order[i]) {
+                    *interaction = Interaction::Pressed;
+                    commands.entity(order[i]).insert(FocusPressed);
+                }
+            }
+        }
+    }
+}
+
+/// フォーカスから押したボタンを押していない状態に戻す
+///
+/// マウスを離したときのように ui_focus_system が戻してはくれないので、次のフレームで戻す。
+pub fn release_focus_press(
+    mut commands: Commands,
+    mut pressed_query: Query<(Entity, &mut Interaction), With<FocusPressed>>,
+) {
+    for (entity, mut interaction) in pressed_query.iter_mut() {
+        interaction.set_if_neq(Interaction::None);
+        commands.entity(entity).remove::<FocusPressed>();
+    }
+}
+
+type HoveredButtonQuery<'w, 's> =
+    Query<'w, 's, (Entity, &'static Interaction), (Changed<Interaction>, With<Button>)>;
+
+/// マウスを重ねたボタンにフォーカスを合わせる（枠が2つ出ないように）
+pub fn follow_hover_focus(mut focus: ResMut<Focus>, hovered_query: HoveredButtonQuery) {
+    for (entity, interaction) in hovered_query.iter() {
+        if *interaction == Interaction::Hovered {
+            focus.0 = Some(entity);
+        }
+    }
+}
+
+/// フォーカスしているボタンに枠を付ける
+pub fn update_focus_outline(
+    mut commands: Commands,
+    focus: Res<Focus>,
+    outlined_query: Query<Entity, (With<Outline>, With<Button>)>,
+) {
+    if !focus.is_changed() {
+        return;
+    }
+    for entity in outlined_query.iter() {
+        if Some(entity) != focus.0 {
+            commands.entity(entity).remove::<Outline>();
+        }
+    }
+    if let Some(entity) = focus.0 {
+        if let Some(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.insert(Outline::new(
+                Val::Px(3.0),
+                Val::Px(2.0),
+                FOCUS_OUTLINE_COLOR,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn focus_app() -> App {
+        let mut app = App::new();
+        app.add_event::<NavigateFocus>();
+        app.init_resource::<Focus>();
+        app.add_systems(
+            Update,
+            (release_focus_press, apply_focus_navigation).chain(),
+        );
+        app
+    }
+
+    fn spawn_button(app: &mut App, x: f32, y: f32) -> Entity {
+        app.world_mut()
+            .spawn((
+                Button,
+                Interaction::None,
+                GlobalTransform::from_translation(Vec3::new(x, y, 0.0)),
+            ))
+            .id()
+    }
+
+    fn navigate(app: &mut App, event: NavigateFocus) -> Option<Entity> {
+        app.world_mut().send_event(event);
+        app.update();
+        app.world().resource::<Focus>().0
+    }
+
+    #[test]
+    fn test_focus_moves_in_reading_order_and_skips_disabled() {
+        let mut app = focus_app();
+        let bottom = spawn_button(&mut app, 0.0, 300.0);
+        let right = spawn_button(&mut app, 200.0, 100.0);
+        let disabled = spawn_button(&mut app, 100.0, 100.0);
+        app.world_mut().entity_mut(disabled).insert(Disabled);
+        let left = spawn_button(&mut app, 0.0, 100.0);
+
+        assert_eq!(navigate(&mut app, NavigateFocus::Next), Some(left));
+        assert_eq!(navigate(&mut app, NavigateFocus::Next), Some(right));
+        assert_eq!(navigate(&mut app, NavigateFocus::Next), Some(bottom));
+        assert_eq!(navigate(&mut app, NavigateFocus::Next), Some(left));
+        assert_eq!(navigate(&mut app, NavigateFocus::Previous), Some(bottom));
+
+        // フォーカス中のボタンが無効になったら外れる
+        app.world_mut().entity_mut(bottom).insert(Disabled);
+        app.update();
+        assert_eq!(app.world().resource::<Focus>().0, None);
+    }
+
+    #[test]
+    fn test_activate_presses_once() {
+        let mut app = focus_app();
+        let button = spawn_button(&mut app, 0.0, 0.0);
+
+        // 最初の Activate はフォーカスを合わせるだけ
+        assert_eq!(navigate(&mut app, NavigateFocus::Activate), Some(button));
+        assert_eq!(
+            *app.world().get::<Interaction>(button).unwrap(),
+            Interaction::None
+        );
+
+        navigate(&mut app, NavigateFocus::Activate);
+        assert_eq!(
+            *app.world().get::<Interaction>(button).unwrap(),
+            Interaction::Pressed
+        );
+        app.update();
+        assert_eq!(
+            *app.world().get::<Interaction>(button).unwrap(),
+            Interaction::None
+        );
+    }
+
+    #[test]
+    fn test_scope_traps_focus() {
+        let mut app = focus_app();
+        let outside = spawn_button(&mut app, 0.0, 0.0);
+        let inside = spawn_button(&mut app, 0.0, 100.0);
+        let scope = app
+            .world_mut()
+            .spawn(FocusScope)
+            .push_children(&[inside])
+            .id();
+
+        assert_eq!(navigate(&mut app, NavigateFocus::Next), Some(inside));
+        assert_eq!(navigate(&mut app, NavigateFocus::Next), Some(inside));
+
+        // 閉じたら外のボタンに戻れる
+        app.world_mut().entity_mut(scope).despawn_recursive();
+        assert_eq!(navigate(&mut app, NavigateFocus::Next), Some(outside));
+    }
+}
@@ -0,0 +1,280 @@
+use crate::bindings::{Action, InputBindings};
+use crate::focus::NavigateFocus;
+use crate::states::ChoiceGlyph;
+use bevy::input::gamepad::{GamepadAxisType, GamepadButtonType};
+use bevy::prelude::*;
+
+/// スティックを倒したとみなす量と、戻したとみなす量
+const STICK_PRESS: f32 = 0.6;
+const STICK_RELEASE: f32 = 0.3;
+
+/// コントローラーのボタン表記の系統
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PadLayout {
+    #[default]
+    Xbox,
+    PlayStation,
+    Nintendo,
+}
+
+impl PadLayout {
+    /// 接続されたコントローラーの名前から表記を決める（分からなければ Xbox 風）
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+        if [
+            "playstation",
+            "dualshock",
+            "dualsense",
+            "sony",
+            "ps4",
+            "ps5",
+        ]
+        .iter()
+        .any(|key| name.contains(key))
+        {
+            PadLayout::PlayStation
+        } else if ["nintendo", "switch", "pro controller", "joy-con"]
+            .iter()
+            .any(|key| name.contains(key))
+        {
+            PadLayout::Nintendo
+        } else {
+            PadLayout::Xbox
+        }
+    }
+
+    /// ボタンの表記（South などは位置なので、系統によって文字が入れ替わる）
+    pub fn glyph(self, button: GamepadButtonType) -> &'static str {
+        use GamepadButtonType::*;
+        match (self, button) {
+            (PadLayout::Xbox, South) => "A",
+            (PadLayout::Xbox, East) => "B",
+            (PadLayout::Xbox, West) => "X",
+            (PadLayout::Xbox, North) => "Y",
+            (PadLayout::Xbox, LeftTrigger) => "LB",
+            (PadLayout::Xbox, RightTrigger) => "RB",
+            (PadLayout::PlayStation, South) => "×",
+            (PadLayout::PlayStation, East) => "○",
+            (PadLayout::PlayStation, West) => "□",
+            (PadLayout::PlayStation, North) => "△",
+            (PadLayout::PlayStation, LeftTrigger) => "L1",
+            (PadLayout::PlayStation, RightTrigger) => "R1",
+            (PadLayout::Nintendo, South) => "B",
+            (PadLayout::Nintendo, East) => "A",
+            (PadLayout::Nintendo, West) => "Y",
+            (PadLayout::Nintendo, North) => "X",
+            (PadLayout::Nintendo, LeftTrigger) => "L",
+            (PadLayout::Nintendo, RightTrigger) => "R",
+            (PadLayout::Xbox, LeftTrigger2) => "LT",
+            (PadLayout::Xbox, RightTrigger2) => "RT",
+            (PadLayout::PlayStation, LeftTrigger2) => "L2",
+            (PadLayout::PlayStation, RightTrigger2) => "R2",
+            (PadLayout::Nintendo, LeftTrigger2) => "ZL",
+            (PadLayout::Nintendo, RightTrigger2) => "ZR",
+            (PadLayout::Xbox, Select) => "View",
+            (PadLayout::PlayStation, Select) => "Share",
+            (PadLayout::Nintendo, Select) => "-",
+            (PadLayout::Xbox, Start) => "Menu",
+            (PadLayout::PlayStation, Start) => "Options",
+            (PadLayout::Nintendo, Start) => "+",
+            (PadLayout::Xbox, Mode) => "Guide",
+            (PadLayout::PlayStation, Mode) => "PS",
+            (PadLayout::Nintendo, Mode) => "Home",
+            (PadLayout::PlayStation, LeftThumb) => "L3",
+            (PadLayout::PlayStation, RightThumb) => "R3",
+            (_, LeftThumb) => "LS",
+            (_, RightThumb) => "RS",
+            (_, DPadUp) => "↑",
+            (_, DPadDown) => "↓",
+            (_, DPadLeft) => "←",
+            (_, DPadRight) => "→",
+            (_, C) => "C",
+            (_, Z) => "Z",
+            _ => "?",
+        }
+    }
+}
+
+/// 最後に操作した入力機器（コントローラーなら選択肢にボタンの表記を出す）
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LastInputDevice {
+    #[default]
+    KeyboardMouse,
+    Gamepad(PadLayout),
+}
+
+/// どれかのコントローラーでそのボタンが押された
+pub fn pad_just_pressed(
+    buttons: &ButtonInput<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    buttons
+        .get_just_pressed()
+        .any(|button| button.button_type == button_type)
+}
+
+/// 左スティックの傾き（複数つながっていれば一番大きく倒しているもの）
+fn left_stick(axes: &Axis<GamepadAxis>) -> Vec2 {
+    let mut sticks = std::collections::HashMap::<Gamepad, Vec2>::new();
+    for axis in axes.devices() {
+        let value = axes.get(*axis).unwrap_or(0.0);
+        let stick = sticks.entry(axis.gamepad).or_default();
+        match axis.axis_type {
+            GamepadAxisType::LeftStickX => stick.x = value,
+            GamepadAxisType::LeftStickY => stick.y = value,
+            _ => {}
+        }
+    }
+    sticks
+        .into_values()
+        .max_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+        .unwrap_or_default()
+}
+
+/// 十字キー・左スティックでフォーカスを動かす（押すのは「決定」の割り当て）
+///
+/// スティックは倒した瞬間に1つだけ動かし、戻すまで繰り返さない。
+pub fn gamepad_focus_input(
+    buttons: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut stick_tilted: Local<bool>,
+    mut navigate_events: EventWriter<NavigateFocus>,
+) {
+    use GamepadButtonType::*;
+    if pad_just_pressed(&buttons, DPadUp) || pad_just_pressed(&buttons, DPadLeft) {
+        navigate_events.send(NavigateFocus::Previous);
+    } else if pad_just_pressed(&buttons, DPadDown) || pad_just_pressed(&buttons, DPadRight) {
+        navigate_events.send(NavigateFocus::Next);
+    }
+
+    let stick = left_stick(&axes);
+    if *stick_tilted {
+        *stick_tilted = stick.length() > STICK_RELEASE;
+    } else if stick.length() > STICK_PRESS {
+        *stick_tilted = true;
+        // 上か左なら前へ（スティックの上は +Y）
+        let previous = if stick.y.abs() > stick.x.abs() {
+            stick.y > 0.0
+        } else {
+            stick.x < 0.0
+        };
+        navigate_events.send(if previous {
+            NavigateFocus::Previous
+        } else {
+            NavigateFocus::Next
+        });
+    }
+}
+
+/// 最後に触った入力機器を覚える
+pub fn track_input_device(
+    mut device: ResMut<LastInputDevice>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut cursor_events: EventReader<CursorMoved>,
+    pad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+) {
+    let cursor_moved = cursor_events.read().count() > 0;
+    if let Some(button) = pad_buttons.get_just_pressed().next() {
+        let layout = gamepads
+            .name(button.gamepad)
+            .map(PadLayout::from_name)
+            .unwrap_or_default();
+        device.set_if_neq(LastInputDevice::Gamepad(layout));
+    } else if keys.get_just_pressed().next().is_some()
+        || mouse_buttons.get_just_pressed().next().is_some()
+        || cursor_moved
+    {
+        device.set_if_neq(LastInputDevice::KeyboardMouse);
+    }
+}
+
+/// コントローラーで遊んでいる間だけ、選択肢の横に割り当てたボタンの表記を出す
+pub fn update_choice_glyphs(
+    device: Res<LastInputDevice>,
+    bindings: Res<InputBindings>,
+    mut glyph_query: Query<(Ref<ChoiceGlyph>, &mut Text)>,
+) {
+    for (glyph, mut text) in glyph_query.iter_mut() {
+        if !device.is_changed() && !bindings.is_changed() && !glyph.is_added() {
+            continue;
+        }
+        let button = bindings
+            .get(Action::Choice(glyph.0 as u8 + 1))
+            .and_then(|binding| binding.buttons.first());
+        let label = match (*device, button) {
+            (LastInputDevice::Gamepad(layout), Some(button)) => {
+                format!("{} ", layout.glyph(*button))
+            }
+            _ => String::new(),
+        };
+        text.sections[0].value = label;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::BINDABLE_PAD_BUTTONS;
+
+    #[test]
+    fn test_layout_from_name() {
+        assert_eq!(
+            PadLayout::from_name("Sony Interactive Entertainment DualSense Wireless Controller"),
+            PadLayout::PlayStation
+        );
+        assert_eq!(
+            PadLayout::from_name("Nintendo Switch Pro Controller"),
+            PadLayout::Nintendo
+        );
+        assert_eq!(
+            PadLayout::from_name("Xbox Wireless Controller"),
+            PadLayout::Xbox
+        );
+        assert_eq!(
+            PadLayout::from_name("Generic USB Joystick"),
+            PadLayout::Xbox
+        );
+    }
+
+    #[test]
+    fn test_glyphs_follow_button_position() {
+        // 右の面ボタンは Xbox では B、任天堂では A
+        assert_eq!(PadLayout::Xbox.glyph(GamepadButtonType::East), "B");
+        assert_eq!(PadLayout::Nintendo.glyph(GamepadButtonType::East), "A");
+        assert_eq!(PadLayout::PlayStation.glyph(GamepadButtonType::East), "○");
+        for layout in [PadLayout::Xbox, PadLayout::PlayStation, PadLayout::Nintendo] {
+            for button in BINDABLE_PAD_BUTTONS {
+                assert_ne!(layout.glyph(*button), "?");
+            }
+        }
+    }
+
+    #[test]
+    fn test_stick_moves_focus_once_per_tilt() {
+        let mut app = App::new();
+        app.add_event::<NavigateFocus>();
+        app.init_resource::<ButtonInput<GamepadButton>>();
+        app.init_resource::<Axis<GamepadAxis>>();
+        app.add_systems(Update, gamepad_focus_input);
+
+        let stick_y = GamepadAxis::new(Gamepad::new(0), GamepadAxisType::LeftStickY);
+        let tilt = |app: &mut App, value: f32| {
+            app.world_mut()
+                .resource_mut::<Axis<GamepadAxis>>()
+                .set(stick_y, value);
+            app.update();
+            app.world_mut()
+                .resource_mut::<Events<NavigateFocus>>()
+                .drain()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(tilt(&mut app, -0.9), vec![NavigateFocus::Next]);
+        // 倒したままでは繰り返さない
+        assert_eq!(tilt(&mut app, -0.9), vec![]);
+        assert_eq!(tilt(&mut app, 0.0), vec![]);
+        assert_eq!(tilt(&mut app, 0.9), vec![NavigateFocus::Previous]);
+    }
+}
@@ -3,14 +3,26 @@
 pub mod app;
 pub mod app_impl;
 pub mod audio;
+pub mod bindings;
+pub mod cli;
+pub mod focus;
+pub mod gamepad;
+pub mod profile;
+pub mod route_id;
 pub mod save;
+pub mod save_migration;
 pub mod scenario;
+pub mod scenario_asset;
+pub mod settings;
 pub mod states;
+pub mod typewriter;
 pub mod ui;
 pub mod ui_impl;
+pub mod validation;
 
 // よく使用される型を再エクスポート
 pub use app_impl::create_app;
+pub use profile::Profile;
 pub use save::SaveManager;
 pub use scenario::{Choice, Current, Ending, Node, ScenarioData};
 pub use states::{AppState, BeginNewGame, ContinueGame, MakeChoice, RestartGame};
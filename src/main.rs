@@ -1,14 +1,30 @@
 mod app;
 mod app_impl;
 mod audio;
+mod bindings;
+mod cli;
+mod focus;
+mod gamepad;
+mod profile;
+mod route_id;
 mod save;
+mod save_migration;
 mod scenario;
+mod scenario_asset;
+mod settings;
 mod states;
+mod typewriter;
 mod ui;
 mod ui_impl;
+mod validation;
 
 use app_impl::create_app;
 
 fn main() {
+    // `routes64 validate ...` はウィンドウを開かずにシナリオを検証して終了する
+    if let Some(code) = cli::run_from_args(std::env::args().skip(1)) {
+        std::process::exit(code);
+    }
+
     create_app().run();
 }
@@ -0,0 +1,311 @@
+use crate::route_id;
+use crate::save::{unix_now, SaveManager};
+use crate::save_migration::SaveFormatError;
+use crate::scenario::{Current, ScenarioData};
+use crate::states::EndingReached;
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 収集記録の形式のバージョン
+pub const PROFILE_VERSION: u8 = 1;
+
+/// 1つのエンディングの到達記録
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EndingRecord {
+    /// 到達したときの Ending::tag（シナリオが変わっても一覧に出せるよう控えておく）
+    pub tag: String,
+    /// 初めて到達した時刻（UNIX 時間の秒）
+    pub first_reached_at: i64,
+    pub reach_count: u32,
+}
+
+/// プレイをまたいで残すエンディングの収集記録（profile.json）
+///
+/// キーはエンディングのルートID（ScenarioData::ending_ids と同じもの）。セーブスロットとは別のファイルなので、
+/// スロットの削除や新しいゲームの開始では消えない。
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub version: u8,
+    /// 最後に記録したときのシナリオの識別子（ScenarioData::fingerprint）
+    pub scenario_fingerprint: String,
+    pub endings: BTreeMap<String, EndingRecord>,
+    /// 訪れたことのある場面のルートID（ルートマップで使う。古い記録には無い）
+    #[serde(default)]
+    pub visited: BTreeSet<String>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            version: PROFILE_VERSION,
+            scenario_fingerprint: String::new(),
+            endings: BTreeMap::new(),
+            visited: BTreeSet::new(),
+        }
+    }
+}
+
+impl Profile {
+    /// 新しいバージョンのゲームで書かれた記録は、読み違えないよう形式を見る前に断る
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).context("Failed to deserialize profile")?;
+        if let Some(found) = value
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .filter(|version| *version > PROFILE_VERSION as u64)
+        {
+            return Err(SaveFormatError::UnsupportedVersion {
+                found,
+                supported: PROFILE_VERSION,
+            }
+            .into());
+        }
+        serde_json::from_value(value).context("Failed to deserialize profile")
+    }
+
+    /// エンディングへの到達を記録する（初めての到達なら true）
+    pub fn record_ending(&mut self, route_id: &str, tag: &str, reached_at: i64) -> bool {
+        match self.endings.get_mut(route_id) {
+            Some(record) => {
+                record.reach_count += 1;
+                record.tag = tag.to_string();
+                false
+            }
+            None => {
+                self.endings.insert(
+                    route_id.to_string(),
+                    EndingRecord {
+                        tag: tag.to_string(),
+                        first_reached_at: reached_at,
+                        reach_count: 1,
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    pub fn get(&self, route_id: &str) -> Option<&EndingRecord> {
+        self.endings.get(route_id)
+    }
+
+    pub fn is_unlocked(&self, route_id: &str) -> bool {
+        self.endings.contains_key(route_id)
+    }
+
+    /// その場面を訪れたことがあるか
+    ///
+    /// 到達済みのエンディングへの途中の場面も訪れたものとみなす（visited を記録する前の記録のため）。
+    pub fn is_visited(&self, route_id: &str) -> bool {
+        self.visited.contains(route_id)
+            || self
+                .endings
+                .keys()
+                .any(|ending| ending.starts_with(route_id))
+    }
+
+    /// 訪れた場面を記録する（新しく記録したら true）
+    pub fn record_visit(&mut self, route_id: &str) -> bool {
+        !self.is_visited(route_id) && self.visited.insert(route_id.to_string())
+    }
+
+    /// 今のシナリオのエンディングのうち到達済みの数と全体の数
+    ///
+    /// シナリオの更新で無くなったエンディングの記録は数えない。
+    pub fn progress(&self, scenario_data: &ScenarioData) -> (usize, usize) {
+        let unlocked = self
+            .endings
+            .keys()
+            .filter(|id| scenario_data.is_ending_route(id))
+            .count();
+        (unlocked, scenario_data.ending_count())
+    }
+
+    /// タイトルに出す収集率（%、切り捨て）
+    pub fn completion_percent(&self, scenario_data: &ScenarioData) -> usize {
+        match self.progress(scenario_data) {
+            (_, 0) => 0,
+            (unlocked, total) => unlocked * 100 / total,
+        }
+    }
+}
+
+/// 起動時に収集記録を読み込む（無い・読めない場合は空から始める）
+pub fn setup_profile(mut commands: Commands, save_manager: Res<SaveManager>) {
+    let profile = match save_manager.load_profile() {
+        Ok(Some(profile)) => {
+            info!("Profile loaded ({} endings reached)", profile.endings.len());
+            profile
+        }
+        Ok(None) => Profile::default(),
+        Err(e) => {
+            error!(key = "profile.load_failed", "{:#}", e);
+            Profile::default()
+        }
+    };
+    commands.insert_resource(profile);
+}
+
+/// 到達したエンディングを記録してすぐに書き出す
+pub fn record_reached_endings(
+    mut reached_events: EventReader<EndingReached>,
+    mut profile: ResMut<Profile>,
+    save_manager: Res<SaveManager>,
+    scenario_data: Res<ScenarioData>,
+) {
+    let mut recorded = false;
+    for reached in reached_events.read() {
+        let Some(ending) = scenario_data
+            .get_node(&reached.node_id)
+            .and_then(|node| node.ending.as_ref())
+        else {
+            continue;
+        };
+        if profile.record_ending(&reached.route_id, &ending.tag, unix_now()) {
+            info!(
+                "First time reaching ending: {} ({})",
+                reached.route_id, ending.tag
+            );
+        }
+        recorded = true;
+    }
+    if !recorded {
+        return;
+    }
+
+    profile.scenario_fingerprint = scenario_data.fingerprint.clone();
+    if let Err(e) = save_manager.save_profile(&profile) {
+        error!("Failed to save profile: {:#}", e);
+    }
+}
+
+/// Current が変わるたびに、今いる場面までの経路を訪れた場面として記録する
+///
+/// セーブから再開した場合も trail 全体を記録するので、ルートマップに反映される。
+pub fn record_visited_routes(
+    current: Res<Current>,
+    mut profile: ResMut<Profile>,
+    save_manager: Res<SaveManager>,
+    scenario_data: Res<ScenarioData>,
+) {
+    if !current.is_changed() {
+        return;
+    }
+    let Some(route) = scenario_data.route_id_of(&current) else {
+        return;
+    };
+
+    // "R10" なら "R", "R1", "R10" を記録する
+    let mut recorded = false;
+    for end in route_id::ROOT.len()..=route.len() {
+        recorded |= profile.record_visit(&route[..end]);
+    }
+    if recorded {
+        if let Err(e) = save_manager.save_profile(&profile) {
+            error!("Failed to save profile: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::save::{SaveData, SaveSlot};
+    use tempfile::tempdir;
+
+    fn test_scenario() -> ScenarioData {
+        ScenarioData::load_from_json(
+            r#"{
+              "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+              "nodes": [
+                {"id": "R", "text": "root", "choices": [{"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}]},
+                {"id": "R1", "text": "e", "ending": {"tag": "A"}},
+                {"id": "R0", "text": "e", "ending": {"tag": "B"}}
+              ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_record_ending_counts_reaches() {
+        let mut profile = Profile::default();
+        assert!(profile.record_ending("R1", "A", 100));
+        assert!(!profile.record_ending("R1", "A", 200));
+
+        let record = profile.get("R1").unwrap();
+        assert_eq!(record.reach_count, 2);
+        assert_eq!(record.first_reached_at, 100);
+        assert!(!profile.is_unlocked("R0"));
+    }
+
+    #[test]
+    fn test_progress_ignores_removed_endings() {
+        let mut profile = Profile::default();
+        profile.record_ending("R1", "A", 1);
+        profile.record_ending("R1X", "消えた", 1);
+        assert_eq!(profile.progress(&test_scenario()), (1, 2));
+        assert_eq!(profile.completion_percent(&test_scenario()), 50);
+    }
+
+    #[test]
+    fn test_visited_includes_paths_to_endings() {
+        let mut profile = Profile::default();
+        profile.record_ending("R10", "B", 1);
+        assert!(profile.is_visited("R"));
+        assert!(profile.is_visited("R1"));
+        assert!(!profile.is_visited("R0"));
+
+        assert!(profile.record_visit("R0"));
+        assert!(!profile.record_visit("R0"));
+        assert!(!profile.record_visit("R1"));
+        assert!(profile.is_visited("R0"));
+    }
+
+    #[test]
+    fn test_old_profile_without_visited() {
+        let profile = Profile::from_json(
+            r#"{"version": 1, "scenario_fingerprint": "", "endings": {
+              "R1": {"tag": "A", "first_reached_at": 1, "reach_count": 1}
+            }}"#,
+        )
+        .unwrap();
+        assert!(profile.visited.is_empty());
+        assert!(profile.is_visited("R1"));
+    }
+
+    #[test]
+    fn test_reached_ending_is_persisted() {
+        let temp_dir = tempdir().unwrap();
+        let mut app = App::new();
+        app.add_event::<EndingReached>();
+        app.insert_resource(SaveManager::with_dir(temp_dir.path().to_path_buf()));
+        app.insert_resource(test_scenario());
+        app.add_systems(Startup, setup_profile);
+        app.add_systems(Update, record_reached_endings);
+
+        app.world_mut().send_event(EndingReached {
+            route_id: "R0".to_string(),
+            node_id: "R0".to_string(),
+        });
+        app.update();
+
+        let save_manager = app.world().resource::<SaveManager>();
+        let stored = save_manager.load_profile().unwrap().unwrap();
+        assert_eq!(stored.get("R0").unwrap().tag, "B");
+        assert_eq!(stored.scenario_fingerprint, test_scenario().fingerprint);
+
+        // スロットを消しても収集記録は残る
+        let data = SaveData::capture(&Current::default(), &test_scenario(), Default::default());
+        save_manager.save(SaveSlot::Auto, &data).unwrap();
+        save_manager.delete(SaveSlot::Auto).unwrap();
+        assert!(save_manager
+            .load_profile()
+            .unwrap()
+            .unwrap()
+            .is_unlocked("R0"));
+    }
+}
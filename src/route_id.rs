@@ -0,0 +1,78 @@
+/// ルートIDのエンコード
+///
+/// ルートIDは "R" の後に、各階層で選んだ枝の番号を1文字ずつ並べたもの。
+/// 枝番号は 0-9, a-z の36進数1桁で表すので、1ノードあたり最大36分岐まで扱える。
+/// 分岐数はノードごとに異なってよく、桁ごとに基数が変わる（混合基数）。
+///
+/// 枝番号は「分岐数 - 1 - 選択肢の添字」とする。二択なら先頭の選択肢が "1"、
+/// 次が "0" となり、従来の二進数のIDと一致する。
+pub const ROOT: &str = "R";
+
+/// 1ノードあたりの最大分岐数
+pub const MAX_BRANCHING: usize = 36;
+
+/// ルートIDを枝番号の列に分解する（ルートIDの形式でなければ None）
+pub fn digits(id: &str) -> Option<Vec<usize>> {
+    let rest = id.strip_prefix(ROOT)?;
+    rest.chars()
+        .map(|c| match c {
+            '0'..='9' | 'a'..='z' => c.to_digit(MAX_BRANCHING as u32).map(|d| d as usize),
+            _ => None,
+        })
+        .collect()
+}
+
+/// ルートIDの階層（"R" は 0）
+pub fn depth(id: &str) -> Option<usize> {
+    digits(id).map(|d| d.len())
+}
+
+/// 選択肢の添字から枝番号を求める
+pub fn branch_for_choice(choice_index: usize, branching: usize) -> usize {
+    branching - 1 - choice_index
+}
+
+/// 親のルートIDに枝番号を1桁足した子のルートID
+pub fn child(parent: &str, branch: usize) -> String {
+    let digit = std::char::from_digit(branch as u32, MAX_BRANCHING as u32)
+        .unwrap_or_else(|| panic!("branch {branch} exceeds {MAX_BRANCHING}-way limit"));
+    format!("{parent}{digit}")
+}
+
+/// 親ノードの choice_index 番目の選択肢が指すべき子のルートID
+pub fn child_for_choice(parent: &str, choice_index: usize, branching: usize) -> String {
+    child(parent, branch_for_choice(choice_index, branching))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digits_and_depth() {
+        assert_eq!(digits("R"), Some(vec![]));
+        assert_eq!(digits("R101"), Some(vec![1, 0, 1]));
+        assert_eq!(digits("R2a0"), Some(vec![2, 10, 0]));
+        assert_eq!(depth("R101011"), Some(6));
+
+        // ルートIDの形式でないもの
+        assert_eq!(digits("START"), None);
+        assert_eq!(digits("R1A"), None);
+        assert_eq!(depth(""), None);
+    }
+
+    #[test]
+    fn test_binary_choices_keep_legacy_ids() {
+        assert_eq!(child_for_choice("R", 0, 2), "R1");
+        assert_eq!(child_for_choice("R", 1, 2), "R0");
+        assert_eq!(child_for_choice("R10", 0, 2), "R101");
+    }
+
+    #[test]
+    fn test_mixed_radix_children() {
+        assert_eq!(child_for_choice("R", 0, 3), "R2");
+        assert_eq!(child_for_choice("R", 2, 3), "R0");
+        assert_eq!(child_for_choice("R2", 0, 4), "R23");
+        assert_eq!(child_for_choice("R", 0, 12), "Rb");
+    }
+}
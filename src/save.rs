@@ -1,23 +1,215 @@
-use crate::scenario::Current;
-use anyhow::{Context, Result};
+use crate::bindings::InputBindings;
+use crate::profile::Profile;
+use crate::save_migration::{upgrade, SaveFormatError, SAVE_VERSION};
+use crate::scenario::{Current, ScenarioData, Variables};
+use crate::settings::PlayerSettings;
+use crate::states::{JumpToRoute, RewindTo};
+use anyhow::{bail, Context, Result};
 use bevy::prelude::*;
+use chrono::{Local, TimeZone};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+/// 手動セーブ用のスロット数（オートセーブは別枠）
+pub const MANUAL_SLOT_COUNT: usize = 6;
+
+/// スロットごとに残すバックアップの世代数
+pub const BACKUP_GENERATIONS: usize = 3;
+
+/// スロット一覧に表示する本文の最大文字数
+const EXCERPT_CHARS: usize = 24;
+
+/// 現在の形式（v{SAVE_VERSION}）のセーブデータ
+///
+/// 古い形式のファイルは save_migration::upgrade で段階的にこの形にしてから読む。
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SaveData {
     pub version: u8,
     pub current: String,
     pub depth: usize,
     pub trail: Vec<String>,
+    pub vars: Variables,
+    /// セーブした時刻（UNIX 時間の秒）
+    pub saved_at: i64,
+    /// 累計プレイ時間（秒）
+    pub play_time_secs: u64,
+    /// セーブ時点のノード本文の冒頭
+    pub excerpt: String,
+    pub scenario_title: String,
+    /// セーブしたときのシナリオの識別子（ScenarioData::fingerprint、分からなければ空）
+    pub scenario_fingerprint: String,
+    /// 今のノードで読んでいたページ（0 始まり）
+    pub page: usize,
+    /// 各階層で選んだ選択肢の添字（Current::choices、記録の無い古いセーブでは空）
+    pub choices: Vec<usize>,
+}
+
+impl SaveData {
+    /// 現在の進行状況からセーブデータを作る
+    pub fn capture(current: &Current, scenario_data: &ScenarioData, play_time: Duration) -> Self {
+        // 冒頭は読んでいたページのもの
+        let text = scenario_data
+            .get_node(&current.id)
+            .and_then(|node| node.pages().get(current.page).cloned())
+            .unwrap_or_default();
+
+        Self {
+            version: SAVE_VERSION,
+            current: current.id.clone(),
+            depth: current.depth,
+            trail: current.trail.clone(),
+            vars: current.vars.clone(),
+            saved_at: unix_now(),
+            play_time_secs: play_time.as_secs(),
+            excerpt: excerpt(&text),
+            scenario_title: scenario_data.scenario.meta.title.clone(),
+            scenario_fingerprint: scenario_data.fingerprint.clone(),
+            page: current.page,
+            choices: current.choices.clone(),
+        }
+    }
+
+    pub fn to_current(&self) -> Current {
+        Current {
+            id: self.current.clone(),
+            depth: self.depth,
+            trail: self.trail.clone(),
+            vars: self.vars.clone(),
+            page: self.page,
+            choices: self.choices.clone(),
+        }
+    }
+
+    /// 途中で切れたり書き換わったりしていないかの簡易チェック
+    pub fn is_consistent(&self) -> bool {
+        self.trail.last() == Some(&self.current)
+            && self.trail.len() == self.depth + 1
+            && (self.choices.is_empty() || self.choices.len() == self.depth)
+    }
+
+    pub fn play_time(&self) -> Duration {
+        Duration::from_secs(self.play_time_secs)
+    }
+
+    /// セーブ時刻をローカル時刻で表示する（時刻のない古いセーブは "----"）
+    pub fn saved_at_label(&self) -> String {
+        format_timestamp(self.saved_at)
+    }
+}
+
+/// 現在時刻（UNIX 時間の秒）
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// UNIX 時間の秒をローカル時刻で表示する（0 は記録なしとして "----"）
+pub fn format_timestamp(secs: i64) -> String {
+    match Local.timestamp_opt(secs, 0).single() {
+        Some(time) if secs > 0 => time.format("%Y/%m/%d %H:%M").to_string(),
+        _ => "----/--/-- --:--".to_string(),
+    }
+}
+
+/// 改行を詰めて先頭の数文字だけを残す
+fn excerpt(text: &str) -> String {
+    let flat: String = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() <= EXCERPT_CHARS {
+        return flat;
+    }
+    let mut cut: String = flat.chars().take(EXCERPT_CHARS).collect();
+    cut.push('…');
+    cut
+}
+
+/// プレイ時間を "H:MM:SS" で表示する
+pub fn format_play_time(play_time: Duration) -> String {
+    let secs = play_time.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// セーブの保存先
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SaveSlot {
+    /// 選択のたびに上書きされる
+    Auto,
+    /// プレイヤーが選んで保存する（1 始まり）
+    Manual(usize),
+    /// クイックセーブの操作で上書きされる
+    Quick,
+}
+
+impl SaveSlot {
+    /// オートセーブを先頭に、クイックセーブを末尾にして全スロットを並べる
+    pub fn all() -> impl Iterator<Item = SaveSlot> {
+        std::iter::once(SaveSlot::Auto)
+            .chain((1..=MANUAL_SLOT_COUNT).map(SaveSlot::Manual))
+            .chain(std::iter::once(SaveSlot::Quick))
+    }
+
+    /// スロット選択画面から直接書き込めるか
+    pub fn is_manual(&self) -> bool {
+        matches!(self, SaveSlot::Manual(_))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            SaveSlot::Auto => "オートセーブ".to_string(),
+            SaveSlot::Manual(n) => format!("スロット{n}"),
+            SaveSlot::Quick => "クイックセーブ".to_string(),
+        }
+    }
+
+    fn file_name(&self) -> String {
+        match self {
+            // 単一スロット時代の save.json をそのままオートセーブとして引き継ぐ
+            SaveSlot::Auto => "save.json".to_string(),
+            SaveSlot::Manual(n) => format!("slot{n}.json"),
+            SaveSlot::Quick => "quick.json".to_string(),
+        }
+    }
+}
+
+/// 読み込んだセーブと、バックアップから復元したかどうか
+#[derive(Debug, Clone)]
+pub struct LoadedSave {
+    pub data: SaveData,
+    pub recovery: Option<Recovery>,
+}
+
+/// 最新のファイルが読めず、バックアップから復元したことの記録
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovery {
+    pub slot: SaveSlot,
+    /// 何世代前のバックアップを使ったか（1 が直前）
+    pub generation: usize,
+}
+
+impl Recovery {
+    /// プレイヤーに伝える文言
+    pub fn message(&self) -> String {
+        format!(
+            "{}のセーブデータが読み込めなかったため、{}世代前のバックアップから復元しました",
+            self.slot.label(),
+            self.generation
+        )
+    }
 }
 
 #[derive(Resource)]
 pub struct SaveManager {
-    save_path: PathBuf,
+    save_dir: PathBuf,
     disabled: bool,
+    /// 読み込み・一覧で、最新のファイルが新しいゲームで作られたものだと分かったスロット
+    newer_slots: Mutex<HashSet<SaveSlot>>,
 }
 
 impl SaveManager {
@@ -29,113 +221,481 @@ impl SaveManager {
         fs::create_dir_all(data_dir)
             .with_context(|| format!("Failed to create data directory: {data_dir:?}"))?;
 
-        let save_path = data_dir.join("save.json");
+        Ok(Self::with_dir(data_dir.to_path_buf()))
+    }
 
-        Ok(Self {
-            save_path,
+    /// 指定したディレクトリにスロットを置く
+    pub fn with_dir(save_dir: PathBuf) -> Self {
+        Self {
+            save_dir,
             disabled: false,
-        })
+            newer_slots: Mutex::default(),
+        }
     }
 
     /// セーブ機能が無効化されたSaveManagerを作成
     pub fn new_disabled() -> Self {
         Self {
-            save_path: PathBuf::new(), // 無効なパス
+            save_dir: PathBuf::new(), // 無効なパス
             disabled: true,
+            newer_slots: Mutex::default(),
+        }
+    }
+
+    fn slot_path(&self, slot: SaveSlot) -> PathBuf {
+        self.save_dir.join(slot.file_name())
+    }
+
+    /// generation 世代前のバックアップ（0 は最新のファイルそのもの）
+    fn generation_path(&self, slot: SaveSlot, generation: usize) -> PathBuf {
+        if generation == 0 {
+            return self.slot_path(slot);
         }
+        self.save_dir
+            .join(format!("{}.bak{generation}", slot.file_name()))
     }
 
-    pub fn save(&self, current: &Current) -> Result<()> {
+    /// 一時ファイルに書いて fsync してから差し替える
+    ///
+    /// 書き込み途中で落ちても、元のファイルかバックアップのどちらかは必ず無傷で残る。
+    pub fn save(&self, slot: SaveSlot, save_data: &SaveData) -> Result<()> {
+        self.write_slot(slot, save_data, true)
+    }
+
+    /// バックアップをずらさずに最新のファイルだけを差し替える
+    ///
+    /// ページを送っただけのような同じ場面での保存に使い、バックアップの世代を同じ場面で埋めないようにする。
+    pub fn update(&self, slot: SaveSlot, save_data: &SaveData) -> Result<()> {
+        self.write_slot(slot, save_data, false)
+    }
+
+    fn write_slot(&self, slot: SaveSlot, save_data: &SaveData, rotate: bool) -> Result<()> {
         if self.disabled {
             debug!("Save disabled, skipping save operation");
             return Ok(());
         }
 
-        let save_data = SaveData {
-            version: 1,
-            current: current.id.clone(),
-            depth: current.depth,
-            trail: current.trail.clone(),
-        };
-
+        let save_path = self.slot_path(slot);
         let json =
-            serde_json::to_string_pretty(&save_data).context("Failed to serialize save data")?;
+            serde_json::to_string_pretty(save_data).context("Failed to serialize save data")?;
+        self.set_aside_newer_save(slot)?;
 
-        fs::write(&self.save_path, json).with_context(|| {
-            format!(
-                "Failed to write save file: {save_path:?}",
-                save_path = self.save_path
-            )
-        })?;
+        let tmp_path = save_path.with_extension("json.tmp");
+        write_synced(&tmp_path, json.as_bytes())
+            .with_context(|| format!("Failed to write save file: {tmp_path:?}"))?;
 
-        info!("Game saved to {:?}", self.save_path);
+        if rotate {
+            self.rotate_backups(slot)?;
+        }
+        fs::rename(&tmp_path, &save_path)
+            .with_context(|| format!("Failed to replace save file: {save_path:?}"))?;
+        sync_dir(&self.save_dir);
+
+        info!("Game saved to {:?}", save_path);
         Ok(())
     }
 
-    pub fn load(&self) -> Result<Option<Current>> {
-        if self.disabled {
-            debug!("Save disabled, no save data available");
-            return Ok(None);
+    /// 最新のファイルが新しいゲームで作られたものなら、バックアップとして押し出されて消えないよう .newer に退避する
+    ///
+    /// 書き込みのたびに読み直さないよう、読み込み・一覧で見つけたスロットだけを対象にする。
+    /// すでに退避したファイルがあればそれも消さないよう、書き込みをやめてエラーにする。
+    fn set_aside_newer_save(&self, slot: SaveSlot) -> Result<()> {
+        let path = self.slot_path(slot);
+        if !self.newer_slots().contains(&slot) || !path.exists() {
+            return Ok(());
         }
 
-        if !self.save_path.exists() {
-            return Ok(None);
+        let newer = self.newer_path(slot);
+        if newer.exists() {
+            bail!(
+                "{} holds a save from a newer version and {newer:?} is already taken",
+                slot.label()
+            );
         }
+        fs::rename(&path, &newer)
+            .with_context(|| format!("Failed to set aside newer save: {path:?}"))?;
+        self.newer_slots().remove(&slot);
+        warn!("Save from a newer version moved to {:?}", newer);
+        Ok(())
+    }
 
-        let json = fs::read_to_string(&self.save_path).with_context(|| {
-            format!(
-                "Failed to read save file: {save_path:?}",
-                save_path = self.save_path
-            )
-        })?;
+    fn newer_slots(&self) -> MutexGuard<'_, HashSet<SaveSlot>> {
+        self.newer_slots
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
 
-        let save_data: SaveData =
-            serde_json::from_str(&json).context("Failed to deserialize save data")?;
+    fn newer_path(&self, slot: SaveSlot) -> PathBuf {
+        self.save_dir.join(format!("{}.newer", slot.file_name()))
+    }
 
-        if save_data.version != 1 {
-            warn!(
-                "Save file version mismatch. Expected: 1, Found: {}. Save will be ignored.",
-                save_data.version
-            );
+    /// 古い世代から順に1つずつずらし、最新のファイルを1世代前にする
+    fn rotate_backups(&self, slot: SaveSlot) -> Result<()> {
+        for generation in (0..BACKUP_GENERATIONS).rev() {
+            let from = self.generation_path(slot, generation);
+            if from.exists() {
+                let to = self.generation_path(slot, generation + 1);
+                fs::rename(&from, &to)
+                    .with_context(|| format!("Failed to rotate backup: {from:?} -> {to:?}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 最新のファイルから順に、読めて整合の取れている世代を探す
+    pub fn load(&self, slot: SaveSlot) -> Result<Option<LoadedSave>> {
+        if self.disabled {
+            debug!("Save disabled, no save data available");
             return Ok(None);
         }
 
-        let current = Current {
-            id: save_data.current,
-            depth: save_data.depth,
-            trail: save_data.trail,
-        };
+        let mut last_error = None;
+        for generation in 0..=BACKUP_GENERATIONS {
+            let path = self.generation_path(slot, generation);
+            if !path.exists() {
+                continue;
+            }
+
+            let read = read_save(&path);
+            if generation == 0 {
+                let newer = read.as_ref().is_err_and(is_unsupported_version);
+                let mut newer_slots = self.newer_slots();
+                if newer {
+                    newer_slots.insert(slot);
+                } else {
+                    newer_slots.remove(&slot);
+                }
+            }
+            match read {
+                Ok(data) => {
+                    let recovery = (generation > 0).then_some(Recovery { slot, generation });
+                    match &recovery {
+                        Some(recovery) => warn!("{} ({:?})", recovery.message(), path),
+                        None => debug!("Save read from {:?}", path),
+                    }
+                    return Ok(Some(LoadedSave { data, recovery }));
+                }
+                // 新しいゲームで作られたセーブは壊れているわけではないので、古い世代には戻らない
+                Err(e) if is_unsupported_version(&e) => {
+                    warn!("Refusing save {:?}: {}", path, e);
+                    return Err(e);
+                }
+                Err(e) => {
+                    warn!("Skipping unreadable save {:?}: {:#}", path, e);
+                    last_error = Some(e);
+                }
+            }
+        }
 
-        info!("Game loaded from {:?}", self.save_path);
-        Ok(Some(current))
+        match last_error {
+            Some(e) => Err(e.context(format!("No readable save in {}", slot.label()))),
+            None => Ok(None),
+        }
     }
 
-    #[allow(dead_code)]
-    pub fn delete(&self) -> Result<()> {
-        if self.save_path.exists() {
-            fs::remove_file(&self.save_path).with_context(|| {
-                format!(
-                    "Failed to delete save file: {save_path:?}",
-                    save_path = self.save_path
-                )
-            })?;
-            info!("Save file deleted: {:?}", self.save_path);
+    /// 全スロットの中身（空きスロットは Ok(None)）
+    pub fn list(&self) -> Vec<(SaveSlot, Result<Option<LoadedSave>>)> {
+        SaveSlot::all()
+            .map(|slot| {
+                let loaded = self.load(slot);
+                if let Err(e) = &loaded {
+                    warn!("Failed to read {}: {:#}", slot.label(), e);
+                }
+                (slot, loaded)
+            })
+            .collect()
+    }
+
+    /// いちばん新しいセーブ（「前回の続きから」で再開する先）
+    pub fn newest(&self) -> Option<(SaveSlot, LoadedSave)> {
+        self.list()
+            .into_iter()
+            .filter_map(|(slot, loaded)| loaded.ok().flatten().map(|loaded| (slot, loaded)))
+            .max_by_key(|(_, loaded)| loaded.data.saved_at)
+    }
+
+    /// スロットをバックアップと退避した新しいゲームのセーブごと消す
+    pub fn delete(&self, slot: SaveSlot) -> Result<()> {
+        let newer = self.newer_path(slot);
+        for save_path in (0..=BACKUP_GENERATIONS)
+            .map(|generation| self.generation_path(slot, generation))
+            .chain([newer])
+        {
+            if save_path.exists() {
+                fs::remove_file(&save_path)
+                    .with_context(|| format!("Failed to delete save file: {save_path:?}"))?;
+                info!("Save file deleted: {:?}", save_path);
+            }
         }
+        self.newer_slots().remove(&slot);
         Ok(())
     }
 
+    /// 読み込めるセーブが1つでもあるか（壊れたファイルしかなければ false）
     pub fn has_save(&self) -> bool {
         if self.disabled {
             return false;
         }
-        self.save_path.exists()
+        self.newest().is_some()
+    }
+
+    fn session_path(&self) -> PathBuf {
+        self.save_dir.join("session.json")
+    }
+
+    /// 前回起動したときのシナリオの識別子
+    pub fn last_session(&self) -> Option<SessionRecord> {
+        if self.disabled {
+            return None;
+        }
+        let json = fs::read_to_string(self.session_path()).ok()?;
+        serde_json::from_str(&json)
+            .inspect_err(|e| warn!("Ignoring unreadable session record: {}", e))
+            .ok()
+    }
+
+    /// 今回のシナリオの識別子を次回の起動のために残す
+    pub fn record_session(&self, record: &SessionRecord) -> Result<()> {
+        if self.disabled {
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(record).context("Failed to serialize session")?;
+        replace_file(&self.session_path(), json.as_bytes())
+    }
+
+    fn profile_path(&self) -> PathBuf {
+        self.save_dir.join("profile.json")
+    }
+
+    /// エンディングの収集記録を読む（まだ無ければ None）
+    ///
+    /// 壊れていた場合は次の書き込みで消えないよう脇へ退避してからエラーを返す。
+    pub fn load_profile(&self) -> Result<Option<Profile>> {
+        self.load_or_set_aside(&self.profile_path(), "profile", Profile::from_json)
+    }
+
+    /// 設定ファイルを読む（壊れていたら .broken に、新しいバージョンのものなら .newer に退避してエラー）
+    fn load_or_set_aside<T>(
+        &self,
+        path: &Path,
+        what: &str,
+        parse: impl FnOnce(&str) -> Result<T>,
+    ) -> Result<Option<T>> {
+        if self.disabled {
+            return Ok(None);
+        }
+        let json = match fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {what}: {path:?}")),
+        };
+
+        match parse(&json) {
+            Ok(value) => Ok(Some(value)),
+            // 新しいゲームの記録は壊れているわけではないので、上書きせず別名で残す
+            Err(e) if is_unsupported_version(&e) => {
+                let newer = path.with_extension("json.newer");
+                if let Err(rename_error) = fs::rename(path, &newer) {
+                    warn!("Failed to set aside newer {}: {}", what, rename_error);
+                }
+                Err(e.context(format!(
+                    "The {what} is from a newer version, moved to {newer:?}"
+                )))
+            }
+            Err(e) => {
+                let broken = path.with_extension("json.broken");
+                if let Err(rename_error) = fs::rename(path, &broken) {
+                    warn!("Failed to set aside broken {}: {}", what, rename_error);
+                }
+                Err(e.context(format!("The {what} is unreadable, moved to {broken:?}")))
+            }
+        }
+    }
+
+    /// エンディングの収集記録を書く（スロットの削除や新しいゲームでは消えない）
+    pub fn save_profile(&self, profile: &Profile) -> Result<()> {
+        if self.disabled {
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(profile).context("Failed to serialize profile")?;
+        replace_file(&self.profile_path(), json.as_bytes())
+    }
+
+    fn bindings_path(&self) -> PathBuf {
+        self.save_dir.join("bindings.json")
+    }
+
+    /// 操作の割り当てを読む（まだ無ければ None、壊れていたら退避してエラー）
+    pub fn load_bindings(&self) -> Result<Option<InputBindings>> {
+        self.load_or_set_aside(
+            &self.bindings_path(),
+            "input bindings",
+            InputBindings::from_json,
+        )
+    }
+
+    pub fn save_bindings(&self, bindings: &InputBindings) -> Result<()> {
+        if self.disabled {
+            return Ok(());
+        }
+        replace_file(&self.bindings_path(), bindings.to_json()?.as_bytes())
+    }
+
+    fn settings_path(&self) -> PathBuf {
+        self.save_dir.join("settings.json")
+    }
+
+    /// 遊び方の設定を読む（まだ無ければ None、壊れていたら退避してエラー）
+    pub fn load_settings(&self) -> Result<Option<PlayerSettings>> {
+        self.load_or_set_aside(
+            &self.settings_path(),
+            "player settings",
+            PlayerSettings::from_json,
+        )
+    }
+
+    pub fn save_settings(&self, settings: &PlayerSettings) -> Result<()> {
+        if self.disabled {
+            return Ok(());
+        }
+        replace_file(&self.settings_path(), settings.to_json()?.as_bytes())
+    }
+}
+
+/// 起動のたびに上書きする記録（物語が更新されたかの判定に使う）
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SessionRecord {
+    pub scenario_fingerprint: String,
+}
+
+/// 前回の起動から物語が更新されていたか（タイトルに注記を出す）
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoryUpdated(pub bool);
+
+/// 初めてタイトルに来たときに前回の記録と見比べ、今回の識別子を残す
+pub fn check_story_update(
+    mut commands: Commands,
+    save_manager: Res<SaveManager>,
+    scenario_data: Res<ScenarioData>,
+    story_updated: Option<Res<StoryUpdated>>,
+) {
+    if story_updated.is_some() {
+        return;
+    }
+
+    let updated = save_manager
+        .last_session()
+        .is_some_and(|last| last.scenario_fingerprint != scenario_data.fingerprint);
+    if updated {
+        info!("Scenario changed since the last session");
+    }
+    commands.insert_resource(StoryUpdated(updated));
+
+    let record = SessionRecord {
+        scenario_fingerprint: scenario_data.fingerprint.clone(),
+    };
+    if let Err(e) = save_manager.record_session(&record) {
+        warn!("Failed to record session: {:#}", e);
+    }
+}
+
+/// 新しいバージョンのゲームで作られたセーブを読もうとしたか
+pub fn is_unsupported_version(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<SaveFormatError>()
+        .is_some_and(SaveFormatError::is_unsupported_version)
+}
+
+/// 読み込みに失敗したときにプレイヤーへ見せる文言
+pub fn load_error_message(slot: SaveSlot, e: &anyhow::Error) -> String {
+    match e.downcast_ref::<SaveFormatError>() {
+        Some(format_error) if format_error.is_unsupported_version() => {
+            format!("{}: {}", slot.label(), format_error.player_message())
+        }
+        _ => format!("{}のセーブデータを読み込めませんでした", slot.label()),
     }
 }
 
-pub fn auto_save_system(save_manager: Res<SaveManager>, current: Res<Current>) {
-    if current.is_changed() && current.depth > 0 {
-        if let Err(e) = save_manager.save(&current) {
-            error!("Failed to auto-save: {}", e);
+/// セーブファイルを1つ読み、古い形式なら現在の形式に移行する
+fn read_save(path: &Path) -> Result<SaveData> {
+    let json =
+        fs::read_to_string(path).with_context(|| format!("Failed to read save file: {path:?}"))?;
+
+    let value: serde_json::Value =
+        serde_json::from_str(&json).context("Failed to deserialize save data")?;
+    let save_data = upgrade(value)?;
+
+    if !save_data.is_consistent() {
+        bail!(
+            "Save data is inconsistent: current {} does not match trail {:?}",
+            save_data.current,
+            save_data.trail
+        );
+    }
+
+    Ok(save_data)
+}
+
+/// 一時ファイル経由で丸ごと差し替える（バックアップを持たない記録用）
+fn replace_file(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("json.tmp");
+    write_synced(&tmp_path, bytes).with_context(|| format!("Failed to write {tmp_path:?}"))?;
+    fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {path:?}"))?;
+    if let Some(dir) = path.parent() {
+        sync_dir(dir);
+    }
+    Ok(())
+}
+
+fn write_synced(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+/// rename をディスクに確定させる（対応していない環境では何もしない）
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Err(e) = File::open(dir).and_then(|d| d.sync_all()) {
+        debug!("Failed to sync save directory {:?}: {}", dir, e);
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+/// Playing 中の経過時間（セーブに記録する累計プレイ時間）
+#[derive(Resource, Default)]
+pub struct PlayTime(pub Duration);
+
+pub fn tick_play_time(time: Res<Time>, mut play_time: ResMut<PlayTime>) {
+    play_time.0 += time.delta();
+}
+
+/// 進むたびにオートセーブする
+///
+/// 新しいゲームの開始直後（階層 0）は前回の続きを残すため保存しないが、
+/// 巻き戻しやルートマップからの移動で戻った場合はその位置を保存する。
+/// エンディングやルートマップからの移動は Playing に入った次のフレームで届くので、イベントはここでも読む。
+/// 前回と同じ場面（trail が同じ）でページだけが進んだときは、バックアップをずらさずに書き換える。
+pub fn auto_save_system(
+    save_manager: Res<SaveManager>,
+    current: Res<Current>,
+    scenario_data: Res<ScenarioData>,
+    play_time: Res<PlayTime>,
+    mut rewind_events: EventReader<RewindTo>,
+    mut jump_events: EventReader<JumpToRoute>,
+    mut last_saved_trail: bevy::prelude::Local<Vec<String>>,
+) {
+    let rewound = rewind_events.read().count() + jump_events.read().count() > 0;
+    if current.is_changed() && (current.depth > 0 || rewound) {
+        let save_data = SaveData::capture(&current, &scenario_data, play_time.0);
+        let result = if *last_saved_trail == current.trail {
+            save_manager.update(SaveSlot::Auto, &save_data)
+        } else {
+            save_manager.save(SaveSlot::Auto, &save_data)
+        };
+        match result {
+            Ok(()) => *last_saved_trail = current.trail.clone(),
+            Err(e) => error!("Failed to auto-save: {}", e),
         }
     }
 }
@@ -147,16 +707,30 @@ mod tests {
 
     fn create_test_save_manager() -> (SaveManager, tempfile::TempDir) {
         let temp_dir = tempdir().unwrap();
-        let save_path = temp_dir.path().join("test_save.json");
 
-        let save_manager = SaveManager {
-            save_path,
-            disabled: false,
-        };
+        let save_manager = SaveManager::with_dir(temp_dir.path().to_path_buf());
 
         (save_manager, temp_dir)
     }
 
+    fn test_scenario() -> ScenarioData {
+        ScenarioData::load_from_json(
+            r#"{
+              "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+              "nodes": [
+                {"id": "R", "text": "root", "choices": [{"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}]},
+                {"id": "R1", "text": "雨の音が\n静かに続いている。傘を差して歩き出すと、遠くで電車の音がした。", "ending": {"tag": "A"}},
+                {"id": "R0", "text": "e", "ending": {"tag": "B"}}
+              ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn capture(current: &Current) -> SaveData {
+        SaveData::capture(current, &test_scenario(), Duration::from_secs(75))
+    }
+
     #[test]
     fn test_save_and_load() {
         let (save_manager, _temp_dir) = create_test_save_manager();
@@ -170,22 +744,270 @@ mod tests {
                 "R10".to_string(),
                 "R101".to_string(),
             ],
+            vars: Variables::default(),
+            page: 2,
+            choices: vec![0, 1, 0],
         };
 
-        save_manager.save(&original_current).unwrap();
+        save_manager
+            .save(SaveSlot::Auto, &capture(&original_current))
+            .unwrap();
         assert!(save_manager.has_save());
 
-        let loaded_current = save_manager.load().unwrap().unwrap();
+        let loaded = save_manager.load(SaveSlot::Auto).unwrap().unwrap();
+        assert!(loaded.recovery.is_none());
+        let loaded_current = loaded.data.to_current();
         assert_eq!(loaded_current.id, original_current.id);
         assert_eq!(loaded_current.depth, original_current.depth);
         assert_eq!(loaded_current.trail, original_current.trail);
+        assert_eq!(loaded_current.page, 2);
+    }
+
+    #[test]
+    fn test_save_and_load_variables() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+
+        let mut original_current = Current::default();
+        original_current
+            .vars
+            .set("has_umbrella", crate::scenario::VarValue::Bool(true));
+        original_current
+            .vars
+            .set("courage", crate::scenario::VarValue::Int(2));
+
+        save_manager
+            .save(SaveSlot::Auto, &capture(&original_current))
+            .unwrap();
+
+        let loaded = save_manager.load(SaveSlot::Auto).unwrap().unwrap().data;
+        assert_eq!(loaded.vars, original_current.vars);
+    }
+
+    #[test]
+    fn test_load_save_without_variables() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+
+        // 変数導入前のセーブもそのまま読める
+        let old_save = r#"{"version": 1, "current": "R1", "depth": 1, "trail": ["R", "R1"]}"#;
+        fs::write(save_manager.slot_path(SaveSlot::Auto), old_save).unwrap();
+
+        let loaded = save_manager.load(SaveSlot::Auto).unwrap().unwrap().data;
+        assert_eq!(loaded.current, "R1");
+        assert_eq!(loaded.vars, Variables::default());
+        assert_eq!(loaded.saved_at_label(), "----/--/-- --:--");
+    }
+
+    #[test]
+    fn test_capture_records_metadata() {
+        let current = Current {
+            id: "R1".to_string(),
+            depth: 1,
+            trail: vec!["R".to_string(), "R1".to_string()],
+            vars: Variables::default(),
+            page: 0,
+            choices: vec![0],
+        };
+        let save_data = capture(&current);
+
+        assert_eq!(save_data.scenario_title, "Test");
+        assert_eq!(save_data.play_time_secs, 75);
+        assert!(save_data.saved_at > 0);
+        // 改行は詰め、長い本文は省略する
+        assert_eq!(
+            save_data.excerpt,
+            "雨の音が 静かに続いている。傘を差して歩き出すと…"
+        );
+        assert_eq!(format_play_time(save_data.play_time()), "0:01:15");
+        assert_eq!(
+            format_play_time(Duration::from_secs(3 * 3600 + 5)),
+            "3:00:05"
+        );
+    }
+
+    #[test]
+    fn test_slots_are_independent() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+        let scenario_data = test_scenario();
+
+        let root = scenario_data.initial_current();
+        let ending = scenario_data.transition(&root, 0).unwrap();
+        save_manager
+            .save(SaveSlot::Manual(2), &capture(&root))
+            .unwrap();
+        save_manager
+            .save(SaveSlot::Manual(MANUAL_SLOT_COUNT), &capture(&ending))
+            .unwrap();
+
+        let list = save_manager.list();
+        assert_eq!(list.len(), MANUAL_SLOT_COUNT + 2);
+        assert_eq!(list[0].0, SaveSlot::Auto);
+        assert_eq!(list[MANUAL_SLOT_COUNT + 1].0, SaveSlot::Quick);
+        let current_of = |i: usize| {
+            let loaded = list[i].1.as_ref().unwrap().as_ref();
+            loaded.map(|loaded| loaded.data.current.clone())
+        };
+        assert_eq!(current_of(0), None);
+        assert_eq!(current_of(2).as_deref(), Some("R"));
+        assert_eq!(current_of(MANUAL_SLOT_COUNT).as_deref(), Some("R1"));
+    }
+
+    #[test]
+    fn test_newest_save_wins() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+        assert!(save_manager.newest().is_none());
+
+        let mut older = capture(&Current::default());
+        older.saved_at = 1_000;
+        let mut newer = older.clone();
+        newer.current = "R1".to_string();
+        newer.depth = 1;
+        newer.trail.push("R1".to_string());
+        newer.saved_at = 2_000;
+
+        save_manager.save(SaveSlot::Auto, &older).unwrap();
+        save_manager.save(SaveSlot::Manual(3), &newer).unwrap();
+
+        let (slot, loaded) = save_manager.newest().unwrap();
+        assert_eq!(slot, SaveSlot::Manual(3));
+        assert_eq!(loaded.data.current, "R1");
+    }
+
+    fn save_at(save_manager: &SaveManager, id: &str) {
+        let mut trail = vec!["R".to_string()];
+        if id != "R" {
+            trail.push(id.to_string());
+        }
+        let current = Current {
+            id: id.to_string(),
+            depth: trail.len() - 1,
+            choices: vec![0; trail.len() - 1],
+            trail,
+            vars: Variables::default(),
+            page: 0,
+        };
+        save_manager
+            .save(SaveSlot::Auto, &capture(&current))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_save_keeps_backup_generations() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+
+        for id in ["R", "R1", "R0", "R1", "R0"] {
+            save_at(&save_manager, id);
+        }
+
+        // 一時ファイルは残らず、最新 + BACKUP_GENERATIONS 世代だけが残る
+        let mut files: Vec<_> = fs::read_dir(save_manager.save_dir.clone())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                "save.json",
+                "save.json.bak1",
+                "save.json.bak2",
+                "save.json.bak3"
+            ]
+        );
+        let bak1 = read_save(&save_manager.generation_path(SaveSlot::Auto, 1)).unwrap();
+        assert_eq!(bak1.current, "R1");
+    }
+
+    #[test]
+    fn test_update_keeps_backup_generations() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+        save_at(&save_manager, "R1");
+        save_at(&save_manager, "R0");
+
+        // ページを送っただけの保存では直前の場面のバックアップを押し出さない
+        let mut current = save_manager
+            .load(SaveSlot::Auto)
+            .unwrap()
+            .unwrap()
+            .data
+            .to_current();
+        for page in 1..=BACKUP_GENERATIONS + 1 {
+            current.page = page;
+            save_manager
+                .update(SaveSlot::Auto, &capture(&current))
+                .unwrap();
+        }
+
+        let latest = read_save(&save_manager.generation_path(SaveSlot::Auto, 0)).unwrap();
+        assert_eq!(latest.page, BACKUP_GENERATIONS + 1);
+        let backup = read_save(&save_manager.generation_path(SaveSlot::Auto, 1)).unwrap();
+        assert_eq!(backup.current, "R1");
+        assert!(!save_manager.generation_path(SaveSlot::Auto, 2).exists());
+    }
+
+    #[test]
+    fn test_truncated_save_falls_back_to_backup() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+        save_at(&save_manager, "R1");
+        save_at(&save_manager, "R0");
+
+        // 書き込み途中で落ちたかのように最新のファイルを切り詰める
+        let path = save_manager.slot_path(SaveSlot::Auto);
+        let json = fs::read_to_string(&path).unwrap();
+        fs::write(&path, &json[..json.len() / 2]).unwrap();
+
+        let loaded = save_manager.load(SaveSlot::Auto).unwrap().unwrap();
+        assert_eq!(loaded.data.current, "R1");
+        let recovery = loaded.recovery.unwrap();
+        assert_eq!(recovery.generation, 1);
+        assert!(recovery.message().contains("オートセーブ"));
+        assert!(save_manager.has_save());
+    }
+
+    #[test]
+    fn test_inconsistent_save_is_skipped() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+        save_at(&save_manager, "R1");
+        save_at(&save_manager, "R0");
+
+        // JSON としては読めても、trail と current が食い違うものは採用しない
+        let broken = r#"{"version": 1, "current": "R0", "depth": 1, "trail": ["R", "R1"]}"#;
+        fs::write(save_manager.slot_path(SaveSlot::Auto), broken).unwrap();
+
+        let loaded = save_manager.load(SaveSlot::Auto).unwrap().unwrap();
+        assert_eq!(loaded.data.current, "R1");
+        assert!(loaded.recovery.is_some());
+    }
+
+    #[test]
+    fn test_missing_save_after_rotation_uses_backup() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+        save_at(&save_manager, "R1");
+
+        // 世代をずらした直後、差し替え前に落ちた状態
+        save_manager.rotate_backups(SaveSlot::Auto).unwrap();
+        assert!(!save_manager.slot_path(SaveSlot::Auto).exists());
+
+        let loaded = save_manager.load(SaveSlot::Auto).unwrap().unwrap();
+        assert_eq!(loaded.data.current, "R1");
+        assert_eq!(loaded.recovery.unwrap().generation, 1);
+    }
+
+    #[test]
+    fn test_all_generations_corrupt() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+        fs::write(save_manager.slot_path(SaveSlot::Auto), "{ trunc").unwrap();
+        fs::write(save_manager.generation_path(SaveSlot::Auto, 1), "").unwrap();
+
+        assert!(save_manager.load(SaveSlot::Auto).is_err());
+        // 続きから再開できるものがないので Continue は出さない
+        assert!(!save_manager.has_save());
     }
 
     #[test]
     fn test_load_nonexistent_save() {
         let (save_manager, _temp_dir) = create_test_save_manager();
 
-        let result = save_manager.load().unwrap();
+        let result = save_manager.load(SaveSlot::Auto).unwrap();
         assert!(result.is_none());
         assert!(!save_manager.has_save());
     }
@@ -195,21 +1017,147 @@ mod tests {
         let (save_manager, _temp_dir) = create_test_save_manager();
         let current = Current::default();
 
-        save_manager.save(&current).unwrap();
+        // バックアップも含めて消える
+        for _ in 0..2 {
+            save_manager
+                .save(SaveSlot::Manual(1), &capture(&current))
+                .unwrap();
+        }
         assert!(save_manager.has_save());
+        // 退避しておいた新しいゲームのセーブも消える
+        fs::write(save_manager.newer_path(SaveSlot::Manual(1)), "{}").unwrap();
 
-        save_manager.delete().unwrap();
+        save_manager.delete(SaveSlot::Manual(1)).unwrap();
         assert!(!save_manager.has_save());
+        assert_eq!(fs::read_dir(&save_manager.save_dir).unwrap().count(), 0);
     }
 
     #[test]
-    fn test_version_mismatch() {
+    fn test_v1_save_is_migrated() {
         let (save_manager, _temp_dir) = create_test_save_manager();
 
-        let invalid_save = r#"{"version": 255, "current": "R", "depth": 0, "trail": ["R"]}"#;
-        fs::write(&save_manager.save_path, invalid_save).unwrap();
+        let v1 = r#"{"version": 1, "current": "R1", "depth": 1, "trail": ["R", "R1"]}"#;
+        fs::write(save_manager.slot_path(SaveSlot::Manual(1)), v1).unwrap();
 
-        let result = save_manager.load().unwrap();
-        assert!(result.is_none());
+        let loaded = save_manager.load(SaveSlot::Manual(1)).unwrap().unwrap();
+        assert_eq!(loaded.data.version, SAVE_VERSION);
+        assert!(loaded.recovery.is_none());
+    }
+
+    #[test]
+    fn test_future_version_is_refused() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+        save_at(&save_manager, "R1");
+
+        let future_save = r#"{"version": 255, "current": "R", "depth": 0, "trail": ["R"]}"#;
+        fs::write(save_manager.slot_path(SaveSlot::Auto), future_save).unwrap();
+
+        // バックアップには戻らず、理由の分かるエラーにする
+        let err = save_manager.load(SaveSlot::Auto).unwrap_err();
+        assert!(is_unsupported_version(&err));
+        assert!(load_error_message(SaveSlot::Auto, &err).contains("新しいバージョン"));
+        assert!(!save_manager.has_save());
+
+        // 次のセーブでは消さずに .newer へ退避してから書く
+        save_at(&save_manager, "R0");
+        let newer = save_manager.newer_path(SaveSlot::Auto);
+        assert_eq!(fs::read_to_string(&newer).unwrap(), future_save);
+        assert_eq!(
+            save_manager
+                .load(SaveSlot::Auto)
+                .unwrap()
+                .unwrap()
+                .data
+                .current,
+            "R0"
+        );
+
+        // 退避先が埋まっていれば書き込まない
+        fs::write(save_manager.slot_path(SaveSlot::Auto), future_save).unwrap();
+        assert!(save_manager.load(SaveSlot::Auto).is_err());
+        let current = Current::default();
+        assert!(save_manager
+            .save(SaveSlot::Auto, &capture(&current))
+            .is_err());
+        assert!(save_manager.load(SaveSlot::Auto).is_err());
+    }
+
+    #[test]
+    fn test_capture_records_fingerprint() {
+        let scenario_data = test_scenario();
+        let save_data = capture(&Current::default());
+        assert_eq!(save_data.scenario_fingerprint, scenario_data.fingerprint);
+        assert_eq!(save_data.version, SAVE_VERSION);
+    }
+
+    #[test]
+    fn test_story_update_against_last_session() {
+        let (save_manager, _temp_dir) = create_test_save_manager();
+        let mut app = App::new();
+        app.insert_resource(save_manager);
+        app.insert_resource(test_scenario());
+        app.add_systems(Update, check_story_update);
+
+        // 初回起動は「更新」ではない
+        app.update();
+        assert_eq!(*app.world().resource::<StoryUpdated>(), StoryUpdated(false));
+
+        // 前回と違う識別子が記録されていれば更新あり
+        app.world_mut().remove_resource::<StoryUpdated>();
+        let record = SessionRecord {
+            scenario_fingerprint: "h:previous".to_string(),
+        };
+        app.world()
+            .resource::<SaveManager>()
+            .record_session(&record)
+            .unwrap();
+        app.update();
+        assert_eq!(*app.world().resource::<StoryUpdated>(), StoryUpdated(true));
+
+        let last = app
+            .world()
+            .resource::<SaveManager>()
+            .last_session()
+            .unwrap();
+        assert_eq!(last.scenario_fingerprint, test_scenario().fingerprint);
+    }
+
+    #[test]
+    fn test_broken_profile_is_set_aside() {
+        let (save_manager, temp_dir) = create_test_save_manager();
+        assert!(save_manager.load_profile().unwrap().is_none());
+
+        fs::write(temp_dir.path().join("profile.json"), "{ broken").unwrap();
+        assert!(save_manager.load_profile().is_err());
+        assert!(temp_dir.path().join("profile.json.broken").exists());
+
+        // 退避した後は新しい記録として書ける
+        let mut profile = Profile::default();
+        profile.record_ending("R1", "A", 1);
+        save_manager.save_profile(&profile).unwrap();
+        assert_eq!(save_manager.load_profile().unwrap(), Some(profile));
+    }
+
+    #[test]
+    fn test_newer_profile_is_set_aside() {
+        let (save_manager, temp_dir) = create_test_save_manager();
+        let newer = r#"{"version": 99, "endings": {"R1": {"tag": "A"}}, "trophies": []}"#;
+        fs::write(temp_dir.path().join("profile.json"), newer).unwrap();
+
+        let error = save_manager.load_profile().unwrap_err();
+        assert!(is_unsupported_version(&error));
+        assert!(!temp_dir.path().join("profile.json").exists());
+        assert!(!temp_dir.path().join("profile.json.broken").exists());
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("profile.json.newer")).unwrap(),
+            newer
+        );
+
+        // 新しいゲームの記録は残したまま、この版の記録を始められる
+        save_manager.save_profile(&Profile::default()).unwrap();
+        assert_eq!(
+            save_manager.load_profile().unwrap(),
+            Some(Profile::default())
+        );
     }
 }
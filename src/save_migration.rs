@@ -0,0 +1,269 @@
+use crate::save::SaveData;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+/// このビルドが書き出すセーブ形式のバージョン
+///
+/// SaveData を変えるときはこれを1つ上げ、MIGRATIONS に1段分の移行を足す。
+pub const SAVE_VERSION: u8 = 5;
+
+/// 1段分の移行（vN の JSON を受け取り、vN+1 の JSON を返す）
+type Migration = fn(Map<String, Value>) -> Result<Map<String, Value>, SaveFormatError>;
+
+/// MIGRATIONS[i] は v(i+1) を v(i+2) に上げる
+const MIGRATIONS: &[Migration] = &[v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5];
+
+#[derive(Debug, Error)]
+pub enum SaveFormatError {
+    #[error("save data is not a JSON object")]
+    NotAnObject,
+    #[error("save data has no valid version")]
+    MissingVersion,
+    #[error("save format v{found} is newer than this build supports (up to v{supported})")]
+    UnsupportedVersion { found: u64, supported: u8 },
+    #[error("failed to migrate save from v{from}: {reason}")]
+    Migration { from: u64, reason: String },
+    #[error("save data does not match format v{SAVE_VERSION}: {0}")]
+    Malformed(#[from] serde_json::Error),
+}
+
+impl SaveFormatError {
+    /// 古いバックアップに戻しても解決しない（新しいゲームで作られたセーブ）か
+    pub fn is_unsupported_version(&self) -> bool {
+        matches!(self, SaveFormatError::UnsupportedVersion { .. })
+    }
+
+    /// プレイヤーに伝える文言
+    pub fn player_message(&self) -> String {
+        match self {
+            SaveFormatError::UnsupportedVersion { found, supported } => format!(
+                "新しいバージョンのゲームで作られたセーブです（形式 v{found}、このゲームは v{supported} まで対応）"
+            ),
+            _ => "セーブデータの形式が正しくありません".to_string(),
+        }
+    }
+}
+
+/// 読み込んだ JSON を1段ずつ現在の形式まで上げてから SaveData にする
+pub fn upgrade(value: Value) -> Result<SaveData, SaveFormatError> {
+    let Value::Object(mut save) = value else {
+        return Err(SaveFormatError::NotAnObject);
+    };
+
+    let mut version = save
+        .get("version")
+        .and_then(Value::as_u64)
+        .filter(|v| *v >= 1)
+        .ok_or(SaveFormatError::MissingVersion)?;
+    if version > SAVE_VERSION as u64 {
+        return Err(SaveFormatError::UnsupportedVersion {
+            found: version,
+            supported: SAVE_VERSION,
+        });
+    }
+
+    while version < SAVE_VERSION as u64 {
+        let migration = MIGRATIONS[version as usize - 1];
+        save = migration(save)?;
+        version += 1;
+        save.insert("version".to_string(), json!(version));
+    }
+
+    Ok(serde_json::from_value(Value::Object(save))?)
+}
+
+/// v1 → v2: 変数とスロット表示用の情報を必須にする
+///
+/// v1 の途中から vars やセーブ時刻などが追加されたが、古いファイルには無い。
+fn v1_to_v2(mut save: Map<String, Value>) -> Result<Map<String, Value>, SaveFormatError> {
+    for key in ["current", "depth", "trail"] {
+        if !save.contains_key(key) {
+            return Err(SaveFormatError::Migration {
+                from: 1,
+                reason: format!("missing '{key}'"),
+            });
+        }
+    }
+
+    let defaults = [
+        ("vars", json!({})),
+        ("saved_at", json!(0)),
+        ("play_time_secs", json!(0)),
+        ("excerpt", json!("")),
+        ("scenario_title", json!("")),
+    ];
+    for (key, value) in defaults {
+        save.entry(key).or_insert(value);
+    }
+    Ok(save)
+}
+
+/// v2 → v3: セーブしたときのシナリオの識別子を持たせる
+///
+/// 古いセーブはどのシナリオで作られたか分からないので空にし、読み込み時に必ず辿り直させる。
+fn v2_to_v3(mut save: Map<String, Value>) -> Result<Map<String, Value>, SaveFormatError> {
+    save.entry("scenario_fingerprint").or_insert(json!(""));
+    Ok(save)
+}
+
+/// v3 → v4: 読んでいたページを持たせる
+///
+/// ページ分けの無かった頃のセーブなので、どのノードも1ページ目から読み直す。
+fn v3_to_v4(mut save: Map<String, Value>) -> Result<Map<String, Value>, SaveFormatError> {
+    save.entry("page").or_insert(json!(0));
+    Ok(save)
+}
+
+/// v4 → v5: 各階層で選んだ選択肢の添字を持たせる
+///
+/// 古いセーブにはどれを選んだかの記録が無いので空にし、読み込み時に trail から辿り直させる。
+fn v4_to_v5(mut save: Map<String, Value>) -> Result<Map<String, Value>, SaveFormatError> {
+    save.entry("choices").or_insert(json!([]));
+    Ok(save)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v1_minimal() -> Value {
+        json!({"version": 1, "current": "R1", "depth": 1, "trail": ["R", "R1"]})
+    }
+
+    #[test]
+    fn test_every_version_has_a_migration() {
+        assert_eq!(MIGRATIONS.len(), SAVE_VERSION as usize - 1);
+    }
+
+    #[test]
+    fn test_v1_to_v2_fills_metadata() {
+        let Value::Object(v1) = v1_minimal() else {
+            unreachable!()
+        };
+        let v2 = v1_to_v2(v1).unwrap();
+
+        assert_eq!(v2["vars"], json!({}));
+        assert_eq!(v2["saved_at"], json!(0));
+        assert_eq!(v2["play_time_secs"], json!(0));
+        assert_eq!(v2["excerpt"], json!(""));
+        assert_eq!(v2["scenario_title"], json!(""));
+        assert_eq!(v2["trail"], json!(["R", "R1"]));
+    }
+
+    #[test]
+    fn test_v1_to_v2_keeps_existing_fields() {
+        let Value::Object(mut v1) = v1_minimal() else {
+            unreachable!()
+        };
+        v1.insert("vars".to_string(), json!({"courage": 2}));
+        v1.insert("saved_at".to_string(), json!(1_700_000_000));
+        let v2 = v1_to_v2(v1).unwrap();
+
+        assert_eq!(v2["vars"], json!({"courage": 2}));
+        assert_eq!(v2["saved_at"], json!(1_700_000_000));
+    }
+
+    #[test]
+    fn test_v1_to_v2_rejects_incomplete_save() {
+        let Value::Object(v1) = json!({"version": 1, "current": "R1"}) else {
+            unreachable!()
+        };
+        assert!(matches!(
+            v1_to_v2(v1),
+            Err(SaveFormatError::Migration { from: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_v2_to_v3_marks_fingerprint_unknown() {
+        let Value::Object(v1) = v1_minimal() else {
+            unreachable!()
+        };
+        let v3 = v2_to_v3(v1_to_v2(v1).unwrap()).unwrap();
+        assert_eq!(v3["scenario_fingerprint"], json!(""));
+    }
+
+    #[test]
+    fn test_v3_to_v4_starts_at_first_page() {
+        let Value::Object(v1) = v1_minimal() else {
+            unreachable!()
+        };
+        let v4 = v3_to_v4(v2_to_v3(v1_to_v2(v1).unwrap()).unwrap()).unwrap();
+        assert_eq!(v4["page"], json!(0));
+    }
+
+    #[test]
+    fn test_v4_to_v5_leaves_choices_unrecorded() {
+        let Value::Object(v1) = v1_minimal() else {
+            unreachable!()
+        };
+        let v4 = v3_to_v4(v2_to_v3(v1_to_v2(v1).unwrap()).unwrap()).unwrap();
+        let v5 = v4_to_v5(v4).unwrap();
+        assert_eq!(v5["choices"], json!([]));
+        assert_eq!(v5["trail"], json!(["R", "R1"]));
+    }
+
+    #[test]
+    fn test_upgrade_from_v1() {
+        let save_data = upgrade(v1_minimal()).unwrap();
+        assert_eq!(save_data.version, SAVE_VERSION);
+        assert_eq!(save_data.current, "R1");
+        assert_eq!(save_data.saved_at, 0);
+        assert_eq!(save_data.scenario_fingerprint, "");
+        assert_eq!(save_data.page, 0);
+        assert!(save_data.choices.is_empty());
+    }
+
+    #[test]
+    fn test_upgrade_from_v2() {
+        let mut value = v1_minimal();
+        value["version"] = json!(2);
+        for (key, v) in [
+            ("vars", json!({})),
+            ("saved_at", json!(5)),
+            ("play_time_secs", json!(0)),
+            ("excerpt", json!("")),
+            ("scenario_title", json!("Test")),
+        ] {
+            value[key] = v;
+        }
+
+        let save_data = upgrade(value).unwrap();
+        assert_eq!(save_data.saved_at, 5);
+        assert_eq!(save_data.scenario_fingerprint, "");
+    }
+
+    #[test]
+    fn test_current_version_passes_through() {
+        let mut value = v1_minimal();
+        value["version"] = json!(SAVE_VERSION);
+        // 現在の形式では欠けたフィールドを補わない
+        assert!(matches!(upgrade(value), Err(SaveFormatError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_future_version_is_refused() {
+        let mut value = v1_minimal();
+        value["version"] = json!(SAVE_VERSION as u64 + 1);
+
+        let err = upgrade(value).unwrap_err();
+        assert!(err.is_unsupported_version());
+        assert!(err.player_message().contains("新しいバージョン"));
+    }
+
+    #[test]
+    fn test_missing_version() {
+        assert!(matches!(
+            upgrade(json!({"current": "R"})),
+            Err(SaveFormatError::MissingVersion)
+        ));
+        assert!(matches!(
+            upgrade(json!({"version": 0})),
+            Err(SaveFormatError::MissingVersion)
+        ));
+        assert!(matches!(
+            upgrade(json!([1, 2])),
+            Err(SaveFormatError::NotAnObject)
+        ));
+    }
+}
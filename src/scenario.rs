@@ -1,50 +1,299 @@
-use anyhow::{Context, Result};
+use crate::route_id;
+use crate::validation::ScenarioError;
+use anyhow::Result;
 use bevy::prelude::*;
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Scenario {
     pub meta: Meta,
     pub nodes: Vec<Node>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Meta {
     pub title: String,
+    /// 最大の階層数（エンディングはこれより浅くてもよい）
     pub depth: usize,
     pub default_background: String,
-    #[allow(dead_code)]
     pub rain_bgm: String,
-    #[allow(dead_code)]
     pub font: String,
+    /// ストーリー変数の宣言（初期値の型がそのまま変数の型になる）
+    #[serde(default)]
+    pub variables: BTreeMap<String, VarValue>,
+    /// 開始ノードのID
+    #[serde(default = "default_start")]
+    pub start: String,
+    /// 物語の版（指定すると、本文の修正などでは「更新された」とみなさない）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+fn default_start() -> String {
+    route_id::ROOT.to_string()
+}
+
+/// text の中でページを区切る行（前後の空白は無視する）
+pub const PAGE_BREAK: &str = "---";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Node {
     pub id: String,
+    /// 本文（"---" だけの行でページを分けられる。pages を使うなら省略）
+    #[serde(default)]
     pub text: String,
+    /// ページごとの本文（text とはどちらか一方だけ書く）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<String>,
     pub bg: Option<String>,
+    /// 本文を1秒に何文字ずつ出すか（省略時はプレイヤーの設定）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_speed: Option<u32>,
     #[serde(default)]
     pub choices: Vec<Choice>,
     pub ending: Option<Ending>,
 }
 
-#[derive(Deserialize, Clone, Debug)]
+impl Node {
+    /// クリックで1枚ずつ送るページ（少なくとも1枚）
+    ///
+    /// pages があればそれを、無ければ text を PAGE_BREAK の行で分けたものを返す。
+    pub fn pages(&self) -> Vec<String> {
+        if !self.pages.is_empty() {
+            return self.pages.clone();
+        }
+        let mut pages = vec![Vec::new()];
+        for line in self.text.lines() {
+            if line.trim() == PAGE_BREAK {
+                pages.push(Vec::new());
+            } else if let Some(page) = pages.last_mut() {
+                page.push(line);
+            }
+        }
+        pages
+            .into_iter()
+            .map(|lines| lines.join("\n").trim_matches('\n').to_string())
+            .collect()
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages().len()
+    }
+
+    /// page 枚目までの本文を続けたもの（バックログなどで使う）
+    pub fn text_through(&self, page: usize) -> String {
+        let pages = self.pages();
+        let end = (page + 1).min(pages.len());
+        pages[..end].join("\n")
+    }
+
+    /// 全ページの本文を続けたもの
+    pub fn full_text(&self) -> String {
+        self.pages().join("\n")
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Choice {
     pub label: String,
     pub to: String,
+    /// 選択肢を有効にする条件（省略時は常に有効）
+    #[serde(default)]
+    pub condition: Option<Condition>,
+    /// 条件を満たさないときの扱い
+    #[serde(default)]
+    pub on_unmet: UnmetBehavior,
+    /// 選択時に適用する変数操作
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+}
+
+/// ストーリー変数の値
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum VarValue {
+    Bool(bool),
+    Int(i64),
+    Str(String),
 }
 
-#[derive(Deserialize, Clone, Debug)]
+impl VarValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            VarValue::Bool(_) => "bool",
+            VarValue::Int(_) => "int",
+            VarValue::Str(_) => "string",
+        }
+    }
+}
+
+/// 選択肢の表示条件
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Condition {
+    Eq { var: String, value: VarValue },
+    Ne { var: String, value: VarValue },
+    Gt { var: String, value: i64 },
+    Gte { var: String, value: i64 },
+    Lt { var: String, value: i64 },
+    Lte { var: String, value: i64 },
+    All { of: Vec<Condition> },
+    Any { of: Vec<Condition> },
+    Not { of: Box<Condition> },
+}
+
+/// 条件を満たさない選択肢をどう見せるか
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnmetBehavior {
+    #[default]
+    Hide,
+    Disable,
+}
+
+/// 選択肢を選んだときの変数操作
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Effect {
+    Set {
+        var: String,
+        value: VarValue,
+    },
+    Inc {
+        var: String,
+        #[serde(default = "default_inc")]
+        by: i64,
+    },
+    /// 宣言時の初期値に戻す
+    Clear {
+        var: String,
+    },
+}
+
+fn default_inc() -> i64 {
+    1
+}
+
+/// 現在の状態から見た選択肢の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChoiceState {
+    Enabled,
+    Disabled,
+    Hidden,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Ending {
     pub tag: String,
 }
 
-#[derive(Resource, Debug)]
+/// meta.version があればそれを、なければ内容のハッシュを使う
+///
+/// ハッシュは空白や項目の順序に左右されないよう、読み込んだ後の構造を JSON にしてから取る。
+/// 構造体に項目を足すときは、既定値を書き出さないようにしないと全セーブが「更新あり」になる。
+/// ビルドをまたいで変わらないよう、標準ライブラリの Hasher ではなく FNV-1a を使う。
+fn fingerprint_of(scenario: &Scenario) -> String {
+    if let Some(version) = &scenario.meta.version {
+        return format!("v:{version}");
+    }
+    let canonical = serde_json::to_vec(scenario).unwrap_or_default();
+    let hash = canonical
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    format!("h:{hash:016x}")
+}
+
+#[derive(Resource, Debug, Clone)]
 pub struct ScenarioData {
     pub scenario: Scenario,
     pub nodes: HashMap<String, Node>,
+    /// シナリオの内容を表す識別子（セーブと突き合わせて更新を検知する）
+    pub fingerprint: String,
+}
+
+/// セーブを今のシナリオに照らして辿り直した結果
+#[derive(Debug, Clone)]
+pub struct Reconciliation {
+    /// 辿り直せたところまでの状態
+    pub current: Current,
+    /// 辿れずに取り消した選択の数（0 ならセーブのまま再開できる）
+    pub rolled_back: usize,
+}
+
+/// セーブをどう再開するか
+#[derive(Debug, Clone)]
+pub enum ResumePlan {
+    /// 同じシナリオで作られたセーブなのでそのまま再開する
+    Resume(Current),
+    /// シナリオが変わっていたので辿り直した（辿れたところまでで再開する）
+    Replayed(Reconciliation),
+    /// 何も辿れなかったので最初から始める
+    Restart(Reconciliation),
+}
+
+impl ResumePlan {
+    pub fn current(&self) -> &Current {
+        match self {
+            ResumePlan::Resume(current) => current,
+            ResumePlan::Replayed(reconciled) | ResumePlan::Restart(reconciled) => {
+                &reconciled.current
+            }
+        }
+    }
+
+    /// プレイヤーに伝える文言
+    pub fn message(&self) -> Option<String> {
+        match self {
+            ResumePlan::Resume(_) => None,
+            ResumePlan::Replayed(reconciled) | ResumePlan::Restart(reconciled) => {
+                reconciled.message()
+            }
+        }
+    }
+}
+
+impl Reconciliation {
+    /// 巻き戻した場合にプレイヤーへ伝える文言
+    pub fn message(&self) -> Option<String> {
+        match (self.rolled_back, self.current.depth) {
+            (0, _) => None,
+            (_, 0) => Some(
+                "シナリオが更新され、セーブした場面が見つからなかったため最初から始めます"
+                    .to_string(),
+            ),
+            (n, _) => Some(format!(
+                "シナリオが更新されたため、{n}つ前の選択まで戻って再開します"
+            )),
+        }
+    }
+}
+
+/// ルートマップの1マス（開始ノードから辿った経路ごとに1つ）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteMapNode {
+    pub route_id: String,
+    pub node_id: String,
+    pub depth: usize,
+    /// 親のマスの添字（開始ノードは None）
+    pub parent: Option<usize>,
+    /// 親のノードでこのマスへ進む選択肢
+    pub choice_label: Option<String>,
+    pub is_ending: bool,
+}
+
+/// バックログの1行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BacklogEntry {
+    /// trail 上の位置（そのノードに着いたときの階層、RewindTo の行き先になる）
+    pub depth: usize,
+    pub node_id: String,
+    /// 本文（今いるノードでは読んだページまで）
+    pub text: String,
+    /// そのノードで選んだ選択肢（今いるノードでは None）
+    pub choice_label: Option<String>,
 }
 
 #[derive(Resource, Debug, Clone)]
@@ -52,6 +301,21 @@ pub struct Current {
     pub id: String,
     pub depth: usize,
     pub trail: Vec<String>,
+    pub vars: Variables,
+    /// 今のノードで読んでいるページ（0 始まり）
+    pub page: usize,
+    /// 各階層で選んだ選択肢の添字（trail[i] で choices[i] を選んで trail[i + 1] へ進んだ）
+    ///
+    /// 同じノードへ向かう選択肢が複数あっても、どれを選んだかが分かるように持つ。
+    /// 記録の無い古いセーブから作ったものは空になる。
+    pub choices: Vec<usize>,
+}
+
+impl Current {
+    /// 選んだ選択肢がすべて記録されているか
+    pub fn has_recorded_choices(&self) -> bool {
+        self.choices.len() == self.depth
+    }
 }
 
 impl Default for Current {
@@ -60,108 +324,445 @@ impl Default for Current {
             id: "R".to_string(),
             depth: 0,
             trail: vec!["R".to_string()],
+            vars: Variables::default(),
+            page: 0,
+            choices: Vec::new(),
         }
     }
 }
 
+/// プレイ中のストーリー変数
+///
+/// 値が入っていない変数は Meta で宣言された初期値として扱う。
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Variables(BTreeMap<String, VarValue>);
+
+impl Variables {
+    pub fn get(&self, name: &str) -> Option<&VarValue> {
+        self.0.get(name)
+    }
+
+    pub fn set(&mut self, name: &str, value: VarValue) {
+        self.0.insert(name.to_string(), value);
+    }
+}
+
 impl ScenarioData {
-    pub fn load_from_json(json_content: &str) -> Result<Self> {
-        let scenario: Scenario =
-            serde_json::from_str(json_content).context("Failed to parse scenario JSON")?;
+    /// JSON を読み込んで検証し、エラーがあれば ScenarioError::Invalid にする
+    ///
+    /// ゲーム本体と validate コマンドは検証結果を自前で扱うので、ライブラリ利用とテスト向け。
+    #[allow(dead_code)]
+    pub fn load_from_json(json_content: &str) -> Result<Self, ScenarioError> {
+        let scenario_data = Self::parse_json(json_content)?;
+        let report = scenario_data.validate();
+        if report.has_errors() {
+            return Err(ScenarioError::Invalid(report));
+        }
+        for issue in report.warnings() {
+            warn!("{}", issue);
+        }
+
+        Ok(scenario_data)
+    }
+
+    /// JSON を読み込むだけで検証はしない（検証結果を別に扱いたい呼び出し元向け）
+    pub fn parse_json(json_content: &str) -> Result<Self, serde_json::Error> {
+        let scenario: Scenario = serde_json::from_str(json_content)?;
 
         let mut nodes = HashMap::new();
         for node in &scenario.nodes {
             nodes.insert(node.id.clone(), node.clone());
         }
 
-        let scenario_data = Self { scenario, nodes };
-        scenario_data.validate()?;
+        let fingerprint = fingerprint_of(&scenario);
+        Ok(Self {
+            scenario,
+            nodes,
+            fingerprint,
+        })
+    }
 
-        Ok(scenario_data)
+    /// 開始ノードから辿れるノードのID
+    pub fn reachable_ids(&self) -> HashSet<String> {
+        self.reachable_from(&self.scenario.meta.start)
     }
 
-    pub fn validate(&self) -> Result<()> {
-        self.validate_references()?;
-        self.validate_endings();
-        Ok(())
+    /// 指定したノード（自身を含む）から辿れるノードのID
+    fn reachable_from(&self, start: &str) -> HashSet<String> {
+        let mut reachable = HashSet::new();
+        let mut stack = vec![start.to_string()];
+        while let Some(id) = stack.pop() {
+            let Some(node) = self.nodes.get(&id) else {
+                continue;
+            };
+            if reachable.insert(id) {
+                stack.extend(node.choices.iter().map(|c| c.to.clone()));
+            }
+        }
+        reachable
     }
 
-    fn validate_references(&self) -> Result<()> {
-        for node in &self.scenario.nodes {
-            for choice in &node.choices {
-                if !self.nodes.contains_key(&choice.to) {
-                    return Err(anyhow::anyhow!(
-                        "Invalid reference: Node '{}' references non-existent node '{}'",
-                        node.id,
-                        choice.to
-                    ));
-                }
+    /// 開始ノードから辿れるエンディングのルートIDを列挙する
+    ///
+    /// ルートIDは辿った選択肢の列から求めるので、合流するノードは経路ごとに別のIDになる。
+    /// ループは1周目で打ち切る。途中のノードが欠けている場合は、その地点のIDをそのまま含める。
+    /// 件数や個別の判定だけなら、経路を列挙しない ending_count・is_ending_route を使う。
+    pub fn ending_ids(&self) -> Vec<String> {
+        let mut ending_ids = Vec::new();
+        let mut on_path = HashSet::new();
+        self.collect_ending_ids(
+            &self.scenario.meta.start,
+            route_id::ROOT.to_string(),
+            &mut on_path,
+            &mut ending_ids,
+        );
+        ending_ids
+    }
+
+    fn collect_ending_ids(
+        &self,
+        id: &str,
+        route: String,
+        on_path: &mut HashSet<String>,
+        out: &mut Vec<String>,
+    ) {
+        let node = match self.nodes.get(id) {
+            Some(node) if node.ending.is_none() && !node.choices.is_empty() => node,
+            _ => {
+                out.push(route);
+                return;
             }
+        };
+
+        on_path.insert(id.to_string());
+        let branching = node.choices.len();
+        for (i, choice) in node.choices.iter().enumerate() {
+            if on_path.contains(&choice.to) {
+                continue;
+            }
+            let child = route_id::child_for_choice(&route, i, branching);
+            self.collect_ending_ids(&choice.to, child, on_path, out);
         }
-        Ok(())
+        on_path.remove(id);
     }
 
-    fn validate_endings(&self) {
-        let expected_endings = 2_usize.pow(self.scenario.meta.depth as u32);
-        let mut actual_endings = 0;
-        let mut missing_endings = Vec::new();
+    /// ending_ids の件数を、全経路を列挙せずに数える
+    ///
+    /// 合流の多いシナリオでは経路の数が指数的に増えるので、ノードごとの件数をメモして使い回す。
+    /// ループを打ち切る都合で、先の件数は経路上のノードのうちそこから戻ってこられるものにも左右されるため、
+    /// ノードIDとその組をキーにする。
+    pub fn ending_count(&self) -> usize {
+        let mut on_path = HashSet::new();
+        let mut reachable = HashMap::new();
+        let mut memo = HashMap::new();
+        self.count_endings(
+            &self.scenario.meta.start,
+            &mut on_path,
+            &mut reachable,
+            &mut memo,
+        )
+    }
 
-        for ending_id in self.generate_ending_ids() {
-            if let Some(node) = self.nodes.get(&ending_id) {
-                if node.ending.is_some() {
-                    actual_endings += 1;
-                } else {
-                    missing_endings.push(ending_id);
-                }
-            } else {
-                missing_endings.push(ending_id);
+    fn count_endings(
+        &self,
+        id: &str,
+        on_path: &mut HashSet<String>,
+        reachable: &mut HashMap<String, HashSet<String>>,
+        memo: &mut HashMap<(String, Vec<String>), usize>,
+    ) -> usize {
+        let node = match self.nodes.get(id) {
+            Some(node) if node.ending.is_none() && !node.choices.is_empty() => node,
+            _ => return 1,
+        };
+
+        let from_here = reachable
+            .entry(id.to_string())
+            .or_insert_with(|| self.reachable_from(id));
+        let mut loop_state: Vec<String> = on_path
+            .iter()
+            .filter(|visited| from_here.contains(*visited))
+            .cloned()
+            .collect();
+        loop_state.sort();
+        let key = (id.to_string(), loop_state);
+        if let Some(&count) = memo.get(&key) {
+            return count;
+        }
+
+        on_path.insert(id.to_string());
+        let mut count = 0usize;
+        for choice in &node.choices {
+            if on_path.contains(&choice.to) {
+                continue;
             }
+            count = count.saturating_add(self.count_endings(&choice.to, on_path, reachable, memo));
         }
+        on_path.remove(id);
+        memo.insert(key, count);
+        count
+    }
 
-        if actual_endings != expected_endings {
-            warn!(
-                "Expected {} endings but found {}. Missing endings: {:?}",
-                expected_endings, actual_endings, missing_endings
+    /// ending_ids に含まれるルートIDかどうかを、そのルートだけ辿って調べる
+    pub fn is_ending_route(&self, route: &str) -> bool {
+        let Some(digits) = route_id::digits(route) else {
+            return false;
+        };
+        let mut on_path = HashSet::new();
+        let mut id = self.scenario.meta.start.as_str();
+        for branch in digits {
+            let Some(node) = self.nodes.get(id) else {
+                return false;
+            };
+            let branching = node.choices.len();
+            if node.ending.is_some() || branch >= branching {
+                return false;
+            }
+            on_path.insert(id);
+            let next = node.choices[branching - 1 - branch].to.as_str();
+            if on_path.contains(next) {
+                return false;
+            }
+            id = next;
+        }
+        self.nodes
+            .get(id)
+            .is_none_or(|node| node.ending.is_some() || node.choices.is_empty())
+    }
+
+    /// 開始ノードからの木を、親が子より先に来る順（深さ優先）で並べる
+    ///
+    /// ending_ids と同じく合流するノードは経路ごとに別のマスになり、ループは1周目で打ち切る。
+    pub fn route_tree(&self) -> Vec<RouteMapNode> {
+        let mut tree = Vec::new();
+        let mut on_path = HashSet::new();
+        self.collect_route_tree(
+            &self.scenario.meta.start,
+            route_id::ROOT.to_string(),
+            None,
+            None,
+            &mut on_path,
+            &mut tree,
+        );
+        tree
+    }
+
+    fn collect_route_tree(
+        &self,
+        id: &str,
+        route: String,
+        parent: Option<usize>,
+        choice_label: Option<String>,
+        on_path: &mut HashSet<String>,
+        out: &mut Vec<RouteMapNode>,
+    ) {
+        let node = self.nodes.get(id);
+        let index = out.len();
+        out.push(RouteMapNode {
+            depth: on_path.len(),
+            route_id: route.clone(),
+            node_id: id.to_string(),
+            parent,
+            choice_label,
+            is_ending: node.is_some_and(|node| node.ending.is_some()),
+        });
+        let Some(node) = node.filter(|node| node.ending.is_none()) else {
+            return;
+        };
+
+        on_path.insert(id.to_string());
+        let branching = node.choices.len();
+        for (i, choice) in node.choices.iter().enumerate() {
+            if on_path.contains(&choice.to) {
+                continue;
+            }
+            let child = route_id::child_for_choice(&route, i, branching);
+            self.collect_route_tree(
+                &choice.to,
+                child,
+                Some(index),
+                Some(choice.label.clone()),
+                on_path,
+                out,
             );
         }
+        on_path.remove(id);
     }
 
-    fn generate_ending_ids(&self) -> Vec<String> {
-        let depth = self.scenario.meta.depth;
-        let mut ending_ids = Vec::new();
+    /// Current の trail と選んだ選択肢からルートIDを求める
+    ///
+    /// ending_ids・route_tree はループを1周目で打ち切るので、それに合わせて
+    /// 一度通ったノードに戻ったら、そのノードに最初に着いたときのルートIDに戻す（回った分は数えない）。
+    /// 選んだ選択肢の記録が無い、または trail がシナリオと食い違う場合は None。
+    pub fn route_id_of(&self, current: &Current) -> Option<String> {
+        if !current.has_recorded_choices() || current.trail.len() != current.depth + 1 {
+            return None;
+        }
+        // ループを除いた経路上のノードと、そこに着いたときのルートID
+        let mut path = vec![(current.trail[0].as_str(), route_id::ROOT.to_string())];
+        for (pair, &index) in current.trail.windows(2).zip(&current.choices) {
+            let node = self.get_node(&pair[0])?;
+            node.choices.get(index).filter(|c| c.to == pair[1])?;
+            if let Some(visited) = path.iter().position(|(id, _)| *id == pair[1]) {
+                path.truncate(visited + 1);
+                continue;
+            }
+            let (_, route) = path.last()?;
+            let child = route_id::child_for_choice(route, index, node.choices.len());
+            path.push((pair[1].as_str(), child));
+        }
+        path.pop().map(|(_, route)| route)
+    }
+
+    /// trail に沿って、訪れたノードの本文とそこで選んだ選択肢を古い順に並べる
+    ///
+    /// 今いるノードの本文は読んでいるページまで。
+    /// 選んだ選択肢は Current::choices の記録から引く（記録が無ければ同じノードへ向かう先頭のもの）。
+    /// シナリオの更新で消えたノードは飛ばす。
+    pub fn backlog(&self, current: &Current) -> Vec<BacklogEntry> {
+        current
+            .trail
+            .iter()
+            .enumerate()
+            .filter_map(|(i, id)| {
+                let node = self.get_node(id)?;
+                let next = current.trail.get(i + 1);
+                let choice_label = next.and_then(|next| {
+                    current
+                        .choices
+                        .get(i)
+                        .and_then(|&index| node.choices.get(index))
+                        .filter(|c| &c.to == next)
+                        .or_else(|| node.choices.iter().find(|c| &c.to == next))
+                        .map(|c| c.label.clone())
+                });
+                // 今いるノードはまだ読んでいないページを出さない
+                let text = match next {
+                    Some(_) => node.full_text(),
+                    None => node.text_through(current.page),
+                };
+                Some(BacklogEntry {
+                    depth: i,
+                    node_id: id.clone(),
+                    text,
+                    choice_label,
+                })
+            })
+            .collect()
+    }
 
-        for i in 0..(2_usize.pow(depth as u32)) {
-            let mut id = "R".to_string();
-            for bit in (0..depth).rev() {
-                id.push_str(&((i >> bit) & 1).to_string());
+    /// ルートIDの枝番号に従って開始ノードから transition で辿った Current
+    ///
+    /// 今のシナリオで辿れない（枝が無い・条件を満たさない）ルートIDなら None。
+    pub fn current_for_route(&self, route: &str) -> Option<Current> {
+        let mut current = self.initial_current();
+        for branch in route_id::digits(route)? {
+            let branching = self.get_node(&current.id)?.choices.len();
+            if branch >= branching {
+                return None;
             }
-            ending_ids.push(id);
+            let choice_index = branching - 1 - branch;
+            current = self.transition(&current, choice_index).ok()?;
         }
+        Some(current)
+    }
 
-        ending_ids
+    /// 変数の現在値（未設定なら宣言時の初期値）
+    pub fn var_value<'a>(&'a self, current: &'a Current, name: &str) -> Option<&'a VarValue> {
+        current
+            .vars
+            .get(name)
+            .or_else(|| self.scenario.meta.variables.get(name))
+    }
+
+    /// 開始ノードに置き、宣言された初期値で変数を埋めた新規ゲーム用の Current
+    pub fn initial_current(&self) -> Current {
+        let start = &self.scenario.meta.start;
+        let mut current = Current {
+            id: start.clone(),
+            trail: vec![start.clone()],
+            ..Current::default()
+        };
+        for (name, value) in &self.scenario.meta.variables {
+            current.vars.set(name, value.clone());
+        }
+        current
+    }
+
+    pub fn evaluate(&self, current: &Current, condition: &Condition) -> bool {
+        let int_of = |var: &str| match self.var_value(current, var) {
+            Some(VarValue::Int(n)) => Some(*n),
+            _ => None,
+        };
+        match condition {
+            Condition::Eq { var, value } => self.var_value(current, var) == Some(value),
+            Condition::Ne { var, value } => self.var_value(current, var) != Some(value),
+            Condition::Gt { var, value } => int_of(var).is_some_and(|n| n > *value),
+            Condition::Gte { var, value } => int_of(var).is_some_and(|n| n >= *value),
+            Condition::Lt { var, value } => int_of(var).is_some_and(|n| n < *value),
+            Condition::Lte { var, value } => int_of(var).is_some_and(|n| n <= *value),
+            Condition::All { of } => of.iter().all(|c| self.evaluate(current, c)),
+            Condition::Any { of } => of.iter().any(|c| self.evaluate(current, c)),
+            Condition::Not { of } => !self.evaluate(current, of),
+        }
+    }
+
+    pub fn choice_state(&self, current: &Current, choice: &Choice) -> ChoiceState {
+        match &choice.condition {
+            Some(condition) if !self.evaluate(current, condition) => match choice.on_unmet {
+                UnmetBehavior::Hide => ChoiceState::Hidden,
+                UnmetBehavior::Disable => ChoiceState::Disabled,
+            },
+            _ => ChoiceState::Enabled,
+        }
+    }
+
+    fn apply_effects(&self, vars: &mut Variables, effects: &[Effect]) {
+        for effect in effects {
+            match effect {
+                Effect::Set { var, value } => vars.set(var, value.clone()),
+                Effect::Inc { var, by } => {
+                    let base = vars
+                        .get(var)
+                        .or_else(|| self.scenario.meta.variables.get(var));
+                    match base {
+                        Some(VarValue::Int(n)) => {
+                            let next = n.saturating_add(*by);
+                            vars.set(var, VarValue::Int(next));
+                        }
+                        _ => warn!("Cannot increment non-integer variable '{}'", var),
+                    }
+                }
+                Effect::Clear { var } => match self.scenario.meta.variables.get(var) {
+                    Some(initial) => vars.set(var, initial.clone()),
+                    None => warn!("Cannot clear undeclared variable '{}'", var),
+                },
+            }
+        }
     }
 
     pub fn get_node(&self, id: &str) -> Option<&Node> {
         self.nodes.get(id)
     }
 
-    /// ノードが存在しない場合は警告を出してroot('R')にフォールバックする
+    /// ノードが存在しない場合は警告を出して開始ノード（既定は'R'）にフォールバックする
     pub fn get_node_or_fallback(&self, id: &str) -> &Node {
         if let Some(node) = self.nodes.get(id) {
             node
         } else {
+            let start = &self.scenario.meta.start;
             warn!(
                 key = "scenario.node_missing",
                 id = %id,
-                "Node not found, falling back to root 'R'"
+                "Node not found, falling back to start '{}'", start
             );
-            // root('R')が存在しない場合は最初のノードを返す（設計上の問題を回避）
-            if let Some(root_node) = self.nodes.get("R") {
+            // 開始ノードが存在しない場合は最初のノードを返す（設計上の問題を回避）
+            if let Some(root_node) = self.nodes.get(start) {
                 root_node
             } else {
                 error!(
                     key = "scenario.root_missing",
-                    "Root node 'R' missing, using first available node"
+                    "Start node '{}' missing, using first available node", start
                 );
                 // 最後の手段：最初に見つかったノードを返す
                 self.scenario.nodes.first()
@@ -186,25 +787,204 @@ impl ScenarioData {
             ));
         }
 
-        let next_id = &node.choices[choice_index].to;
-        let new_depth = next_id.len() - 1; // "R"を除く桁数
+        let choice = &node.choices[choice_index];
+        if self.choice_state(current, choice) != ChoiceState::Enabled {
+            return Err(anyhow::anyhow!(
+                "Choice {} of node {} is not available",
+                choice_index,
+                current.id
+            ));
+        }
+
+        // 階層は選んだ選択肢の数（ノードIDとは無関係）
+        let next_id = &choice.to;
+        let new_depth = current.depth + 1;
         let mut new_trail = current.trail.clone();
         new_trail.push(next_id.clone());
 
+        let mut new_vars = current.vars.clone();
+        self.apply_effects(&mut new_vars, &choice.effects);
+
         Ok(Current {
             id: next_id.clone(),
             depth: new_depth,
             trail: new_trail,
+            vars: new_vars,
+            page: 0,
+            choices: current
+                .choices
+                .iter()
+                .copied()
+                .chain([choice_index])
+                .collect(),
         })
     }
 
-    pub fn is_ending(&self, current: &Current) -> bool {
-        if current.depth == self.scenario.meta.depth {
-            if let Some(node) = self.get_node(&current.id) {
-                return node.ending.is_some();
+    /// セーブされた trail を開始ノードから、記録された選択肢の transition で辿り直す
+    ///
+    /// シナリオの更新でノードが消えたり選択肢が変わったりしていれば、辿れた最後のノードで打ち切る。
+    /// 変数も選択肢の効果から計算し直すので、セーブ側の値は使わない。
+    /// ただし選択肢の記録が無い古いセーブで、どの選択肢を選んだか決められない場合は、
+    /// 最後まで辿れたときに限りセーブ側の変数を残す（推測した選択肢の効果で上書きしない）。
+    /// 最後まで辿れたときだけ読んでいたページを引き継ぐ（ページが減っていれば最後のページ）。
+    pub fn reconcile(&self, saved: &Current) -> Reconciliation {
+        let mut current = self.initial_current();
+        let steps = saved.trail.len().saturating_sub(1);
+
+        if saved.trail.first() != Some(&current.id) {
+            return Reconciliation {
+                current,
+                rolled_back: steps,
+            };
+        }
+
+        let recorded = saved.has_recorded_choices();
+        let mut ambiguous = false;
+        for (step, next_id) in saved.trail.iter().skip(1).enumerate() {
+            let next = if recorded {
+                self.replay_choice(&current, saved.choices[step], next_id)
+            } else {
+                ambiguous |= self.choices_leading_to(&current, next_id) > 1;
+                self.replay_step(&current, next_id)
+            };
+            let Some(next) = next else {
+                break;
+            };
+            current = next;
+        }
+
+        let rolled_back = steps - current.depth;
+        if rolled_back == 0 {
+            current.page = self.clamp_page(&current.id, saved.page);
+            if ambiguous {
+                current.vars = saved.vars.clone();
             }
         }
-        false
+        Reconciliation {
+            current,
+            rolled_back,
+        }
+    }
+
+    /// steps 回前の選択肢を選ぶ直前まで戻した Current
+    ///
+    /// 変数は戻した先までの選択肢の効果から計算し直す。選び直せるよう、そのノードの最後のページに置く。
+    /// 今のシナリオで辿り直せない trail（ホットリロードで変わったなど）や、
+    /// 選んだ選択肢の記録が無い（変数を計算し直せない）場合は戻さずにエラーにする。
+    pub fn rewind(&self, current: &Current, steps: usize) -> Result<Current> {
+        if steps > current.depth
+            || current.trail.len() != current.depth + 1
+            || !current.has_recorded_choices()
+        {
+            return Err(anyhow::anyhow!(
+                "Cannot rewind {} step(s) from {} (depth {})",
+                steps,
+                current.id,
+                current.depth
+            ));
+        }
+
+        let target_depth = current.depth - steps;
+        let truncated = Current {
+            id: current.trail[target_depth].clone(),
+            depth: target_depth,
+            trail: current.trail[..=target_depth].to_vec(),
+            vars: current.vars.clone(),
+            page: usize::MAX,
+            choices: current.choices.iter().copied().take(target_depth).collect(),
+        };
+        let reconciled = self.reconcile(&truncated);
+        if reconciled.rolled_back > 0 {
+            return Err(anyhow::anyhow!(
+                "Trail to {} no longer matches the scenario",
+                truncated.id
+            ));
+        }
+        Ok(reconciled.current)
+    }
+
+    /// そのノードにあるページの範囲に収めたページ番号
+    pub fn clamp_page(&self, id: &str, page: usize) -> usize {
+        let count = self.get_node(id).map_or(1, Node::page_count);
+        page.min(count - 1)
+    }
+
+    /// セーブに記録されたシナリオの識別子と見比べて、再開の仕方を決める
+    ///
+    /// 同じシナリオならセーブのまま再開し、違う（または記録のない古いセーブ）なら trail を辿り直す。
+    /// 選んだ選択肢の記録が無いセーブも、辿り直して記録を作る。
+    pub fn plan_resume(&self, saved: &Current, saved_fingerprint: &str) -> ResumePlan {
+        if saved_fingerprint == self.fingerprint
+            && self.get_node(&saved.id).is_some()
+            && saved.has_recorded_choices()
+        {
+            return ResumePlan::Resume(saved.clone());
+        }
+
+        let reconciled = self.reconcile(saved);
+        if reconciled.rolled_back > 0 && reconciled.current.depth == 0 {
+            ResumePlan::Restart(reconciled)
+        } else {
+            ResumePlan::Replayed(reconciled)
+        }
+    }
+
+    /// 記録された choice_index の選択肢を選び直す（今のシナリオでも next_id へ進む場合だけ）
+    fn replay_choice(
+        &self,
+        current: &Current,
+        choice_index: usize,
+        next_id: &str,
+    ) -> Option<Current> {
+        self.get_node(next_id)?;
+        self.transition(current, choice_index)
+            .ok()
+            .filter(|next| next.id == next_id)
+    }
+
+    /// 今選べる選択肢のうち next_id へ進むものの数
+    fn choices_leading_to(&self, current: &Current, next_id: &str) -> usize {
+        self.get_node(&current.id).map_or(0, |node| {
+            node.choices
+                .iter()
+                .filter(|choice| {
+                    choice.to == next_id
+                        && self.choice_state(current, choice) == ChoiceState::Enabled
+                })
+                .count()
+        })
+    }
+
+    /// 選択肢の記録が無いとき、next_id へ進める選択肢を選んだことにする（同じ行き先が複数あれば選べる先頭のもの）
+    fn replay_step(&self, current: &Current, next_id: &str) -> Option<Current> {
+        self.get_node(next_id)?;
+        let node = self.get_node(&current.id)?;
+        node.choices
+            .iter()
+            .enumerate()
+            .filter(|(_, choice)| choice.to == next_id)
+            .find_map(|(index, _)| self.transition(current, index).ok())
+    }
+
+    /// 今のノードで選べる選択肢があるか（エンディングや選択肢の無いノードでは true）
+    ///
+    /// 条件でどの選択肢も選べないと先へ進めないので、画面ではすべてを無効にして見せる。
+    pub fn has_available_choice(&self, current: &Current) -> bool {
+        let Some(node) = self.get_node(&current.id) else {
+            return true;
+        };
+        node.ending.is_some()
+            || node.choices.is_empty()
+            || node
+                .choices
+                .iter()
+                .any(|choice| self.choice_state(current, choice) == ChoiceState::Enabled)
+    }
+
+    /// エンディングを持つノードにいるか（階層は問わない）
+    pub fn is_ending(&self, current: &Current) -> bool {
+        self.get_node(&current.id)
+            .is_some_and(|node| node.ending.is_some())
     }
 }
 
@@ -298,6 +1078,9 @@ mod tests {
             id: "R11".to_string(),
             depth: 2,
             trail: vec!["R".to_string(), "R1".to_string(), "R11".to_string()],
+            vars: Variables::default(),
+            page: 0,
+            choices: vec![0, 0],
         };
 
         assert!(scenario_data.is_ending(&ending_current));
@@ -332,4 +1115,768 @@ mod tests {
         let fallback_node = scenario_data.get_node_or_fallback("NONEXISTENT");
         assert_eq!(fallback_node.id, "R");
     }
+
+    fn variables_scenario_json() -> &'static str {
+        r#"{
+          "meta": {
+            "title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": "",
+            "variables": {"has_umbrella": false, "courage": 0, "mood": "calm"}
+          },
+          "nodes": [
+            {
+              "id": "R",
+              "text": "root",
+              "choices": [
+                {
+                  "label": "傘を持つ",
+                  "to": "R2",
+                  "effects": [
+                    {"op": "set", "var": "has_umbrella", "value": true},
+                    {"op": "inc", "var": "courage", "by": 2},
+                    {"op": "clear", "var": "mood"}
+                  ]
+                },
+                {
+                  "label": "傘をさす",
+                  "to": "R1",
+                  "condition": {"op": "eq", "var": "has_umbrella", "value": true},
+                  "on_unmet": "disable"
+                },
+                {
+                  "label": "走る",
+                  "to": "R0",
+                  "condition": {"op": "gte", "var": "courage", "value": 1}
+                }
+              ]
+            },
+            {"id": "R2", "text": "r2", "ending": {"tag": "A"}},
+            {"id": "R1", "text": "r1", "ending": {"tag": "B"}},
+            {"id": "R0", "text": "r0", "ending": {"tag": "C"}}
+          ]
+        }"#
+    }
+
+    #[test]
+    fn test_initial_current_uses_declared_variables() {
+        let scenario_data = ScenarioData::load_from_json(variables_scenario_json()).unwrap();
+        let current = scenario_data.initial_current();
+
+        assert_eq!(
+            current.vars.get("has_umbrella"),
+            Some(&VarValue::Bool(false))
+        );
+        assert_eq!(current.vars.get("courage"), Some(&VarValue::Int(0)));
+        assert_eq!(
+            current.vars.get("mood"),
+            Some(&VarValue::Str("calm".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_choice_state_by_condition() {
+        let scenario_data = ScenarioData::load_from_json(variables_scenario_json()).unwrap();
+        let current = scenario_data.initial_current();
+        let choices = &scenario_data.get_node("R").unwrap().choices;
+
+        assert_eq!(
+            scenario_data.choice_state(&current, &choices[0]),
+            ChoiceState::Enabled
+        );
+        assert_eq!(
+            scenario_data.choice_state(&current, &choices[1]),
+            ChoiceState::Disabled
+        );
+        assert_eq!(
+            scenario_data.choice_state(&current, &choices[2]),
+            ChoiceState::Hidden
+        );
+
+        // 条件を満たせば有効になる
+        let mut current = current;
+        current.vars.set("has_umbrella", VarValue::Bool(true));
+        current.vars.set("courage", VarValue::Int(1));
+        assert_eq!(
+            scenario_data.choice_state(&current, &choices[1]),
+            ChoiceState::Enabled
+        );
+        assert_eq!(
+            scenario_data.choice_state(&current, &choices[2]),
+            ChoiceState::Enabled
+        );
+    }
+
+    #[test]
+    fn test_transition_applies_effects() {
+        let scenario_data = ScenarioData::load_from_json(variables_scenario_json()).unwrap();
+        let mut current = scenario_data.initial_current();
+        current.vars.set("mood", VarValue::Str("tense".to_string()));
+
+        let next = scenario_data.transition(&current, 0).unwrap();
+        assert_eq!(next.vars.get("has_umbrella"), Some(&VarValue::Bool(true)));
+        assert_eq!(next.vars.get("courage"), Some(&VarValue::Int(2)));
+        assert_eq!(
+            next.vars.get("mood"),
+            Some(&VarValue::Str("calm".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_transition_rejects_unavailable_choice() {
+        let scenario_data = ScenarioData::load_from_json(variables_scenario_json()).unwrap();
+        let current = scenario_data.initial_current();
+
+        assert!(scenario_data.transition(&current, 1).is_err());
+        assert!(scenario_data.transition(&current, 2).is_err());
+    }
+
+    #[test]
+    fn test_undeclared_variable() {
+        let invalid_json = r#"{
+          "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+          "nodes": [
+            {
+              "id": "R",
+              "text": "test",
+              "choices": [{"label": "a", "to": "R1", "effects": [{"op": "set", "var": "x", "value": 1}]}]
+            },
+            {"id": "R1", "text": "end", "ending": {"tag": "A"}}
+          ]
+        }"#;
+
+        let result = ScenarioData::load_from_json(invalid_json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_variable_type_mismatch() {
+        let invalid_json = r#"{
+          "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": "",
+                   "variables": {"flag": false}},
+          "nodes": [
+            {
+              "id": "R",
+              "text": "test",
+              "choices": [{"label": "a", "to": "R1", "effects": [{"op": "inc", "var": "flag"}]}]
+            },
+            {"id": "R1", "text": "end", "ending": {"tag": "A"}}
+          ]
+        }"#;
+
+        let result = ScenarioData::load_from_json(invalid_json);
+        assert!(result.is_err());
+    }
+
+    fn mixed_branching_scenario_json() -> &'static str {
+        r#"{
+          "meta": {"title": "Test", "depth": 2, "default_background": "", "rain_bgm": "", "font": ""},
+          "nodes": [
+            {"id": "R", "text": "root", "choices": [
+              {"label": "a", "to": "R2"}, {"label": "b", "to": "R1"}, {"label": "c", "to": "R0"}
+            ]},
+            {"id": "R2", "text": "r2", "choices": [
+              {"label": "a", "to": "R21"}, {"label": "b", "to": "R20"}
+            ]},
+            {"id": "R1", "text": "r1", "choices": [
+              {"label": "a", "to": "R13"}, {"label": "b", "to": "R12"},
+              {"label": "c", "to": "R11"}, {"label": "d", "to": "R10"}
+            ]},
+            {"id": "R0", "text": "r0", "choices": [
+              {"label": "a", "to": "R01"}, {"label": "b", "to": "R00"}
+            ]},
+            {"id": "R21", "text": "e", "ending": {"tag": "A"}},
+            {"id": "R20", "text": "e", "ending": {"tag": "B"}},
+            {"id": "R13", "text": "e", "ending": {"tag": "C"}},
+            {"id": "R12", "text": "e", "ending": {"tag": "D"}},
+            {"id": "R11", "text": "e", "ending": {"tag": "E"}},
+            {"id": "R10", "text": "e", "ending": {"tag": "F"}},
+            {"id": "R01", "text": "e", "ending": {"tag": "G"}},
+            {"id": "R00", "text": "e", "ending": {"tag": "H"}}
+          ]
+        }"#
+    }
+
+    #[test]
+    fn test_ending_ids_follow_tree_shape() {
+        let scenario_data = ScenarioData::load_from_json(mixed_branching_scenario_json()).unwrap();
+
+        assert_eq!(
+            scenario_data.ending_ids(),
+            vec!["R21", "R20", "R13", "R12", "R11", "R10", "R01", "R00"]
+        );
+
+        // 二択だけのシナリオは従来通り 2^depth
+        let binary = ScenarioData::load_from_json(sample_scenario_json()).unwrap();
+        assert_eq!(binary.ending_ids(), vec!["R11", "R10", "R01", "R00"]);
+    }
+
+    #[test]
+    fn test_transition_with_mixed_branching() {
+        let scenario_data = ScenarioData::load_from_json(mixed_branching_scenario_json()).unwrap();
+        let current = Current::default();
+
+        let next = scenario_data.transition(&current, 1).unwrap();
+        assert_eq!(next.id, "R1");
+        assert_eq!(next.depth, 1);
+
+        let next = scenario_data.transition(&next, 0).unwrap();
+        assert_eq!(next.id, "R13");
+        assert_eq!(next.depth, 2);
+        assert!(scenario_data.is_ending(&next));
+    }
+
+    #[test]
+    fn test_route_id_mismatch() {
+        // 三択なのに二択のIDを振っている
+        let invalid_json = r#"{
+          "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+          "nodes": [
+            {"id": "R", "text": "root", "choices": [
+              {"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}, {"label": "c", "to": "R2"}
+            ]},
+            {"id": "R2", "text": "e", "ending": {"tag": "A"}},
+            {"id": "R1", "text": "e", "ending": {"tag": "B"}},
+            {"id": "R0", "text": "e", "ending": {"tag": "C"}}
+          ]
+        }"#;
+
+        assert!(ScenarioData::load_from_json(invalid_json).is_err());
+    }
+
+    #[test]
+    fn test_early_ending() {
+        let scenario_data = ScenarioData::load_from_json(
+            r#"{
+              "meta": {"title": "Test", "depth": 3, "default_background": "", "rain_bgm": "", "font": ""},
+              "nodes": [
+                {"id": "R", "text": "root", "choices": [{"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}]},
+                {"id": "R1", "text": "early", "ending": {"tag": "早いEND"}},
+                {"id": "R0", "text": "r0", "choices": [{"label": "a", "to": "R01"}, {"label": "b", "to": "R00"}]},
+                {"id": "R01", "text": "e", "ending": {"tag": "A"}},
+                {"id": "R00", "text": "r00", "choices": [{"label": "a", "to": "R001"}, {"label": "b", "to": "R000"}]},
+                {"id": "R001", "text": "e", "ending": {"tag": "B"}},
+                {"id": "R000", "text": "e", "ending": {"tag": "C"}}
+              ]
+            }"#,
+        )
+        .unwrap();
+
+        let next = scenario_data.transition(&Current::default(), 0).unwrap();
+        assert_eq!(next.depth, 1);
+        assert!(scenario_data.is_ending(&next));
+
+        // エンディング数は実際の葉の数
+        assert_eq!(
+            scenario_data.ending_ids(),
+            vec!["R1", "R01", "R001", "R000"]
+        );
+    }
+
+    #[test]
+    fn test_leaf_without_ending() {
+        let invalid_json = r#"{
+          "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+          "nodes": [
+            {"id": "R", "text": "root", "choices": [{"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}]},
+            {"id": "R1", "text": "e", "ending": {"tag": "A"}},
+            {"id": "R0", "text": "stuck"}
+          ]
+        }"#;
+
+        assert!(ScenarioData::load_from_json(invalid_json).is_err());
+    }
+
+    fn graph_scenario_json() -> &'static str {
+        r#"{
+          "meta": {
+            "title": "Test", "depth": 4, "default_background": "", "rain_bgm": "", "font": "",
+            "start": "home",
+            "variables": {"waited": 0}
+          },
+          "nodes": [
+            {"id": "home", "text": "家", "choices": [
+              {"label": "歩く", "to": "station"},
+              {"label": "バス", "to": "bus"}
+            ]},
+            {"id": "bus", "text": "バス", "choices": [{"label": "降りる", "to": "station"}]},
+            {"id": "station", "text": "駅に着く", "choices": [
+              {"label": "待つ", "to": "station", "effects": [{"op": "inc", "var": "waited"}],
+               "condition": {"op": "lt", "var": "waited", "value": 2}},
+              {"label": "乗る", "to": "end"}
+            ]},
+            {"id": "end", "text": "終わり", "ending": {"tag": "駅END"}}
+          ]
+        }"#
+    }
+
+    #[test]
+    fn test_graph_merge_and_guarded_loop() {
+        let scenario_data = ScenarioData::load_from_json(graph_scenario_json()).unwrap();
+
+        let start = scenario_data.initial_current();
+        assert_eq!(start.id, "home");
+        assert_eq!(start.trail, vec!["home"]);
+
+        // ループは条件を満たす間だけ回れる
+        let at_station = scenario_data.transition(&start, 0).unwrap();
+        let waited_once = scenario_data.transition(&at_station, 0).unwrap();
+        let waited_twice = scenario_data.transition(&waited_once, 0).unwrap();
+        assert_eq!(waited_twice.id, "station");
+        assert_eq!(waited_twice.depth, 3);
+        assert!(scenario_data.transition(&waited_twice, 0).is_err());
+
+        let ended = scenario_data.transition(&waited_twice, 1).unwrap();
+        assert!(scenario_data.is_ending(&ended));
+        assert_eq!(ended.choices, vec![0, 0, 0, 1]);
+        // 駅で待った分は数えず、列挙されるルートIDと一致させる
+        let route = scenario_data.route_id_of(&ended).unwrap();
+        assert_eq!(route, "R10");
+        assert!(scenario_data.ending_ids().contains(&route));
+
+        // バスで回り道しても駅に着いた時点のIDは経路ごとに別
+        let by_bus = scenario_data.transition(&start, 1).unwrap();
+        let by_bus = scenario_data.transition(&by_bus, 0).unwrap();
+        let ended = scenario_data.transition(&by_bus, 1).unwrap();
+        assert_eq!(scenario_data.route_id_of(&ended).unwrap(), "R000");
+    }
+
+    #[test]
+    fn test_route_id_of_uses_chosen_choice() {
+        let scenario_data = ScenarioData::load_from_json(
+            r#"{
+              "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": "",
+                       "start": "home"},
+              "nodes": [
+                {"id": "home", "text": "家", "choices": [
+                  {"label": "歩く", "to": "end"}, {"label": "走る", "to": "end"}
+                ]},
+                {"id": "end", "text": "終わり", "ending": {"tag": "END"}}
+              ]
+            }"#,
+        )
+        .unwrap();
+        let start = scenario_data.initial_current();
+
+        // 同じノードへ向かう選択肢でも、選んだ方のルートIDになる
+        let walked = scenario_data.transition(&start, 0).unwrap();
+        let ran = scenario_data.transition(&start, 1).unwrap();
+        assert_eq!(scenario_data.route_id_of(&walked).unwrap(), "R1");
+        assert_eq!(scenario_data.route_id_of(&ran).unwrap(), "R0");
+        assert_eq!(scenario_data.ending_ids(), vec!["R1", "R0"]);
+
+        // 選んだ選択肢の記録が無ければ決められない
+        let mut unrecorded = ran.clone();
+        unrecorded.choices.clear();
+        assert_eq!(scenario_data.route_id_of(&unrecorded), None);
+    }
+
+    #[test]
+    fn test_graph_ending_ids_per_route() {
+        let scenario_data = ScenarioData::load_from_json(graph_scenario_json()).unwrap();
+
+        // 合流したノードでも経路ごとに別のルートになる（ループは1周目で打ち切る）
+        assert_eq!(scenario_data.ending_ids(), vec!["R10", "R000"]);
+        assert_eq!(scenario_data.reachable_ids().len(), 4);
+
+        // 列挙せずに数えた件数・判定も列挙と一致する
+        assert_eq!(scenario_data.ending_count(), 2);
+        assert!(scenario_data.is_ending_route("R10"));
+        assert!(scenario_data.is_ending_route("R000"));
+        assert!(!scenario_data.is_ending_route("R1"));
+        assert!(!scenario_data.is_ending_route("R110"));
+    }
+
+    #[test]
+    fn test_ending_count_with_many_merges() {
+        // 二手に分かれてすぐ合流する菱形を30段つなげる（経路は 2^30 通り）
+        const DIAMONDS: usize = 30;
+        let mut nodes = Vec::new();
+        for i in 0..DIAMONDS {
+            nodes.push(format!(
+                r#"{{"id": "d{i}", "text": "分かれ道", "choices": [
+                  {{"label": "左", "to": "l{i}"}}, {{"label": "右", "to": "r{i}"}}
+                ]}}"#
+            ));
+            for side in ["l", "r"] {
+                nodes.push(format!(
+                    r#"{{"id": "{side}{i}", "text": "道", "choices": [{{"label": "進む", "to": "d{next}"}}]}}"#,
+                    next = i + 1
+                ));
+            }
+        }
+        nodes.push(format!(
+            r#"{{"id": "d{DIAMONDS}", "text": "終わり", "ending": {{"tag": "END"}}}}"#
+        ));
+        let json = format!(
+            r#"{{
+              "meta": {{"title": "Test", "depth": {depth}, "default_background": "", "rain_bgm": "", "font": "",
+                        "start": "d0"}},
+              "nodes": [{nodes}]
+            }}"#,
+            depth = DIAMONDS * 2,
+            nodes = nodes.join(",")
+        );
+        let scenario_data = ScenarioData::load_from_json(&json).unwrap();
+
+        assert_eq!(scenario_data.ending_count(), 1 << DIAMONDS);
+        assert!(scenario_data.is_ending_route(&format!("R{}", "10".repeat(DIAMONDS))));
+        assert!(!scenario_data.is_ending_route(&format!("R{}", "10".repeat(DIAMONDS - 1))));
+    }
+
+    #[test]
+    fn test_route_id_of_tree_matches_node_id() {
+        let scenario_data = ScenarioData::load_from_json(sample_scenario_json()).unwrap();
+        let current = scenario_data.transition(&Current::default(), 1).unwrap();
+        let current = scenario_data.transition(&current, 0).unwrap();
+
+        assert_eq!(scenario_data.route_id_of(&current).unwrap(), current.id);
+    }
+
+    #[test]
+    fn test_backlog_follows_trail() {
+        let scenario_data = ScenarioData::load_from_json(mixed_branching_scenario_json()).unwrap();
+        let current = scenario_data.current_for_route("R13").unwrap();
+
+        let backlog = scenario_data.backlog(&current);
+        let lines: Vec<_> = backlog
+            .iter()
+            .map(|e| (e.text.as_str(), e.choice_label.as_deref()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![("root", Some("b")), ("r1", Some("a")), ("e", None)]
+        );
+
+        // シナリオに無いノードは飛ばす
+        let mut stale = current.clone();
+        stale.trail.insert(1, "GONE".to_string());
+        assert_eq!(scenario_data.backlog(&stale).len(), 3);
+    }
+
+    #[test]
+    fn test_pages_and_backlog_keep_page() {
+        let json = r#"{
+          "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+          "nodes": [
+            {"id": "R", "text": "一枚目\n ---\n二枚目\n続き\n---\n三枚目", "choices": [
+              {"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}
+            ]},
+            {"id": "R1", "pages": ["p1", "p2"], "ending": {"tag": "A"}},
+            {"id": "R0", "text": "e", "ending": {"tag": "B"}}
+          ]
+        }"#;
+        let scenario_data = ScenarioData::load_from_json(json).unwrap();
+        let root = scenario_data.get_node("R").unwrap();
+        assert_eq!(root.pages(), vec!["一枚目", "二枚目\n続き", "三枚目"]);
+        assert_eq!(scenario_data.get_node("R1").unwrap().page_count(), 2);
+        assert_eq!(scenario_data.get_node("R0").unwrap().pages(), vec!["e"]);
+
+        // 今のノードは読んだページまで、通り過ぎたノードは全ページ
+        let mut current = scenario_data.initial_current();
+        current.page = 1;
+        let backlog = scenario_data.backlog(&current);
+        assert_eq!(backlog[0].text, "一枚目\n二枚目\n続き");
+        let next = scenario_data.transition(&current, 0).unwrap();
+        assert_eq!(next.page, 0);
+        let backlog = scenario_data.backlog(&next);
+        assert_eq!(backlog[0].text, root.full_text());
+        assert_eq!(backlog[1].text, "p1");
+
+        // 戻ったノードでは選び直せるよう最後のページから
+        assert_eq!(scenario_data.rewind(&next, 1).unwrap().page, 2);
+        // 保存されたページがシナリオの変更で無くなっていれば最後のページ
+        let mut saved = next.clone();
+        saved.page = 5;
+        assert_eq!(scenario_data.reconcile(&saved).current.page, 1);
+    }
+
+    #[test]
+    fn test_rewind_recomputes_variables() {
+        let json = r#"{
+          "meta": {"title": "Test", "depth": 2, "default_background": "", "rain_bgm": "", "font": "",
+                   "variables": {"courage": 0}},
+          "nodes": [
+            {"id": "R", "text": "root", "choices": [
+              {"label": "a", "to": "R1", "effects": [{"op": "inc", "var": "courage"}]},
+              {"label": "b", "to": "R0"}
+            ]},
+            {"id": "R1", "text": "r1", "choices": [
+              {"label": "a", "to": "R11", "effects": [{"op": "inc", "var": "courage"}]},
+              {"label": "b", "to": "R10"}
+            ]},
+            {"id": "R0", "text": "r0", "choices": [{"label": "a", "to": "R01"}, {"label": "b", "to": "R00"}]},
+            {"id": "R11", "text": "e", "ending": {"tag": "A"}},
+            {"id": "R10", "text": "e", "ending": {"tag": "B"}},
+            {"id": "R01", "text": "e", "ending": {"tag": "C"}},
+            {"id": "R00", "text": "e", "ending": {"tag": "D"}}
+          ]
+        }"#;
+        let scenario_data = ScenarioData::load_from_json(json).unwrap();
+        let end = scenario_data.current_for_route("R11").unwrap();
+        assert_eq!(
+            scenario_data.var_value(&end, "courage"),
+            Some(&VarValue::Int(2))
+        );
+
+        let back = scenario_data.rewind(&end, 1).unwrap();
+        assert_eq!(back.id, "R1");
+        assert_eq!(back.trail, vec!["R", "R1"]);
+        assert_eq!(
+            scenario_data.var_value(&back, "courage"),
+            Some(&VarValue::Int(1))
+        );
+
+        let start = scenario_data.rewind(&end, 2).unwrap();
+        assert_eq!(start.depth, 0);
+        assert_eq!(
+            scenario_data.var_value(&start, "courage"),
+            Some(&VarValue::Int(0))
+        );
+
+        assert!(scenario_data.rewind(&end, 3).is_err());
+        assert_eq!(scenario_data.rewind(&end, 0).unwrap().id, "R11");
+    }
+
+    #[test]
+    fn test_route_tree_matches_ending_ids() {
+        let scenario_data = ScenarioData::load_from_json(mixed_branching_scenario_json()).unwrap();
+        let tree = scenario_data.route_tree();
+
+        // 3 + 2 + 4 + 2 の子と開始ノード
+        assert_eq!(tree.len(), 12);
+        assert_eq!(tree[0].route_id, "R");
+        assert_eq!(tree[0].parent, None);
+        assert_eq!(tree[1].route_id, "R2");
+        assert_eq!(tree[1].choice_label.as_deref(), Some("a"));
+        assert_eq!(tree[2].parent, Some(1));
+        assert_eq!(tree[2].depth, 2);
+
+        let leaves: Vec<_> = tree
+            .iter()
+            .filter(|node| node.is_ending)
+            .map(|node| node.route_id.clone())
+            .collect();
+        assert_eq!(leaves, scenario_data.ending_ids());
+        assert_eq!(leaves.len(), scenario_data.ending_count());
+    }
+
+    #[test]
+    fn test_current_for_route() {
+        let scenario_data = ScenarioData::load_from_json(mixed_branching_scenario_json()).unwrap();
+
+        let current = scenario_data.current_for_route("R13").unwrap();
+        assert_eq!(current.id, "R13");
+        assert_eq!(current.trail, vec!["R", "R1", "R13"]);
+        assert_eq!(scenario_data.route_id_of(&current).unwrap(), "R13");
+
+        // どのエンディングのルートIDからも辿り直せる
+        for route in scenario_data.ending_ids() {
+            let current = scenario_data.current_for_route(&route).unwrap();
+            assert!(scenario_data.is_ending(&current), "{route}");
+        }
+
+        assert!(scenario_data.current_for_route("R3").is_none());
+        assert!(scenario_data.current_for_route("R219").is_none());
+        assert!(scenario_data.current_for_route("START").is_none());
+    }
+
+    #[test]
+    fn test_unguarded_loop() {
+        let invalid_json = r#"{
+          "meta": {"title": "Test", "depth": 3, "default_background": "", "rain_bgm": "", "font": "", "start": "a"},
+          "nodes": [
+            {"id": "a", "text": "a", "choices": [{"label": "x", "to": "b"}, {"label": "y", "to": "end"}]},
+            {"id": "b", "text": "b", "choices": [{"label": "x", "to": "a"}]},
+            {"id": "end", "text": "e", "ending": {"tag": "A"}}
+          ]
+        }"#;
+
+        let Err(ScenarioError::Invalid(report)) = ScenarioData::load_from_json(invalid_json) else {
+            panic!("expected validation error");
+        };
+        let issue = report.errors().next().unwrap();
+        assert_eq!(issue.node.as_deref(), Some("a"));
+        assert_eq!(
+            issue.kind,
+            crate::validation::IssueKind::UnguardedLoop {
+                cycle: vec!["a".to_string(), "b".to_string(), "a".to_string()]
+            }
+        );
+    }
+
+    #[test]
+    fn test_reconcile_intact_trail() {
+        let scenario_data = ScenarioData::load_from_json(graph_scenario_json()).unwrap();
+        let start = scenario_data.initial_current();
+        let station = scenario_data.transition(&start, 0).unwrap();
+        let waited = scenario_data.transition(&station, 0).unwrap();
+
+        // 変数が壊れていても効果から計算し直す
+        let mut saved = waited.clone();
+        saved.vars.set("waited", VarValue::Int(99));
+
+        let reconciled = scenario_data.reconcile(&saved);
+        assert_eq!(reconciled.rolled_back, 0);
+        assert!(reconciled.message().is_none());
+        assert_eq!(reconciled.current.id, "station");
+        assert_eq!(reconciled.current.trail, waited.trail);
+        assert_eq!(
+            reconciled.current.vars.get("waited"),
+            Some(&VarValue::Int(1))
+        );
+    }
+
+    #[test]
+    fn test_reconcile_replays_recorded_choice() {
+        let scenario_data = ScenarioData::load_from_json(
+            r#"{
+              "meta": {"title": "Test", "depth": 2, "default_background": "", "rain_bgm": "", "font": "",
+                       "start": "home", "variables": {"tired": 0}},
+              "nodes": [
+                {"id": "home", "text": "家", "choices": [
+                  {"label": "歩く", "to": "station"},
+                  {"label": "走る", "to": "station", "effects": [{"op": "inc", "var": "tired"}]}
+                ]},
+                {"id": "station", "text": "駅", "choices": [{"label": "乗る", "to": "end"}]},
+                {"id": "end", "text": "終わり", "ending": {"tag": "END"}}
+              ]
+            }"#,
+        )
+        .unwrap();
+        let ran = scenario_data
+            .transition(&scenario_data.initial_current(), 1)
+            .unwrap();
+        let tired = Some(&VarValue::Int(1));
+
+        // 2つ目の選択肢を選んだ記録の通りに辿り直す
+        let reconciled = scenario_data.reconcile(&ran);
+        assert_eq!(reconciled.rolled_back, 0);
+        assert_eq!(reconciled.current.choices, vec![1]);
+        assert_eq!(reconciled.current.vars.get("tired"), tired);
+        assert_eq!(
+            scenario_data.backlog(&ran)[0].choice_label.as_deref(),
+            Some("走る")
+        );
+        let ended = scenario_data.transition(&ran, 0).unwrap();
+        assert_eq!(
+            scenario_data.rewind(&ended, 1).unwrap().vars.get("tired"),
+            tired
+        );
+
+        // 記録の無い古いセーブではどちらか決められないので、セーブの変数を残す
+        let mut legacy = ran.clone();
+        legacy.choices.clear();
+        let reconciled = scenario_data.reconcile(&legacy);
+        assert_eq!(reconciled.rolled_back, 0);
+        assert_eq!(reconciled.current.vars.get("tired"), tired);
+        assert!(scenario_data.rewind(&legacy, 1).is_err());
+    }
+
+    #[test]
+    fn test_reconcile_cuts_at_deleted_node() {
+        let scenario_data = ScenarioData::load_from_json(sample_scenario_json()).unwrap();
+        let saved = Current {
+            id: "R10X".to_string(),
+            depth: 3,
+            trail: vec!["R", "R1", "R10", "R10X"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            vars: Variables::default(),
+            page: 0,
+            choices: vec![0, 1, 0],
+        };
+
+        let reconciled = scenario_data.reconcile(&saved);
+        assert_eq!(reconciled.current.id, "R10");
+        assert_eq!(reconciled.current.depth, 2);
+        assert_eq!(reconciled.current.trail, vec!["R", "R1", "R10"]);
+        assert_eq!(reconciled.rolled_back, 1);
+        assert!(reconciled.message().unwrap().contains("1つ前"));
+    }
+
+    #[test]
+    fn test_reconcile_cuts_at_unavailable_choice() {
+        let scenario_data = ScenarioData::load_from_json(graph_scenario_json()).unwrap();
+
+        // 待てるのは2回まで（3回目の「待つ」は条件を満たさない）
+        let saved = Current {
+            id: "end".to_string(),
+            depth: 5,
+            trail: ["home", "station", "station", "station", "station", "end"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            vars: Variables::default(),
+            page: 0,
+            choices: vec![0, 0, 0, 0, 1],
+        };
+
+        let reconciled = scenario_data.reconcile(&saved);
+        assert_eq!(reconciled.current.depth, 3);
+        assert_eq!(reconciled.current.id, "station");
+        assert_eq!(reconciled.rolled_back, 2);
+    }
+
+    #[test]
+    fn test_reconcile_unknown_start() {
+        let scenario_data = ScenarioData::load_from_json(graph_scenario_json()).unwrap();
+
+        // 開始ノードが変わったシナリオでは最初からになる
+        let saved = Current {
+            id: "R1".to_string(),
+            depth: 1,
+            trail: vec!["R".to_string(), "R1".to_string()],
+            vars: Variables::default(),
+            page: 0,
+            choices: vec![0],
+        };
+
+        let reconciled = scenario_data.reconcile(&saved);
+        assert_eq!(reconciled.current.id, "home");
+        assert_eq!(reconciled.rolled_back, 1);
+        assert!(reconciled.message().unwrap().contains("最初から"));
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_formatting() {
+        let compact: serde_json::Value = serde_json::from_str(sample_scenario_json()).unwrap();
+        let a = ScenarioData::load_from_json(sample_scenario_json()).unwrap();
+        let b = ScenarioData::load_from_json(&compact.to_string()).unwrap();
+        assert_eq!(a.fingerprint, b.fingerprint);
+        assert!(a.fingerprint.starts_with("h:"));
+
+        let edited = sample_scenario_json().replacen("雨。", "雪。", 1);
+        let c = ScenarioData::load_from_json(&edited).unwrap();
+        assert_ne!(a.fingerprint, c.fingerprint);
+    }
+
+    #[test]
+    fn test_fingerprint_prefers_meta_version() {
+        let versioned =
+            sample_scenario_json().replacen(r#""title": "#, r#""version": "1.2", "title": "#, 1);
+        let scenario_data = ScenarioData::load_from_json(&versioned).unwrap();
+        assert_eq!(scenario_data.fingerprint, "v:1.2");
+    }
+
+    #[test]
+    fn test_plan_resume() {
+        let scenario_data = ScenarioData::load_from_json(sample_scenario_json()).unwrap();
+        let saved = scenario_data.transition(&Current::default(), 0).unwrap();
+
+        let plan = scenario_data.plan_resume(&saved, &scenario_data.fingerprint);
+        assert!(matches!(plan, ResumePlan::Resume(_)));
+        assert!(plan.message().is_none());
+
+        // 識別子が違えば辿り直す（辿れれば巻き戻しはない）
+        let plan = scenario_data.plan_resume(&saved, "");
+        assert!(matches!(&plan, ResumePlan::Replayed(r) if r.rolled_back == 0));
+        assert_eq!(plan.current().id, saved.id);
+
+        let mut stale = saved.clone();
+        stale.trail = vec!["R".to_string(), "R9".to_string()];
+        stale.id = "R9".to_string();
+        let plan = scenario_data.plan_resume(&stale, "h:0000000000000000");
+        assert!(matches!(plan, ResumePlan::Restart(_)));
+        assert_eq!(plan.current().id, "R");
+    }
 }
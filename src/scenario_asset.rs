@@ -0,0 +1,295 @@
+use crate::scenario::{Current, ScenarioData};
+use crate::states::ShowNotice;
+use crate::validation::ValidationReport;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoadFailedEvent, AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use thiserror::Error;
+
+/// AssetServer 経由で読み込んだシナリオと、その検証結果
+///
+/// 検証エラーがあってもアセット自体は読み込み済みとし、採用するかどうかは利用側で決める。
+#[derive(Asset, TypePath, Debug)]
+pub struct ScenarioAsset {
+    pub data: ScenarioData,
+    pub report: ValidationReport,
+}
+
+impl ScenarioAsset {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ScenarioLoaderError> {
+        let json_content = std::str::from_utf8(bytes)?;
+        let data = ScenarioData::parse_json(json_content)?;
+        let report = data.validate();
+        Ok(Self { data, report })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ScenarioLoaderError {
+    #[error("failed to read scenario: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("scenario is not valid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("Failed to parse scenario JSON: {0}")]
+    Parse(#[from] serde_json::Error),
+}
+
+/// シナリオ JSON 用のローダー
+///
+/// 拡張子は登録せず、`asset_server.load::<ScenarioAsset>` のように型で選ばれる。
+/// "json" を登録すると、他の JSON アセットまでシナリオとして読もうとしてしまう。
+#[derive(Default)]
+pub struct ScenarioLoader;
+
+impl AssetLoader for ScenarioLoader {
+    type Asset = ScenarioAsset;
+    type Settings = ();
+    type Error = ScenarioLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        ScenarioAsset::from_bytes(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[]
+    }
+}
+
+/// 読み込み中・読み込み済みのシナリオのハンドル（ホットリロードの監視にも使う）
+#[derive(Resource)]
+pub struct ScenarioHandle(pub Handle<ScenarioAsset>);
+
+/// シナリオファイルが書き換えられたら ScenarioData を差し替える
+///
+/// 検証エラーのある版は採用せず、直前のシナリオで遊び続けられるようにする。
+/// 差し替えた場合は ScenarioData の変更検知で表示中のノードが描き直される。
+/// 表示中のノードが無くなっていたら、セーブの再開と同じく辿れるところまで戻す。
+pub fn apply_scenario_reload(
+    mut asset_events: EventReader<AssetEvent<ScenarioAsset>>,
+    mut failed_events: EventReader<AssetLoadFailedEvent<ScenarioAsset>>,
+    scenario_handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<ScenarioAsset>>,
+    mut scenario_data: ResMut<ScenarioData>,
+    mut current: ResMut<Current>,
+    mut notices: EventWriter<ShowNotice>,
+) {
+    for event in failed_events.read() {
+        error!(key = "scenario.reload_failed", "{}", event.error);
+    }
+
+    let Some(scenario_handle) = scenario_handle else {
+        return;
+    };
+    // 同じフレームに複数届いても差し替えは1回でよい
+    let modified = asset_events
+        .read()
+        .filter(|event| event.is_modified(scenario_handle.0.id()))
+        .count();
+    if modified == 0 {
+        return;
+    }
+    let Some(asset) = scenarios.get(&scenario_handle.0) else {
+        return;
+    };
+
+    if asset.report.has_errors() {
+        for issue in asset.report.errors() {
+            error!(key = "scenario.reload_rejected", "{}", issue);
+        }
+        warn!("Scenario reload rejected, keeping the previous scenario");
+        return;
+    }
+    for issue in asset.report.warnings() {
+        warn!("{}", issue);
+    }
+
+    *scenario_data = asset.data.clone();
+    if scenario_data.get_node(&current.id).is_none() {
+        let reconciled = scenario_data.reconcile(&current);
+        warn!(
+            "Node {} no longer exists after reload, rolled back {} step(s) to {}",
+            current.id, reconciled.rolled_back, reconciled.current.id
+        );
+        if let Some(message) = reconciled.message() {
+            notices.send(ShowNotice(message));
+        }
+        *current = reconciled.current;
+    }
+    info!(
+        "Scenario reloaded ({} endings)",
+        scenario_data.ending_count()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r#"{
+      "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+      "nodes": [
+        {"id": "R", "text": "root", "choices": [{"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}]},
+        {"id": "R1", "text": "e", "ending": {"tag": "A"}},
+        {"id": "R0", "text": "e", "ending": {"tag": "B"}}
+      ]
+    }"#;
+
+    fn reload_app(initial: &str) -> (App, Handle<ScenarioAsset>) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(AssetPlugin::default())
+            .init_asset::<ScenarioAsset>()
+            .add_event::<ShowNotice>()
+            .add_systems(Update, apply_scenario_reload);
+
+        let data = ScenarioData::load_from_json(initial).unwrap();
+        app.insert_resource(data.initial_current());
+        app.insert_resource(data);
+
+        let asset = ScenarioAsset::from_bytes(initial.as_bytes()).unwrap();
+        let handle = app
+            .world_mut()
+            .resource_mut::<Assets<ScenarioAsset>>()
+            .add(asset);
+        app.insert_resource(ScenarioHandle(handle.clone()));
+        app.update();
+        (app, handle)
+    }
+
+    fn replace(app: &mut App, handle: &Handle<ScenarioAsset>, json: &str) {
+        let asset = ScenarioAsset::from_bytes(json.as_bytes()).unwrap();
+        app.world_mut()
+            .resource_mut::<Assets<ScenarioAsset>>()
+            .insert(handle, asset);
+        // AssetEvent は Last で送られるので、受け取るのは次のフレーム
+        app.update();
+        app.update();
+    }
+
+    #[test]
+    fn test_loader_keeps_validation_report() {
+        let asset = ScenarioAsset::from_bytes(VALID.as_bytes()).unwrap();
+        assert!(asset.report.is_empty());
+
+        let invalid = VALID.replace(r#""to": "R0""#, r#""to": "NOPE""#);
+        let asset = ScenarioAsset::from_bytes(invalid.as_bytes()).unwrap();
+        assert!(asset.report.has_errors());
+
+        assert!(matches!(
+            ScenarioAsset::from_bytes(b"{ not json"),
+            Err(ScenarioLoaderError::Parse(_))
+        ));
+        assert!(matches!(
+            ScenarioAsset::from_bytes(b"\xff\xfe"),
+            Err(ScenarioLoaderError::Utf8(_))
+        ));
+    }
+
+    #[test]
+    fn test_loader_is_chosen_by_asset_type() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("scenario.json"), VALID).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(AssetPlugin {
+                file_path: temp_dir.path().to_string_lossy().into_owned(),
+                ..default()
+            })
+            .init_asset::<ScenarioAsset>()
+            .init_asset_loader::<ScenarioLoader>();
+
+        let handle: Handle<ScenarioAsset> =
+            app.world().resource::<AssetServer>().load("scenario.json");
+        for _ in 0..500 {
+            app.update();
+            if app
+                .world()
+                .resource::<Assets<ScenarioAsset>>()
+                .contains(&handle)
+            {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let asset = app
+            .world()
+            .resource::<Assets<ScenarioAsset>>()
+            .get(&handle)
+            .expect("scenario should load without a registered extension");
+        assert!(asset.report.is_empty());
+
+        // パスを指定した読み直しも、既存のハンドルの型からローダーが決まる
+        let edited = VALID.replace(r#""root""#, r#""edited""#);
+        std::fs::write(temp_dir.path().join("scenario.json"), edited).unwrap();
+        app.world()
+            .resource::<AssetServer>()
+            .reload("scenario.json");
+        let text = |app: &App| {
+            let scenarios = app.world().resource::<Assets<ScenarioAsset>>();
+            let node = scenarios.get(&handle).unwrap().data.get_node("R").cloned();
+            node.unwrap().text
+        };
+        for _ in 0..500 {
+            app.update();
+            if text(&app) == "edited" {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(text(&app), "edited");
+    }
+
+    #[test]
+    fn test_reload_swaps_scenario_data() {
+        let (mut app, handle) = reload_app(VALID);
+
+        replace(
+            &mut app,
+            &handle,
+            &VALID.replace(r#""root""#, r#""edited""#),
+        );
+        let data = app.world().resource::<ScenarioData>();
+        assert_eq!(data.get_node("R").unwrap().text, "edited");
+    }
+
+    #[test]
+    fn test_reload_rewinds_when_current_node_is_removed() {
+        let (mut app, handle) = reload_app(VALID);
+        let at_r0 = {
+            let data = app.world().resource::<ScenarioData>();
+            data.transition(&data.initial_current(), 1).unwrap()
+        };
+        app.insert_resource(at_r0);
+
+        let renamed = VALID
+            .replace(r#""to": "R0""#, r#""to": "B""#)
+            .replace(r#""id": "R0""#, r#""id": "B""#);
+        replace(&mut app, &handle, &renamed);
+
+        let current = app.world().resource::<Current>();
+        assert_eq!(current.id, "R");
+        assert_eq!(current.depth, 0);
+        let notices = app.world().resource::<Events<ShowNotice>>();
+        assert_eq!(notices.len(), 1);
+    }
+
+    #[test]
+    fn test_reload_rejects_invalid_scenario() {
+        let (mut app, handle) = reload_app(VALID);
+
+        let invalid = VALID
+            .replace(r#""root""#, r#""edited""#)
+            .replace(r#""to": "R0""#, r#""to": "NOPE""#);
+        replace(&mut app, &handle, &invalid);
+        let data = app.world().resource::<ScenarioData>();
+        assert_eq!(data.get_node("R").unwrap().text, "root");
+    }
+}
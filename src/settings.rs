@@ -0,0 +1,120 @@
+use crate::save::SaveManager;
+use anyhow::{Context, Result};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 遊び方の設定の形式のバージョン
+pub const SETTINGS_VERSION: u8 = 1;
+
+/// 本文を表示する速さ
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TextSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+    /// 1文字ずつ出さずに全文を一度に出す
+    Instant,
+}
+
+impl TextSpeed {
+    /// 1秒あたりの文字数（Instant は None）
+    pub fn chars_per_sec(self) -> Option<u32> {
+        match self {
+            TextSpeed::Slow => Some(15),
+            TextSpeed::Normal => Some(30),
+            TextSpeed::Fast => Some(60),
+            TextSpeed::Instant => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TextSpeed::Slow => "遅い",
+            TextSpeed::Normal => "普通",
+            TextSpeed::Fast => "速い",
+            TextSpeed::Instant => "一瞬",
+        }
+    }
+
+    /// 設定画面のボタンを押したときの次の速さ
+    pub fn next(self) -> Self {
+        match self {
+            TextSpeed::Slow => TextSpeed::Normal,
+            TextSpeed::Normal => TextSpeed::Fast,
+            TextSpeed::Fast => TextSpeed::Instant,
+            TextSpeed::Instant => TextSpeed::Slow,
+        }
+    }
+}
+
+/// プレイヤーごとの遊び方の設定（settings.json）
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlayerSettings {
+    pub version: u8,
+    #[serde(default)]
+    pub text_speed: TextSpeed,
+}
+
+impl Default for PlayerSettings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            text_speed: TextSpeed::default(),
+        }
+    }
+}
+
+impl PlayerSettings {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).context("Failed to deserialize player settings")
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize player settings")
+    }
+}
+
+/// 起動時に遊び方の設定を読み込む（無い・読めない場合は初期設定）
+pub fn setup_player_settings(mut commands: Commands, save_manager: Res<SaveManager>) {
+    let settings = match save_manager.load_settings() {
+        Ok(Some(settings)) => {
+            info!("Player settings loaded");
+            settings
+        }
+        Ok(None) => PlayerSettings::default(),
+        Err(e) => {
+            error!(key = "settings.load_failed", "{:#}", e);
+            PlayerSettings::default()
+        }
+    };
+    commands.insert_resource(settings);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_round_trip_and_defaults() {
+        let settings = PlayerSettings {
+            text_speed: TextSpeed::Fast,
+            ..default()
+        };
+        let json = settings.to_json().unwrap();
+        assert!(json.contains("\"fast\""));
+        assert_eq!(PlayerSettings::from_json(&json).unwrap(), settings);
+
+        // 項目が足りなければ初期設定
+        let old = PlayerSettings::from_json(r#"{"version": 1}"#).unwrap();
+        assert_eq!(old.text_speed, TextSpeed::Normal);
+
+        // 押すたびに一周する
+        let mut speed = TextSpeed::Slow;
+        for _ in 0..4 {
+            speed = speed.next();
+        }
+        assert_eq!(speed, TextSpeed::Slow);
+    }
+}
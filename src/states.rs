@@ -1,3 +1,5 @@
+use crate::bindings::{Action, BindingDevice};
+use crate::save::SaveSlot;
 use bevy::prelude::*;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
@@ -7,6 +9,32 @@ pub enum AppState {
     Title,
     Playing,
     Ending,
+    /// 起動時の読み込みに失敗した
+    Error,
+    /// セーブ・ロードのスロット選択画面
+    SlotMenu,
+    /// 到達したエンディングの一覧
+    Gallery,
+    /// シナリオの分岐を木で見せるルートマップ
+    RouteMap,
+    /// 文字の速さとキー・コントローラーの割り当てを変える画面
+    Settings,
+}
+
+/// スロット選択画面を開いた目的
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotMode {
+    Save,
+    Load,
+}
+
+/// 表示中のスロット選択画面と、閉じたときの戻り先
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct SlotMenu {
+    pub mode: SlotMode,
+    pub back_to: AppState,
+    /// 「削除」を一度押して確認待ちのスロット（もう一度押すと消す）
+    pub pending_delete: Option<SaveSlot>,
 }
 
 #[derive(Component)]
@@ -18,6 +46,47 @@ pub struct PlayingUI;
 #[derive(Component)]
 pub struct EndingUI;
 
+#[derive(Component)]
+pub struct ErrorUI;
+
+#[derive(Component)]
+pub struct SlotUI;
+
+/// 表示中のルートマップと、閉じたときの戻り先
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct RouteMap {
+    pub back_to: AppState,
+}
+
+#[derive(Component)]
+pub struct RouteMapUI;
+
+#[derive(Component)]
+pub struct GalleryUI;
+
+/// Playing の上に重ねるバックログ（開いている間は下の選択肢を押せない）
+#[derive(Component)]
+pub struct BacklogUI;
+
+/// プレイ中に Esc を押したときの「タイトルに戻りますか？」
+#[derive(Component)]
+pub struct TitleConfirmUI;
+
+#[derive(Component)]
+pub struct SettingsUI;
+
+/// 「文章を隠す」で背景だけを見ている間、クリックなどを受け止める透明な覆い
+#[derive(Component)]
+pub struct HiddenUIOverlay;
+
+/// ギャラリーからエンディングを見返している（エンディング画面から戻る先がギャラリーになる）
+#[derive(Resource)]
+pub struct GalleryReplay;
+
+/// しばらく表示して消えるお知らせ（ステートをまたいで残る）
+#[derive(Component)]
+pub struct NoticeUI(pub Timer);
+
 #[derive(Component)]
 pub struct BeginNewButton;
 
@@ -29,9 +98,96 @@ pub struct ChoiceButton {
     pub choice_index: usize,
 }
 
+/// 選択肢の横に出すコントローラーのボタン表記（画面に並んだ順の番号）
+#[derive(Component)]
+pub struct ChoiceGlyph(pub usize);
+
 #[derive(Component)]
 pub struct RestartButton;
 
+#[derive(Component)]
+pub struct RetryButton;
+
+#[derive(Component)]
+pub struct QuitButton;
+
+/// どの選択肢も選べない場面で出す「タイトルへ」
+#[derive(Component)]
+pub struct StuckTitleButton;
+
+#[derive(Component)]
+pub struct OpenSlotMenuButton(pub SlotMode);
+
+#[derive(Component)]
+pub struct SlotButton(pub SaveSlot);
+
+/// スロットの横の「削除」
+#[derive(Component)]
+pub struct DeleteSlotButton(pub SaveSlot);
+
+#[derive(Component)]
+pub struct SlotBackButton;
+
+#[derive(Component)]
+pub struct OpenGalleryButton;
+
+#[derive(Component)]
+pub struct OpenBacklogButton;
+
+#[derive(Component)]
+pub struct CloseBacklogButton;
+
+#[derive(Component)]
+pub struct OpenRouteMapButton;
+
+/// ルートマップの訪れた場面（ルートID）
+#[derive(Component)]
+pub struct RouteMapNodeButton(pub String);
+
+#[derive(Component)]
+pub struct RouteMapBackButton;
+
+/// 押すと指定した階層の分岐点まで戻る
+#[derive(Component)]
+pub struct RewindButton(pub usize);
+
+/// ギャラリーの1マス（エンディングのルートID）
+#[derive(Component)]
+pub struct GalleryEntryButton(pub String);
+
+#[derive(Component)]
+pub struct GalleryBackButton;
+
+#[derive(Component)]
+pub struct ConfirmYesButton;
+
+#[derive(Component)]
+pub struct ConfirmNoButton;
+
+#[derive(Component)]
+pub struct OpenSettingsButton;
+
+/// 押すとその操作の割り当ての入力待ちになる
+#[derive(Component)]
+pub struct BindingButton {
+    pub action: Action,
+    pub device: BindingDevice,
+}
+
+/// 押すたびに文字の速さを切り替える
+#[derive(Component)]
+pub struct TextSpeedButton;
+
+#[derive(Component)]
+pub struct ResetBindingsButton;
+
+#[derive(Component)]
+pub struct SettingsBackButton;
+
+/// カーソルを合わせたエンディングの本文を出す欄
+#[derive(Component)]
+pub struct GalleryDetailText;
+
 #[derive(Event)]
 pub struct BeginNewGame;
 
@@ -46,6 +202,92 @@ pub struct MakeChoice {
 #[derive(Event)]
 pub struct RestartGame;
 
+#[derive(Event)]
+pub struct RetryBoot;
+
+#[derive(Event)]
+pub struct QuitGame;
+
+#[derive(Event)]
+pub struct OpenSlotMenu(pub SlotMode);
+
+#[derive(Event)]
+pub struct SelectSlot(pub SaveSlot);
+
+/// スロットをバックアップごと消す（1回目は確認待ちにするだけ）
+#[derive(Event)]
+pub struct DeleteSlot(pub SaveSlot);
+
+#[derive(Event)]
+pub struct CloseSlotMenu;
+
+#[derive(Event)]
+pub struct OpenGallery;
+
+#[derive(Event)]
+pub struct CloseGallery;
+
+/// ギャラリーからエンディングの最後の場面を見返す（ルートID）
+#[derive(Event)]
+pub struct ReplayEnding(pub String);
+
+#[derive(Event)]
+pub struct OpenBacklog;
+
+#[derive(Event)]
+pub struct CloseBacklog;
+
+/// 指定した階層（trail 上の位置）の分岐点まで戻って選び直す
+#[derive(Event)]
+pub struct RewindTo(pub usize);
+
+#[derive(Event)]
+pub struct OpenRouteMap;
+
+#[derive(Event)]
+pub struct CloseRouteMap;
+
+/// ルートマップで選んだ場面へ移る（ルートID）
+#[derive(Event)]
+pub struct JumpToRoute(pub String);
+
+/// タイトルに戻る（プレイ中は確認してから）
+#[derive(Event)]
+pub struct ReturnToTitle;
+
+#[derive(Event)]
+pub struct OpenSettings;
+
+#[derive(Event)]
+pub struct CloseSettings;
+
+/// 次に押したキー（ボタン）をその操作に割り当てる（入力待ち中にもう一度送ると取り消し）
+#[derive(Event)]
+pub struct StartRebind {
+    pub action: Action,
+    pub device: BindingDevice,
+}
+
+/// 文字の速さを次の段階にする
+#[derive(Event)]
+pub struct CycleTextSpeed;
+
+/// 割り当てをすべて初期設定に戻す
+#[derive(Event)]
+pub struct ResetBindings;
+
+/// 選択の結果エンディングに到達した
+#[derive(Event)]
+pub struct EndingReached {
+    /// 辿ってきた経路のルートID（合流するノードでも経路ごとに異なる）
+    pub route_id: String,
+    pub node_id: String,
+}
+
+/// プレイヤーに知らせたいこと（セーブの復元など）
+#[derive(Event)]
+pub struct ShowNotice(pub String);
+
 pub fn cleanup_ui<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
@@ -68,6 +310,11 @@ mod tests {
             AppState::Title,
             AppState::Playing,
             AppState::Ending,
+            AppState::Error,
+            AppState::SlotMenu,
+            AppState::Gallery,
+            AppState::RouteMap,
+            AppState::Settings,
         ];
         for state in states {
             match state {
@@ -75,6 +322,11 @@ mod tests {
                 AppState::Title => assert_ne!(state, AppState::Playing),
                 AppState::Playing => assert_ne!(state, AppState::Ending),
                 AppState::Ending => assert_ne!(state, AppState::Boot),
+                AppState::Error => assert_ne!(state, AppState::Title),
+                AppState::SlotMenu => assert_ne!(state, AppState::Playing),
+                AppState::Gallery => assert_ne!(state, AppState::Ending),
+                AppState::RouteMap => assert_ne!(state, AppState::Playing),
+                AppState::Settings => assert_ne!(state, AppState::Title),
             }
         }
     }
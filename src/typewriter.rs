@@ -0,0 +1,439 @@
+use crate::bindings::{Action, ActionInput};
+use crate::focus::FocusScope;
+use crate::scenario::{Current, Node};
+use crate::settings::{PlayerSettings, TextSpeed};
+use crate::states::ShowNotice;
+use crate::ui_impl::{ChoiceList, GameText};
+use bevy::prelude::*;
+
+/// オートで、ページを出し終えてから次のページへ送るまでの秒数
+const AUTO_PAGE_SECONDS: f32 = 2.0;
+
+/// スキップを押している間に、ページを送る間隔の秒数
+const SKIP_PAGE_SECONDS: f32 = 0.1;
+
+/// Playing の本文（今のページ）を1文字ずつ出す進み具合（本文を作り直すたびに入れ直す）
+///
+/// 数えるのはバイトではなく文字なので、日本語でも文字の途中で切れない。
+#[derive(Resource, Debug, Clone)]
+pub struct TextReveal {
+    text: String,
+    /// ノードの最後のページか（選択肢はこのページを出し終えてから見せる）
+    last_page: bool,
+    total: usize,
+    /// 出し終えた文字数
+    shown: usize,
+    /// 1秒あたりの文字数（None なら一度に全部出す）
+    chars_per_sec: Option<u32>,
+    elapsed: f32,
+}
+
+impl TextReveal {
+    pub fn new(text: &str, chars_per_sec: Option<u32>) -> Self {
+        let total = text.chars().count();
+        Self {
+            text: text.to_string(),
+            last_page: true,
+            total,
+            shown: if chars_per_sec.is_some() { 0 } else { total },
+            chars_per_sec,
+            elapsed: 0.0,
+        }
+    }
+
+    /// ノードの page 枚目を、ノードの指定があればその速さで、無ければプレイヤーの設定の速さで出す
+    ///
+    /// プレイヤーが「一瞬」を選んでいるときはノードの指定より優先する。
+    pub fn for_page(node: &Node, page: usize, settings: &PlayerSettings) -> Self {
+        let speed = match settings.text_speed {
+            TextSpeed::Instant => None,
+            speed => node.text_speed.or(speed.chars_per_sec()),
+        };
+        let pages = node.pages();
+        let page = page.min(pages.len() - 1);
+        Self {
+            last_page: page + 1 == pages.len(),
+            ..Self::new(&pages[page], speed)
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.shown >= self.total
+    }
+
+    pub fn is_last_page(&self) -> bool {
+        self.last_page
+    }
+
+    /// 最後のページまで出し終えたか（選択肢を見せてよいか）
+    pub fn is_finished(&self) -> bool {
+        self.is_done() && self.last_page
+    }
+
+    /// 残りを一度に出す
+    pub fn finish(&mut self) {
+        self.shown = self.total;
+    }
+
+    /// 時間を進める（出した文字数が変わったら true）
+    pub fn advance(&mut self, secs: f32) -> bool {
+        let Some(chars_per_sec) = self.chars_per_sec else {
+            return false;
+        };
+        if self.is_done() {
+            return false;
+        }
+        self.elapsed += secs;
+        let shown = ((self.elapsed * chars_per_sec as f32) as usize).min(self.total);
+        let changed = shown != self.shown;
+        self.shown = shown;
+        changed
+    }
+
+    /// 出した部分とまだの部分
+    pub fn split(&self) -> (&str, &str) {
+        let at = self
+            .text
+            .char_indices()
+            .nth(self.shown)
+            .map_or(self.text.len(), |(i, _)| i);
+        self.text.split_at(at)
+    }
+}
+
+/// 本文を読んでいる途中か（出している途中か、まだ次のページがある）
+///
+/// この間は選択肢が隠れているので、番号キーなどで選ばせない。
+pub fn reading_text(reveal: Option<Res<TextReveal>>) -> bool {
+    reveal.is_some_and(|reveal| !reveal.is_finished())
+}
+
+/// オートで次のページへ送るか
+#[derive(Resource, Debug, Default)]
+pub struct AutoRead(pub bool);
+
+/// 本文を送るクリック（ボタンの上のクリックはボタンのもの）
+fn clicked_text(
+    mouse_buttons: &ButtonInput<MouseButton>,
+    button_query: &Query<&Interaction, With<Button>>,
+) -> bool {
+    mouse_buttons.just_pressed(MouseButton::Left)
+        && button_query
+            .iter()
+            .all(|interaction| *interaction == Interaction::None)
+}
+
+/// 本文の2つの区切り（出した部分と、透明にしたまだの部分）
+///
+/// まだの部分も透明な文字として置いておくので、途中で行の折り返しや枠の大きさが変わらない。
+pub fn reveal_sections(reveal: &TextReveal, style: &TextStyle) -> [TextSection; 2] {
+    let (shown, hidden) = reveal.split();
+    [
+        TextSection::new(shown, style.clone()),
+        TextSection::new(
+            hidden,
+            TextStyle {
+                color: Color::NONE,
+                ..style.clone()
+            },
+        ),
+    ]
+}
+
+fn apply_reveal(reveal: &TextReveal, text_query: &mut Query<&mut Text, With<GameText>>) {
+    let (shown, hidden) = reveal.split();
+    for mut text in text_query.iter_mut() {
+        if let [shown_section, hidden_section] = &mut text.sections[..] {
+            shown_section.value = shown.to_string();
+            hidden_section.value = hidden.to_string();
+        }
+    }
+}
+
+/// 時間に合わせて本文を出していく
+pub fn tick_text_reveal(
+    time: Res<Time>,
+    mut reveal: ResMut<TextReveal>,
+    mut text_query: Query<&mut Text, With<GameText>>,
+) {
+    if reveal.advance(time.delta_seconds()) {
+        apply_reveal(&reveal, &mut text_query);
+    }
+}
+
+/// 出し終えたページでのクリック・「決定」で次のページへ送る（Current::page が変わり本文が作り直される）
+///
+/// 同じクリックで出し終えてすぐ送らないよう、complete_text_reveal より先に動かす。
+pub fn advance_page(
+    actions: Res<ActionInput>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    scope_query: Query<(), With<FocusScope>>,
+    button_query: Query<&Interaction, With<Button>>,
+    reveal: Res<TextReveal>,
+    mut current: ResMut<Current>,
+) {
+    if !reveal.is_done() || reveal.is_last_page() || !scope_query.is_empty() {
+        return;
+    }
+    if clicked_text(&mouse_buttons, &button_query) || actions.just_pressed(Action::Confirm) {
+        current.page += 1;
+    }
+}
+
+/// 出している途中のクリック・「決定」は選択ではなく残りを一度に出す
+///
+/// 「スキップ」を押している間は、ページが変わるたびにすぐ全部を出す。
+/// バックログなどの覆いが開いている間のクリックはそちらのもの。
+pub fn complete_text_reveal(
+    actions: Res<ActionInput>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    scope_query: Query<(), With<FocusScope>>,
+    button_query: Query<&Interaction, With<Button>>,
+    mut reveal: ResMut<TextReveal>,
+    mut text_query: Query<&mut Text, With<GameText>>,
+) {
+    if reveal.is_done() || !scope_query.is_empty() {
+        return;
+    }
+    if clicked_text(&mouse_buttons, &button_query)
+        || actions.just_pressed(Action::Confirm)
+        || actions.pressed(Action::Skip)
+    {
+        reveal.finish();
+        apply_reveal(&reveal, &mut text_query);
+    }
+}
+
+/// 「オート」の割り当てで、ページを自動で送るかを切り替える（覆いが開いている間は何もしない）
+pub fn toggle_auto_read(
+    actions: Res<ActionInput>,
+    scope_query: Query<(), With<FocusScope>>,
+    mut auto_read: ResMut<AutoRead>,
+    mut notices: EventWriter<ShowNotice>,
+) {
+    if !actions.just_pressed(Action::ToggleAuto) || !scope_query.is_empty() {
+        return;
+    }
+    auto_read.0 = !auto_read.0;
+    let label = if auto_read.0 { "オン" } else { "オフ" };
+    notices.send(ShowNotice(format!("オート：{label}")));
+}
+
+/// オートの間は、ページを出し終えて少し待ってから次のページへ送る（選択肢は選ばない）
+///
+/// 「スキップ」を押している間は待ち時間を短くして次々に送り、選択肢のあるページで止まる。
+pub fn auto_advance_page(
+    time: Res<Time>,
+    actions: Res<ActionInput>,
+    auto_read: Res<AutoRead>,
+    reveal: Res<TextReveal>,
+    scope_query: Query<(), With<FocusScope>>,
+    mut current: ResMut<Current>,
+    mut waited: Local<f32>,
+) {
+    let skipping = actions.pressed(Action::Skip);
+    let waiting = (auto_read.0 || skipping) && reveal.is_done() && !reveal.is_last_page();
+    if !waiting || reveal.is_added() {
+        *waited = 0.0;
+        return;
+    }
+    // バックログなどを見ている間は待つ時間を数えない
+    if !scope_query.is_empty() {
+        return;
+    }
+    *waited += time.delta_seconds();
+    let wait = if skipping {
+        SKIP_PAGE_SECONDS
+    } else {
+        AUTO_PAGE_SECONDS
+    };
+    if *waited >= wait {
+        *waited = 0.0;
+        current.page += 1;
+    }
+}
+
+/// 最後のページを出し終えたら選択肢を見せる（隠れている間は押せず、フォーカスも移らない）
+pub fn show_choices_after_reveal(
+    reveal: Res<TextReveal>,
+    mut choice_list_query: Query<&mut Visibility, With<ChoiceList>>,
+) {
+    if !reveal.is_finished() {
+        return;
+    }
+    for mut visibility in choice_list_query.iter_mut() {
+        visibility.set_if_neq(Visibility::Inherited);
+    }
+}
+
+/// Playing を離れたら進み具合を捨てる
+pub fn clear_text_reveal(mut commands: Commands) {
+    commands.remove_resource::<TextReveal>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reveal_counts_characters_not_bytes() {
+        let mut reveal = TextReveal::new("雨の朝、傘", Some(10));
+        assert_eq!(reveal.split(), ("", "雨の朝、傘"));
+
+        // 0.3秒で3文字
+        assert!(reveal.advance(0.3));
+        assert_eq!(reveal.split(), ("雨の朝", "、傘"));
+        assert!(!reveal.is_done());
+
+        assert!(reveal.advance(1.0));
+        assert_eq!(reveal.split(), ("雨の朝、傘", ""));
+        assert!(reveal.is_done());
+        assert!(!reveal.advance(1.0));
+    }
+
+    #[test]
+    fn test_node_speed_overrides_player_speed() {
+        let node: Node = serde_json::from_str(
+            r#"{"id": "R", "text": "abc", "bg": null, "ending": null, "text_speed": 5}"#,
+        )
+        .unwrap();
+        let mut settings = PlayerSettings::default();
+        assert_eq!(
+            TextReveal::for_page(&node, 0, &settings).chars_per_sec,
+            Some(5)
+        );
+
+        // 「一瞬」を選んだプレイヤーにはノードの指定でも一度に出す
+        settings.text_speed = TextSpeed::Instant;
+        let reveal = TextReveal::for_page(&node, 0, &settings);
+        assert!(reveal.is_done());
+        assert_eq!(reveal.split(), ("abc", ""));
+    }
+
+    #[test]
+    fn test_click_completes_reveal_and_shows_choices() {
+        let mut app = App::new();
+        app.init_resource::<ActionInput>();
+        app.init_resource::<ButtonInput<MouseButton>>();
+        let reveal = TextReveal::new("ながいながい本文", Some(1));
+        let sections = reveal_sections(&reveal, &TextStyle::default());
+        app.insert_resource(reveal);
+        app.add_systems(
+            Update,
+            (complete_text_reveal, show_choices_after_reveal).chain(),
+        );
+        let text = app
+            .world_mut()
+            .spawn((Text::from_sections(sections), GameText))
+            .id();
+        let choices = app.world_mut().spawn((Visibility::Hidden, ChoiceList)).id();
+
+        app.update();
+        assert_eq!(
+            *app.world().get::<Visibility>(choices).unwrap(),
+            Visibility::Hidden
+        );
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .press(MouseButton::Left);
+        app.update();
+        assert!(app.world().resource::<TextReveal>().is_done());
+        assert_eq!(
+            app.world().get::<Text>(text).unwrap().sections[0].value,
+            "ながいながい本文"
+        );
+        assert_eq!(
+            *app.world().get::<Visibility>(choices).unwrap(),
+            Visibility::Inherited
+        );
+    }
+
+    #[test]
+    fn test_click_turns_pages_until_the_last() {
+        let node: Node = serde_json::from_str(
+            r#"{"id": "R", "text": "一枚目\n---\n二枚目", "bg": null, "ending": null}"#,
+        )
+        .unwrap();
+        let settings = PlayerSettings {
+            text_speed: TextSpeed::Instant,
+            ..default()
+        };
+        let mut app = App::new();
+        app.init_resource::<ActionInput>();
+        app.init_resource::<ButtonInput<MouseButton>>();
+        app.init_resource::<Current>();
+        app.insert_resource(TextReveal::for_page(&node, 0, &settings));
+        app.add_systems(Update, advance_page);
+
+        let click = |app: &mut App| {
+            let mut mouse_buttons = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+            mouse_buttons.press(MouseButton::Left);
+            app.update();
+            let mut mouse_buttons = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+            mouse_buttons.release(MouseButton::Left);
+            mouse_buttons.clear();
+            app.world().resource::<Current>().page
+        };
+
+        assert!(!app.world().resource::<TextReveal>().is_finished());
+        assert_eq!(click(&mut app), 1);
+
+        // 最後のページではもう送らない（選択肢を選ぶ番）
+        let last = TextReveal::for_page(&node, 1, &settings);
+        assert_eq!(last.split(), ("二枚目", ""));
+        assert!(last.is_finished());
+        app.insert_resource(last);
+        assert_eq!(click(&mut app), 1);
+    }
+
+    #[test]
+    fn test_holding_skip_turns_pages_and_stops_at_choices() {
+        let node: Node = serde_json::from_str(
+            r#"{"id": "R", "text": "一枚目\n---\n二枚目", "bg": null, "ending": null}"#,
+        )
+        .unwrap();
+        let mut app = App::new();
+        app.init_resource::<Time>();
+        app.init_resource::<AutoRead>();
+        app.init_resource::<ButtonInput<MouseButton>>();
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.init_resource::<ButtonInput<GamepadButton>>();
+        app.init_resource::<crate::bindings::InputBindings>();
+        app.init_resource::<ActionInput>();
+        app.init_resource::<Current>();
+        app.insert_resource(TextReveal::for_page(&node, 0, &PlayerSettings::default()));
+        app.add_systems(
+            Update,
+            (
+                crate::bindings::update_action_input,
+                auto_advance_page,
+                complete_text_reveal,
+            )
+                .chain(),
+        );
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ControlLeft);
+
+        let step = |app: &mut App| {
+            app.world_mut()
+                .resource_mut::<Time>()
+                .advance_by(std::time::Duration::from_secs_f32(SKIP_PAGE_SECONDS));
+            app.update();
+            app.world().resource::<Current>().page
+        };
+
+        // 押した最初のフレームで残りを全部出し、次のフレームで送る
+        assert_eq!(step(&mut app), 0);
+        assert!(app.world().resource::<TextReveal>().is_done());
+        assert_eq!(step(&mut app), 1);
+
+        // 最後のページでは選択肢を待つ
+        let last = TextReveal::for_page(&node, 1, &PlayerSettings::default());
+        app.insert_resource(last);
+        assert_eq!(step(&mut app), 1);
+        assert_eq!(step(&mut app), 1);
+        assert!(app.world().resource::<TextReveal>().is_finished());
+    }
+}
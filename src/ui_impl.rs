@@ -1,7 +1,14 @@
+use crate::app::boot::BootError;
 use crate::app::constants::ui::*;
-use crate::save::SaveManager;
-use crate::scenario::{Current, ScenarioData};
+use crate::bindings::{key_label, Action, BindingDevice, InputBindings, InputKey, SettingsMenu};
+use crate::focus::{Focus, FocusScope};
+use crate::gamepad::{LastInputDevice, PadLayout};
+use crate::profile::Profile;
+use crate::save::{format_play_time, load_error_message, SaveManager, StoryUpdated};
+use crate::scenario::{ChoiceState, Current, RouteMapNode, ScenarioData};
+use crate::settings::PlayerSettings;
 use crate::states::*;
+use crate::typewriter::{reveal_sections, TextReveal};
 use crate::ui::components::{
     create_button_text_style, create_game_button, create_game_button_with_color, Disabled,
 };
@@ -68,8 +75,11 @@ pub fn setup_title_ui(
     font: Res<GameFont>,
     save_manager: Res<SaveManager>,
     scenario_data: Res<ScenarioData>,
+    story_updated: Option<Res<StoryUpdated>>,
+    profile: Option<Res<Profile>>,
 ) {
     let has_save = save_manager.has_save();
+    let story_updated = story_updated.is_some_and(|updated| updated.0);
 
     commands
         .spawn((
@@ -98,6 +108,31 @@ pub fn setup_title_ui(
                 },
             ));
 
+            if story_updated {
+                parent.spawn(TextBundle::from_section(
+                    "物語が前回のプレイから更新されています",
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: CHOICE_FONT_SIZE,
+                        color: Color::srgb(1.0, 0.8, 0.0),
+                    },
+                ));
+            }
+
+            if let Some(profile) = &profile {
+                parent.spawn(TextBundle::from_section(
+                    format!(
+                        "エンディング収集率 {}%",
+                        profile.completion_percent(&scenario_data)
+                    ),
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: CHOICE_FONT_SIZE,
+                        color: Color::srgb(0.8, 0.8, 0.8),
+                    },
+                ));
+            }
+
             parent
                 .spawn((create_game_button(), BeginNewButton))
                 .with_children(|parent| {
@@ -135,6 +170,52 @@ pub fn setup_title_ui(
                     },
                 ));
             });
+
+            let mut load_entity = parent.spawn((
+                create_game_button_with_color(if has_save {
+                    BUTTON_NORMAL_COLOR
+                } else {
+                    BUTTON_DISABLED_COLOR
+                }),
+                OpenSlotMenuButton(SlotMode::Load),
+            ));
+
+            if !has_save {
+                load_entity.insert(Disabled);
+            }
+
+            load_entity.with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    "ロード",
+                    TextStyle {
+                        font: font.0.clone(),
+                        font_size: BUTTON_FONT_SIZE,
+                        color: if has_save {
+                            TEXT_NORMAL_COLOR
+                        } else {
+                            TEXT_DISABLED_COLOR
+                        },
+                    },
+                ));
+            });
+
+            parent
+                .spawn((create_game_button(), OpenGalleryButton))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "ギャラリー",
+                        create_button_text_style(font.0.clone(), BUTTON_FONT_SIZE),
+                    ));
+                });
+
+            parent
+                .spawn((create_game_button(), OpenSettingsButton))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "設定",
+                        create_button_text_style(font.0.clone(), BUTTON_FONT_SIZE),
+                    ));
+                });
         });
 }
 
@@ -143,8 +224,66 @@ pub fn setup_playing_ui(
     font: Res<GameFont>,
     scenario_data: Res<ScenarioData>,
     current: Res<Current>,
+    settings: Res<PlayerSettings>,
+) {
+    spawn_playing_ui(&mut commands, &font, &scenario_data, &current, &settings);
+}
+
+/// Playing 中に Current が変わったら本文と選択肢を作り直す
+///
+/// OnEnter(Playing) だけではステートが変わらない遷移（選択肢による移動）を拾えないため、
+/// Current の変更を検知して PlayingUI を再構築する。
+/// エンディングに到達した場合は OnEnter(Ending) 側で片付けるので何もしない。
+pub fn refresh_playing_ui(
+    mut commands: Commands,
+    font: Res<GameFont>,
+    scenario_data: Res<ScenarioData>,
+    current: Res<Current>,
+    settings: Res<PlayerSettings>,
+    playing_ui_query: Query<Entity, With<PlayingUI>>,
+) {
+    // シナリオがホットリロードされた場合も今のノードを描き直す
+    let changed = current.is_changed() || scenario_data.is_changed();
+    if !changed || scenario_data.is_ending(&current) {
+        return;
+    }
+
+    for entity in playing_ui_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    spawn_playing_ui(&mut commands, &font, &scenario_data, &current, &settings);
+    debug!("Playing UI rebuilt for node {}", current.id);
+}
+
+/// 本文は今のページを1文字ずつ出し、最後のページを出し終えるまで選択肢は隠しておく
+fn spawn_playing_ui(
+    commands: &mut Commands,
+    font: &GameFont,
+    scenario_data: &ScenarioData,
+    current: &Current,
+    settings: &PlayerSettings,
 ) {
     let node = scenario_data.get_node_or_fallback(&current.id);
+    let reveal = TextReveal::for_page(node, current.page, settings);
+    let page_count = node.page_count();
+    // 条件で隠れる選択肢は並べない（choice_index は元の並びのまま保持する）
+    // どれも選べないときは、行き止まりと分かるようすべてを押せない状態で並べ、抜け道を添える
+    let stuck = !scenario_data.has_available_choice(current);
+    let visible_choices: Vec<_> = node
+        .choices
+        .iter()
+        .enumerate()
+        .map(|(i, choice)| {
+            let state = if stuck {
+                ChoiceState::Disabled
+            } else {
+                scenario_data.choice_state(current, choice)
+            };
+            (i, choice, state)
+        })
+        .filter(|(_, _, state)| *state != ChoiceState::Hidden)
+        .collect();
 
     commands
         .spawn((
@@ -177,188 +316,1873 @@ pub fn setup_playing_ui(
                 })
                 .with_children(|parent| {
                     parent.spawn((
-                        TextBundle::from_section(
-                            &node.text,
-                            TextStyle {
+                        TextBundle::from_sections(reveal_sections(
+                            &reveal,
+                            &TextStyle {
                                 font: font.0.clone(),
                                 font_size: GAME_TEXT_FONT_SIZE,
                                 color: TEXT_NORMAL_COLOR,
                             },
-                        ),
+                        )),
                         GameText,
                     ));
+                    // 複数ページあれば右下に何ページ目かを出す
+                    if page_count > 1 {
+                        parent.spawn(
+                            TextBundle::from_section(
+                                format!("{}/{}", current.page.min(page_count - 1) + 1, page_count),
+                                TextStyle {
+                                    font: font.0.clone(),
+                                    font_size: PAGE_INDICATOR_FONT_SIZE,
+                                    color: TEXT_DISABLED_COLOR,
+                                },
+                            )
+                            .with_style(Style {
+                                position_type: PositionType::Absolute,
+                                right: Val::Px(8.0),
+                                bottom: Val::Px(4.0),
+                                ..default()
+                            }),
+                        );
+                    }
                 });
 
-            if !node.choices.is_empty() {
+            if !visible_choices.is_empty() {
                 parent
-                    .spawn(NodeBundle {
-                        style: Style {
-                            flex_direction: FlexDirection::Row,
-                            column_gap: Val::Px(20.0),
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Row,
+                                column_gap: Val::Px(20.0),
+                                ..default()
+                            },
+                            // 隠している間も場所は取っておき、出たときに本文が動かないようにする
+                            visibility: if reveal.is_finished() {
+                                Visibility::Inherited
+                            } else {
+                                Visibility::Hidden
+                            },
                             ..default()
                         },
-                        ..default()
-                    })
+                        ChoiceList,
+                    ))
                     .with_children(|parent| {
-                        for (i, choice) in node.choices.iter().enumerate() {
-                            parent
-                                .spawn((
-                                    ButtonBundle {
-                                        style: Style {
-                                            width: Val::Px(150.0),
-                                            height: Val::Px(60.0),
-                                            justify_content: JustifyContent::Center,
-                                            align_items: AlignItems::Center,
-                                            padding: UiRect::all(Val::Px(10.0)),
-                                            ..default()
-                                        },
-                                        background_color: Color::srgb(0.4, 0.4, 0.6).into(),
+                        for (number, (i, choice, state)) in visible_choices.into_iter().enumerate()
+                        {
+                            let enabled = state == ChoiceState::Enabled;
+                            let mut choice_entity = parent.spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        width: Val::Px(150.0),
+                                        height: Val::Px(60.0),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        padding: UiRect::all(Val::Px(10.0)),
                                         ..default()
                                     },
-                                    ChoiceButton { choice_index: i },
-                                ))
-                                .with_children(|parent| {
-                                    parent.spawn(TextBundle::from_section(
-                                        &choice.label,
+                                    background_color: if enabled {
+                                        Color::srgb(0.4, 0.4, 0.6).into()
+                                    } else {
+                                        BUTTON_DISABLED_COLOR.into()
+                                    },
+                                    ..default()
+                                },
+                                ChoiceButton { choice_index: i },
+                            ));
+
+                            // 条件を満たさない選択肢は押せない状態で表示する
+                            if !enabled {
+                                choice_entity.insert(Disabled);
+                            }
+
+                            choice_entity.with_children(|parent| {
+                                // コントローラーで遊んでいる間だけ文字が入る
+                                parent.spawn((
+                                    TextBundle::from_section(
+                                        "",
                                         TextStyle {
                                             font: font.0.clone(),
                                             font_size: CHOICE_FONT_SIZE,
-                                            color: TEXT_NORMAL_COLOR,
+                                            color: FOCUS_OUTLINE_COLOR,
                                         },
-                                    ));
-                                });
+                                    ),
+                                    ChoiceGlyph(number),
+                                ));
+                                parent.spawn(TextBundle::from_section(
+                                    &choice.label,
+                                    TextStyle {
+                                        font: font.0.clone(),
+                                        font_size: CHOICE_FONT_SIZE,
+                                        color: if enabled {
+                                            TEXT_NORMAL_COLOR
+                                        } else {
+                                            TEXT_DISABLED_COLOR
+                                        },
+                                    },
+                                ));
+                            });
                         }
                     });
             }
+
+            if stuck {
+                spawn_stuck_notice(parent, font, current, reveal.is_finished());
+            }
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (mode, label) in [(SlotMode::Save, "セーブ"), (SlotMode::Load, "ロード")]
+                    {
+                        parent
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        width: Val::Px(100.0),
+                                        height: Val::Px(36.0),
+                                        justify_content: JustifyContent::Center,
+                                        align_items: AlignItems::Center,
+                                        ..default()
+                                    },
+                                    background_color: BUTTON_NORMAL_COLOR.into(),
+                                    ..default()
+                                },
+                                OpenSlotMenuButton(mode),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(
+                                    label,
+                                    create_button_text_style(font.0.clone(), CHOICE_FONT_SIZE),
+                                ));
+                            });
+                    }
+
+                    spawn_history_buttons(parent, font, current);
+                });
         });
+
+    commands.insert_resource(reveal);
 }
 
-pub fn setup_ending_ui(
-    mut commands: Commands,
-    font: Res<GameFont>,
-    scenario_data: Res<ScenarioData>,
-    current: Res<Current>,
+/// 選べる選択肢が無い場面の案内と「タイトルへ」（選択肢と同じく本文を出し終えてから見せる）
+fn spawn_stuck_notice(
+    parent: &mut ChildBuilder,
+    font: &GameFont,
+    current: &Current,
+    revealed: bool,
 ) {
-    let node = scenario_data.get_node_or_fallback(&current.id);
-    let ending = match node.ending.as_ref() {
-        Some(ending) => ending,
-        None => {
-            error!(
-                key = "ui.ending.node_missing",
-                id = %current.id,
-                "Node has no ending data, returning early"
-            );
-            return;
-        }
+    let message = if current.depth > 0 {
+        "選べる選択肢がありません。「一つ戻る」で前の分岐点に戻るか、タイトルへ戻ってください"
+    } else {
+        "選べる選択肢がありません。タイトルへ戻ってください"
+    };
+
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                visibility: if revealed {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                },
+                ..default()
+            },
+            ChoiceList,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                message,
+                TextStyle {
+                    font: font.0.clone(),
+                    font_size: CHOICE_FONT_SIZE,
+                    color: TEXT_DISABLED_COLOR,
+                },
+            ));
+            parent
+                .spawn((create_game_button(), StuckTitleButton))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "タイトルへ",
+                        create_button_text_style(font.0.clone(), BUTTON_FONT_SIZE),
+                    ));
+                });
+        });
+}
+
+/// 「ログ」「マップ」「一つ戻る」の小さなボタン（Playing とエンディング画面で使う）
+fn spawn_history_buttons(parent: &mut ChildBuilder, font: &GameFont, current: &Current) {
+    let small_button = |color: Color| ButtonBundle {
+        style: Style {
+            width: Val::Px(100.0),
+            height: Val::Px(36.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: color.into(),
+        ..default()
+    };
+
+    parent
+        .spawn((small_button(BUTTON_NORMAL_COLOR), OpenBacklogButton))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "ログ",
+                create_button_text_style(font.0.clone(), CHOICE_FONT_SIZE),
+            ));
+        });
+
+    parent
+        .spawn((small_button(BUTTON_NORMAL_COLOR), OpenRouteMapButton))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "マップ",
+                create_button_text_style(font.0.clone(), CHOICE_FONT_SIZE),
+            ));
+        });
+
+    // 最初の分岐点では戻る先がない
+    let can_rewind = current.depth > 0;
+    let mut rewind_entity = parent.spawn((
+        small_button(if can_rewind {
+            BUTTON_NORMAL_COLOR
+        } else {
+            BUTTON_DISABLED_COLOR
+        }),
+        RewindButton(current.depth.saturating_sub(1)),
+    ));
+    if !can_rewind {
+        rewind_entity.insert(Disabled);
+    }
+    rewind_entity.with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "一つ戻る",
+            TextStyle {
+                font: font.0.clone(),
+                font_size: CHOICE_FONT_SIZE,
+                color: if can_rewind {
+                    TEXT_NORMAL_COLOR
+                } else {
+                    TEXT_DISABLED_COLOR
+                },
+            },
+        ));
+    });
+}
+
+/// ホイール1行分のスクロール量
+const BACKLOG_LINE_HEIGHT: f32 = 24.0;
+
+/// バックログの一覧（top をずらしてスクロールする。0 が最新の行を表示した状態）
+#[derive(Component, Default)]
+pub struct BacklogList {
+    pub position: f32,
+}
+
+/// 訪れたノードの本文を古い順に並べたオーバーレイを重ねる
+///
+/// 一覧は下端揃えにしてあり、開いた直後は最新の行が見えている。
+pub fn spawn_backlog_ui(
+    commands: &mut Commands,
+    font: &GameFont,
+    scenario_data: &ScenarioData,
+    current: &Current,
+) {
+    let text_style = |size: f32, color: Color| TextStyle {
+        font: font.0.clone(),
+        font_size: size,
+        color,
     };
 
     commands
         .spawn((
             NodeBundle {
                 style: Style {
+                    position_type: PositionType::Absolute,
                     width: Val::Percent(100.0),
                     height: Val::Percent(100.0),
                     align_items: AlignItems::Center,
-                    justify_content: JustifyContent::Center,
                     flex_direction: FlexDirection::Column,
-                    row_gap: Val::Px(30.0),
+                    padding: UiRect::all(Val::Px(30.0)),
+                    row_gap: Val::Px(16.0),
                     ..default()
                 },
-                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.9).into(),
+                focus_policy: bevy::ui::FocusPolicy::Block,
+                z_index: ZIndex::Global(10),
                 ..default()
             },
-            EndingUI,
+            BacklogUI,
+            FocusScope,
         ))
         .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "バックログ",
+                text_style(BUTTON_FONT_SIZE, TEXT_NORMAL_COLOR),
+            ));
+
             parent
                 .spawn(NodeBundle {
                     style: Style {
                         width: Val::Percent(80.0),
-                        max_width: Val::Px(600.0),
-                        padding: UiRect::all(Val::Px(30.0)),
-                        align_items: AlignItems::Center,
+                        max_width: Val::Px(800.0),
+                        flex_grow: 1.0,
                         flex_direction: FlexDirection::Column,
-                        row_gap: Val::Px(20.0),
+                        justify_content: JustifyContent::FlexEnd,
+                        overflow: Overflow::clip_y(),
                         ..default()
                     },
-                    background_color: Color::srgba(0.2, 0.2, 0.2, 0.9).into(),
                     ..default()
                 })
-                .with_children(|parent| {
-                    parent.spawn(TextBundle::from_section(
-                        &node.text,
-                        TextStyle {
-                            font: font.0.clone(),
-                            font_size: GAME_TEXT_FONT_SIZE,
-                            color: TEXT_NORMAL_COLOR,
-                        },
-                    ));
-
-                    parent.spawn(TextBundle::from_section(
-                        format!("ルートID: {}", current.id),
-                        TextStyle {
-                            font: font.0.clone(),
-                            font_size: CHOICE_FONT_SIZE,
-                            color: Color::srgb(0.8, 0.8, 0.8),
-                        },
-                    ));
-
-                    parent.spawn(TextBundle::from_section(
-                        &ending.tag,
-                        TextStyle {
-                            font: font.0.clone(),
-                            font_size: 32.0, // エンディング名は特別なので固有のサイズを保持
-                            color: Color::srgb(1.0, 0.8, 0.0),
-                        },
-                    ));
+                .with_children(|viewport| {
+                    viewport
+                        .spawn((
+                            NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    flex_shrink: 0.0,
+                                    row_gap: Val::Px(18.0),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            BacklogList::default(),
+                        ))
+                        .with_children(|list| {
+                            for entry in scenario_data.backlog(current) {
+                                list.spawn(TextBundle::from_section(
+                                    entry.text,
+                                    text_style(GAME_TEXT_FONT_SIZE, TEXT_NORMAL_COLOR),
+                                ));
+                                let Some(label) = entry.choice_label else {
+                                    continue;
+                                };
+                                // 選択した分岐点からは選び直せる
+                                list.spawn(NodeBundle {
+                                    style: Style {
+                                        align_items: AlignItems::Center,
+                                        column_gap: Val::Px(16.0),
+                                        ..default()
+                                    },
+                                    ..default()
+                                })
+                                .with_children(|row| {
+                                    row.spawn(TextBundle::from_section(
+                                        format!("▶ {label}"),
+                                        text_style(CHOICE_FONT_SIZE, Color::srgb(0.6, 0.7, 1.0)),
+                                    ));
+                                    row.spawn((
+                                        ButtonBundle {
+                                            style: Style {
+                                                padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                                                ..default()
+                                            },
+                                            background_color: BUTTON_NORMAL_COLOR.into(),
+                                            ..default()
+                                        },
+                                        RewindButton(entry.depth),
+                                    ))
+                                    .with_children(|button| {
+                                        button.spawn(TextBundle::from_section(
+                                            "ここから選び直す",
+                                            text_style(CHOICE_FONT_SIZE, TEXT_NORMAL_COLOR),
+                                        ));
+                                    });
+                                });
+                            }
+                        });
                 });
 
             parent
-                .spawn((
-                    ButtonBundle {
-                        style: Style {
-                            width: Val::Px(150.0), // Restartボタンのみ異なる幅
-                            height: Val::Px(50.0),
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
-                            ..default()
-                        },
-                        background_color: Color::srgb(0.6, 0.4, 0.4).into(),
-                        ..default()
-                    },
-                    RestartButton,
-                ))
+                .spawn((create_game_button(), CloseBacklogButton))
                 .with_children(|parent| {
                     parent.spawn(TextBundle::from_section(
-                        "もう一度",
-                        create_button_text_style(font.0.clone(), RESTART_FONT_SIZE),
+                        "閉じる",
+                        create_button_text_style(font.0.clone(), BUTTON_FONT_SIZE),
                     ));
                 });
         });
 }
 
-pub fn update_background(
-    mut background_query: Query<&mut Handle<Image>, With<BackgroundSprite>>,
-    asset_server: Res<AssetServer>,
-    scenario_data: Res<ScenarioData>,
-    current: Res<Current>,
+/// ホイールでバックログをスクロールする（最新の行より下へ回すと閉じる）
+pub fn scroll_backlog(
+    mut wheel_events: EventReader<bevy::input::mouse::MouseWheel>,
+    mut close_events: EventWriter<CloseBacklog>,
+    mut list_query: Query<(&mut BacklogList, &mut Style, &Parent, &Node)>,
+    node_query: Query<&Node>,
 ) {
-    if current.is_changed() {
-        if let Ok(mut background_handle) = background_query.get_single_mut() {
-            let new_bg = if let Some(node) = scenario_data.get_node(&current.id) {
-                node.bg
-                    .as_ref()
-                    .unwrap_or(&scenario_data.scenario.meta.default_background)
-            } else {
-                &scenario_data.scenario.meta.default_background
+    for event in wheel_events.read() {
+        let dy = match event.unit {
+            bevy::input::mouse::MouseScrollUnit::Line => event.y * BACKLOG_LINE_HEIGHT,
+            bevy::input::mouse::MouseScrollUnit::Pixel => event.y,
+        };
+        for (mut list, mut style, parent, list_node) in list_query.iter_mut() {
+            if dy < 0.0 && list.position <= 0.0 {
+                close_events.send(CloseBacklog);
+                return;
+            }
+            let Ok(viewport) = node_query.get(parent.get()) else {
+                continue;
             };
-
-            *background_handle = asset_server.load(new_bg);
+            let max_scroll = (list_node.size().y - viewport.size().y).max(0.0);
+            list.position = (list.position + dy).clamp(0.0, max_scroll);
+            style.top = Val::Px(list.position);
         }
     }
 }
 
-type ButtonInteractionQuery<'w, 's> = Query<
+/// プレイ中に Esc を押したときの確認を重ねる（進み具合はオートセーブに残っている）
+pub fn spawn_title_confirm_ui(commands: &mut Commands, font: &GameFont) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(30.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                focus_policy: bevy::ui::FocusPolicy::Block,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            TitleConfirmUI,
+            FocusScope,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "タイトルに戻りますか？",
+                create_button_text_style(font.0.clone(), BUTTON_FONT_SIZE),
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((create_game_button(), ConfirmYesButton))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "戻る",
+                                create_button_text_style(font.0.clone(), BUTTON_FONT_SIZE),
+                            ));
+                        });
+                    parent
+                        .spawn((create_game_button(), ConfirmNoButton))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "続ける",
+                                create_button_text_style(font.0.clone(), BUTTON_FONT_SIZE),
+                            ));
+                        });
+                });
+        });
+}
+
+const SETTINGS_ROW_HEIGHT: f32 = 28.0;
+const SETTINGS_CONFLICT_COLOR: Color = Color::srgb(1.0, 0.45, 0.35);
+
+/// 割り当てた入力を並べた表記（入力待ちの間は案内を出す）
+fn binding_label(
+    bindings: &InputBindings,
+    action: Action,
+    device: BindingDevice,
+    layout: PadLayout,
+    capturing: bool,
+) -> String {
+    if capturing {
+        return match device {
+            BindingDevice::Keyboard => "キーを押してください（右クリックで取り消し）".to_string(),
+            BindingDevice::Gamepad => "ボタンを押してください".to_string(),
+        };
+    }
+    let binding = bindings.get(action).cloned().unwrap_or_default();
+    let labels: Vec<String> = match device {
+        BindingDevice::Keyboard => binding.keys.iter().map(|key| key_label(*key)).collect(),
+        BindingDevice::Gamepad => binding
+            .buttons
+            .iter()
+            .map(|button| layout.glyph(*button).to_string())
+            .collect(),
+    };
+    if labels.is_empty() {
+        "――".to_string()
+    } else {
+        labels.join(" / ")
+    }
+}
+
+/// 重なっている割り当ての説明（1件1行）
+fn conflict_lines(bindings: &InputBindings, layout: PadLayout) -> Vec<String> {
+    bindings
+        .conflicts()
+        .into_iter()
+        .map(|conflict| {
+            let input = match conflict.input {
+                InputKey::Key(key) => key_label(key),
+                InputKey::Pad(button) => layout.glyph(button).to_string(),
+            };
+            let mut users: Vec<String> = conflict.actions.iter().map(Action::label).collect();
+            if conflict.navigation {
+                users.push("フォーカスの移動".to_string());
+            }
+            format!("{input} が {} に重なっています", users.join("・"))
+        })
+        .collect()
+}
+
+/// 設定画面で描き直しの前後にフォーカスを保つボタン
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SettingsFocus {
+    TextSpeed,
+    Binding(Action, BindingDevice),
+}
+
+pub fn setup_settings_ui(
+    mut commands: Commands,
+    font: Res<GameFont>,
+    settings: Res<PlayerSettings>,
+    bindings: Res<InputBindings>,
+    menu: Res<SettingsMenu>,
+    device: Res<LastInputDevice>,
+) {
+    spawn_settings_ui(
+        &mut commands,
+        &font,
+        &settings,
+        &bindings,
+        &menu,
+        *device,
+        None,
+    );
+}
+
+/// 設定や入力待ち、表記に使うコントローラーが変わったら描き直す（フォーカスしていたボタンは描き直した後も選んだまま）
+#[allow(clippy::too_many_arguments)]
+pub fn refresh_settings_ui(
+    mut commands: Commands,
+    font: Res<GameFont>,
+    settings: Res<PlayerSettings>,
+    bindings: Res<InputBindings>,
+    menu: Res<SettingsMenu>,
+    device: Res<LastInputDevice>,
+    mut focus: ResMut<Focus>,
+    settings_ui_query: Query<Entity, With<SettingsUI>>,
+    focusable_query: Query<(Option<&BindingButton>, Has<TextSpeedButton>)>,
+) {
+    let changed =
+        menu.is_changed() || settings.is_changed() || bindings.is_changed() || device.is_changed();
+    if menu.is_added() || !changed {
+        return;
+    }
+
+    let focused = focus
+        .0
+        .and_then(|entity| focusable_query.get(entity).ok())
+        .and_then(|(binding, text_speed)| match (binding, text_speed) {
+            (Some(button), _) => Some(SettingsFocus::Binding(button.action, button.device)),
+            (None, true) => Some(SettingsFocus::TextSpeed),
+            (None, false) => None,
+        });
+    for entity in settings_ui_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let refocus = spawn_settings_ui(
+        &mut commands,
+        &font,
+        &settings,
+        &bindings,
+        &menu,
+        *device,
+        focused,
+    );
+    if refocus.is_some() {
+        focus.0 = refocus;
+    }
+}
+
+/// 文字の速さと、操作ごとのキーとコントローラーの割り当てを1行ずつ並べる
+///
+/// focused に当たるボタンを作ったらその Entity を返す。
+fn spawn_settings_ui(
+    commands: &mut Commands,
+    font: &GameFont,
+    settings: &PlayerSettings,
+    bindings: &InputBindings,
+    menu: &SettingsMenu,
+    device: LastInputDevice,
+    focused: Option<SettingsFocus>,
+) -> Option<Entity> {
+    let layout = match device {
+        LastInputDevice::Gamepad(layout) => layout,
+        LastInputDevice::KeyboardMouse => PadLayout::default(),
+    };
+    let text_style = |size: f32, color: Color| TextStyle {
+        font: font.0.clone(),
+        font_size: size,
+        color,
+    };
+    let mut refocus = None;
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.85).into(),
+                ..default()
+            },
+            SettingsUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "設定",
+                text_style(BUTTON_FONT_SIZE, TEXT_NORMAL_COLOR),
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(10.0),
+                        margin: UiRect::vertical(Val::Px(6.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn(
+                        TextBundle::from_section(
+                            "文字の速さ",
+                            text_style(CHOICE_FONT_SIZE, TEXT_NORMAL_COLOR),
+                        )
+                        .with_style(Style {
+                            width: Val::Px(160.0),
+                            ..default()
+                        }),
+                    );
+                    let entity = row
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(610.0),
+                                    height: Val::Px(SETTINGS_ROW_HEIGHT),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: BUTTON_NORMAL_COLOR.into(),
+                                ..default()
+                            },
+                            TextSpeedButton,
+                        ))
+                        .with_children(|button| {
+                            button.spawn(TextBundle::from_section(
+                                settings.text_speed.label(),
+                                text_style(CHOICE_FONT_SIZE - 2.0, TEXT_NORMAL_COLOR),
+                            ));
+                        })
+                        .id();
+                    if focused == Some(SettingsFocus::TextSpeed) {
+                        refocus = Some(entity);
+                    }
+                });
+
+            parent.spawn(TextBundle::from_section(
+                "押したあとに入力したキー・ボタンに置き換わります（右クリックかもう一度押すと取り消し）",
+                text_style(CHOICE_FONT_SIZE - 4.0, Color::srgb(0.8, 0.8, 0.8)),
+            ));
+
+            for action in Action::all() {
+                let label_color = if bindings.is_conflicting(action) {
+                    SETTINGS_CONFLICT_COLOR
+                } else {
+                    TEXT_NORMAL_COLOR
+                };
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn(
+                            TextBundle::from_section(
+                                action.label(),
+                                text_style(CHOICE_FONT_SIZE, label_color),
+                            )
+                            .with_style(Style {
+                                width: Val::Px(160.0),
+                                ..default()
+                            }),
+                        );
+
+                        for (device, width) in [
+                            (BindingDevice::Keyboard, 360.0),
+                            (BindingDevice::Gamepad, 240.0),
+                        ] {
+                            let capturing = menu.capture == Some((action, device));
+                            let entity = row
+                                .spawn((
+                                    ButtonBundle {
+                                        style: Style {
+                                            width: Val::Px(width),
+                                            height: Val::Px(SETTINGS_ROW_HEIGHT),
+                                            justify_content: JustifyContent::Center,
+                                            align_items: AlignItems::Center,
+                                            ..default()
+                                        },
+                                        background_color: if capturing {
+                                            BUTTON_PRESSED_COLOR.into()
+                                        } else {
+                                            BUTTON_NORMAL_COLOR.into()
+                                        },
+                                        ..default()
+                                    },
+                                    BindingButton { action, device },
+                                ))
+                                .with_children(|button| {
+                                    button.spawn(TextBundle::from_section(
+                                        binding_label(bindings, action, device, layout, capturing),
+                                        text_style(CHOICE_FONT_SIZE - 2.0, TEXT_NORMAL_COLOR),
+                                    ));
+                                })
+                                .id();
+                            if focused == Some(SettingsFocus::Binding(action, device)) {
+                                refocus = Some(entity);
+                            }
+                        }
+                    });
+            }
+
+            for line in conflict_lines(bindings, layout) {
+                parent.spawn(TextBundle::from_section(
+                    line,
+                    text_style(CHOICE_FONT_SIZE - 2.0, SETTINGS_CONFLICT_COLOR),
+                ));
+            }
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(20.0),
+                        margin: UiRect::top(Val::Px(8.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (label, is_reset) in [("初期設定に戻す", true), ("戻る", false)] {
+                        let mut button = parent.spawn(create_game_button());
+                        if is_reset {
+                            button.insert(ResetBindingsButton);
+                        } else {
+                            button.insert(SettingsBackButton);
+                        }
+                        button.with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                label,
+                                create_button_text_style(font.0.clone(), CHOICE_FONT_SIZE),
+                            ));
+                        });
+                    }
+                });
+        });
+
+    refocus
+}
+
+/// 「文章を隠す」の間、背景だけを見せる（覆いはクリックなどを受け止めて元に戻すためのもの）
+pub fn spawn_hidden_ui_overlay(commands: &mut Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            focus_policy: bevy::ui::FocusPolicy::Block,
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+        HiddenUIOverlay,
+        FocusScope,
+    ));
+}
+
+pub fn setup_slot_menu_ui(
+    mut commands: Commands,
+    font: Res<GameFont>,
+    save_manager: Res<SaveManager>,
+    slot_menu: Res<SlotMenu>,
+) {
+    spawn_slot_menu_ui(&mut commands, &font, &save_manager, &slot_menu);
+}
+
+/// セーブ後にスロットの表示を更新する
+pub fn refresh_slot_menu_ui(
+    mut commands: Commands,
+    font: Res<GameFont>,
+    save_manager: Res<SaveManager>,
+    slot_menu: Res<SlotMenu>,
+    slot_ui_query: Query<Entity, With<SlotUI>>,
+) {
+    if !slot_menu.is_changed() || slot_menu.is_added() {
+        return;
+    }
+
+    for entity in slot_ui_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    spawn_slot_menu_ui(&mut commands, &font, &save_manager, &slot_menu);
+}
+
+fn spawn_slot_menu_ui(
+    commands: &mut Commands,
+    font: &GameFont,
+    save_manager: &SaveManager,
+    slot_menu: &SlotMenu,
+) {
+    let heading = match slot_menu.mode {
+        SlotMode::Save => "セーブ",
+        SlotMode::Load => "ロード",
+    };
+    let text_style = |size: f32, color: Color| TextStyle {
+        font: font.0.clone(),
+        font_size: size,
+        color,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.85).into(),
+                ..default()
+            },
+            SlotUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                heading,
+                text_style(TITLE_FONT_SIZE, TEXT_NORMAL_COLOR),
+            ));
+
+            for (slot, loaded) in save_manager.list() {
+                // オートセーブ・クイックセーブには手動で書き込めず、空きスロットや読めないスロットは読み込めない
+                let enabled = match slot_menu.mode {
+                    SlotMode::Save => slot.is_manual(),
+                    SlotMode::Load => matches!(loaded, Ok(Some(_))),
+                };
+                let (summary, detail) = match &loaded {
+                    Ok(Some(loaded)) => {
+                        let save_data = &loaded.data;
+                        (
+                            format!(
+                                "{}　{}　プレイ時間 {}",
+                                slot.label(),
+                                save_data.saved_at_label(),
+                                format_play_time(save_data.play_time())
+                            ),
+                            format!("{}　{}", save_data.scenario_title, save_data.excerpt),
+                        )
+                    }
+                    Ok(None) => (format!("{}　―― 空き ――", slot.label()), String::new()),
+                    Err(e) => (
+                        format!("{}　―― 読み込めません ――", slot.label()),
+                        load_error_message(slot, e),
+                    ),
+                };
+                let text_color = if enabled {
+                    TEXT_NORMAL_COLOR
+                } else {
+                    TEXT_DISABLED_COLOR
+                };
+                // 空きスロット以外は消せる（読めないスロットも片付けられるように）
+                let deletable = !matches!(loaded, Ok(None));
+                let pending_delete = slot_menu.pending_delete == Some(slot);
+
+                let mut row = parent.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                });
+                row.with_children(|parent| {
+                    let mut slot_entity = parent.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(510.0),
+                                height: Val::Px(60.0),
+                                padding: UiRect::axes(Val::Px(16.0), Val::Px(6.0)),
+                                flex_direction: FlexDirection::Column,
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            background_color: if enabled {
+                                BUTTON_NORMAL_COLOR.into()
+                            } else {
+                                BUTTON_DISABLED_COLOR.into()
+                            },
+                            ..default()
+                        },
+                        SlotButton(slot),
+                    ));
+
+                    if !enabled {
+                        slot_entity.insert(Disabled);
+                    }
+
+                    slot_entity.with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            summary,
+                            text_style(CHOICE_FONT_SIZE, text_color),
+                        ));
+                        parent.spawn(TextBundle::from_section(
+                            detail,
+                            text_style(CHOICE_FONT_SIZE - 4.0, Color::srgb(0.8, 0.8, 0.8)),
+                        ));
+                    });
+
+                    let delete_style = Style {
+                        width: Val::Px(120.0),
+                        height: Val::Px(60.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    };
+                    if !deletable {
+                        parent.spawn(NodeBundle {
+                            style: delete_style,
+                            ..default()
+                        });
+                        return;
+                    }
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: delete_style,
+                                background_color: if pending_delete {
+                                    Color::srgb(0.6, 0.3, 0.3).into()
+                                } else {
+                                    BUTTON_NORMAL_COLOR.into()
+                                },
+                                ..default()
+                            },
+                            DeleteSlotButton(slot),
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                if pending_delete {
+                                    "本当に削除"
+                                } else {
+                                    "削除"
+                                },
+                                text_style(CHOICE_FONT_SIZE, TEXT_NORMAL_COLOR),
+                            ));
+                        });
+                });
+            }
+
+            parent
+                .spawn((create_game_button(), SlotBackButton))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "戻る",
+                        create_button_text_style(font.0.clone(), BUTTON_FONT_SIZE),
+                    ));
+                });
+        });
+}
+
+pub fn setup_ending_ui(
+    mut commands: Commands,
+    font: Res<GameFont>,
+    scenario_data: Res<ScenarioData>,
+    current: Res<Current>,
+    profile: Option<Res<Profile>>,
+    replay: Option<Res<GalleryReplay>>,
+) {
+    let replay = replay.is_some();
+    let node = scenario_data.get_node_or_fallback(&current.id);
+    let ending = match node.ending.as_ref() {
+        Some(ending) => ending,
+        None => {
+            error!(
+                key = "ui.ending.node_missing",
+                id = %current.id,
+                "Node has no ending data, returning early"
+            );
+            return;
+        }
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(30.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                ..default()
+            },
+            EndingUI,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(80.0),
+                        max_width: Val::Px(600.0),
+                        padding: UiRect::all(Val::Px(30.0)),
+                        align_items: AlignItems::Center,
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    background_color: Color::srgba(0.2, 0.2, 0.2, 0.9).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        node.full_text(),
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: GAME_TEXT_FONT_SIZE,
+                            color: TEXT_NORMAL_COLOR,
+                        },
+                    ));
+
+                    let route = scenario_data
+                        .route_id_of(&current)
+                        .unwrap_or_else(|| current.id.clone());
+                    parent.spawn(TextBundle::from_section(
+                        format!("ルートID: {route}"),
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: CHOICE_FONT_SIZE,
+                            color: Color::srgb(0.8, 0.8, 0.8),
+                        },
+                    ));
+
+                    parent.spawn(TextBundle::from_section(
+                        &ending.tag,
+                        TextStyle {
+                            font: font.0.clone(),
+                            font_size: 32.0, // エンディング名は特別なので固有のサイズを保持
+                            color: Color::srgb(1.0, 0.8, 0.0),
+                        },
+                    ));
+
+                    if let Some(profile) = &profile {
+                        let (unlocked, total) = profile.progress(&scenario_data);
+                        let first_time = !replay
+                            && profile
+                                .get(&route)
+                                .is_some_and(|record| record.reach_count == 1);
+                        let label = if first_time {
+                            format!("新しいエンディング！（収集 {unlocked}/{total}）")
+                        } else {
+                            format!("収集 {unlocked}/{total}")
+                        };
+                        parent.spawn(TextBundle::from_section(
+                            label,
+                            TextStyle {
+                                font: font.0.clone(),
+                                font_size: CHOICE_FONT_SIZE,
+                                color: Color::srgb(0.8, 0.8, 0.8),
+                            },
+                        ));
+                    }
+                });
+
+            parent
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(150.0), // Restartボタンのみ異なる幅
+                            height: Val::Px(50.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::srgb(0.6, 0.4, 0.4).into(),
+                        ..default()
+                    },
+                    RestartButton,
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        if replay {
+                            "ギャラリーへ"
+                        } else {
+                            "もう一度"
+                        },
+                        create_button_text_style(font.0.clone(), RESTART_FONT_SIZE),
+                    ));
+                });
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| spawn_history_buttons(parent, &font, &current));
+        });
+}
+
+/// ルートマップの表示領域（ウィンドウは固定サイズなので定数で持つ）
+const ROUTE_MAP_VIEW_WIDTH: f32 = 1220.0;
+const ROUTE_MAP_VIEW_HEIGHT: f32 = 560.0;
+/// 1マスの大きさと、階層ごとの列の幅・葉ごとの行の高さ
+const ROUTE_MAP_NODE_WIDTH: f32 = 140.0;
+const ROUTE_MAP_NODE_HEIGHT: f32 = 20.0;
+const ROUTE_MAP_COLUMN_WIDTH: f32 = 170.0;
+const ROUTE_MAP_ROW_HEIGHT: f32 = 24.0;
+const ROUTE_MAP_LINE_WIDTH: f32 = 2.0;
+
+const ROUTE_MAP_TRAIL_COLOR: Color = Color::srgb(0.85, 0.65, 0.15);
+const ROUTE_MAP_VISITED_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+const ROUTE_MAP_UNEXPLORED_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
+
+/// 各マスの左上の位置（階層で列、葉の並び順で行を決め、親は子の行の中央に置く）
+///
+/// tree は ScenarioData::route_tree の順（親が子より先）であること。
+fn route_map_layout(tree: &[RouteMapNode]) -> Vec<Vec2> {
+    let mut children = vec![Vec::new(); tree.len()];
+    for (i, node) in tree.iter().enumerate() {
+        if let Some(parent) = node.parent {
+            children[parent].push(i);
+        }
+    }
+
+    // 深さ優先の順なので、後ろから見れば子は必ず親より先に位置が決まる
+    let mut leaf_rows = vec![0.0; tree.len()];
+    let mut next_leaf = 0.0;
+    for (i, kids) in children.iter().enumerate() {
+        if kids.is_empty() {
+            leaf_rows[i] = next_leaf;
+            next_leaf += 1.0;
+        }
+    }
+    let mut rows = leaf_rows;
+    for i in (0..tree.len()).rev() {
+        if let (Some(first), Some(last)) = (children[i].first(), children[i].last()) {
+            rows[i] = (rows[*first] + rows[*last]) / 2.0;
+        }
+    }
+
+    tree.iter()
+        .zip(rows)
+        .map(|(node, row)| {
+            Vec2::new(
+                node.depth as f32 * ROUTE_MAP_COLUMN_WIDTH,
+                row * ROUTE_MAP_ROW_HEIGHT,
+            )
+        })
+        .collect()
+}
+
+/// ルートマップの中身（left/top をずらしてスクロールする）
+#[derive(Component)]
+pub struct RouteMapContent {
+    pub offset: Vec2,
+    pub size: Vec2,
+}
+
+impl RouteMapContent {
+    fn clamp(&mut self) {
+        let min =
+            (Vec2::new(ROUTE_MAP_VIEW_WIDTH, ROUTE_MAP_VIEW_HEIGHT) - self.size).min(Vec2::ZERO);
+        self.offset = self.offset.clamp(min, Vec2::ZERO);
+    }
+}
+
+/// シナリオの木を描く
+///
+/// 訪れた場面は選択肢の文言を出して押せるようにし、今の経路は色を変える。
+/// 訪れていない場面は ? だけを出して暗くする。
+pub fn setup_route_map_ui(
+    mut commands: Commands,
+    font: Res<GameFont>,
+    scenario_data: Res<ScenarioData>,
+    current: Res<Current>,
+    profile: Res<Profile>,
+) {
+    let tree = scenario_data.route_tree();
+    let positions = route_map_layout(&tree);
+    let current_route = scenario_data
+        .route_id_of(&current)
+        .unwrap_or_else(|| current.id.clone());
+    let on_trail = |route: &str| current_route.starts_with(route);
+
+    let size = positions
+        .iter()
+        .fold(Vec2::ZERO, |size, pos| size.max(*pos))
+        + Vec2::new(ROUTE_MAP_NODE_WIDTH, ROUTE_MAP_NODE_HEIGHT);
+    // 最初は今いる場面が真ん中あたりに来るようにする
+    let focus = tree
+        .iter()
+        .position(|node| node.route_id == current_route)
+        .map(|i| positions[i])
+        .unwrap_or_default();
+    let mut content = RouteMapContent {
+        offset: Vec2::new(ROUTE_MAP_VIEW_WIDTH, ROUTE_MAP_VIEW_HEIGHT) / 2.0 - focus,
+        size,
+    };
+    content.clamp();
+
+    let text_style = |size: f32, color: Color| TextStyle {
+        font: font.0.clone(),
+        font_size: size,
+        color,
+    };
+    let line = |left: f32, top: f32, width: f32, height: f32, color: Color| NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(left),
+            top: Val::Px(top),
+            width: Val::Px(width),
+            height: Val::Px(height),
+            ..default()
+        },
+        background_color: color.into(),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(20.0)),
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.9).into(),
+                ..default()
+            },
+            RouteMapUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "ルートマップ",
+                text_style(BUTTON_FONT_SIZE, TEXT_NORMAL_COLOR),
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(ROUTE_MAP_VIEW_WIDTH),
+                        height: Val::Px(ROUTE_MAP_VIEW_HEIGHT),
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|viewport| {
+                    viewport
+                        .spawn(NodeBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(content.offset.x),
+                                top: Val::Px(content.offset.y),
+                                width: Val::Px(size.x),
+                                height: Val::Px(size.y),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .insert(content)
+                        .with_children(|map| {
+                            let stub = (ROUTE_MAP_COLUMN_WIDTH - ROUTE_MAP_NODE_WIDTH) / 2.0;
+                            let middle = (ROUTE_MAP_NODE_HEIGHT - ROUTE_MAP_LINE_WIDTH) / 2.0;
+
+                            // 親から子への線（親の右端 → 列の間で縦に → 子の左端）
+                            for (node, pos) in tree.iter().zip(&positions) {
+                                let Some(parent_pos) = node.parent.map(|p| positions[p]) else {
+                                    continue;
+                                };
+                                let color = if on_trail(&node.route_id) {
+                                    ROUTE_MAP_TRAIL_COLOR
+                                } else if profile.is_visited(&node.route_id) {
+                                    ROUTE_MAP_VISITED_COLOR
+                                } else {
+                                    ROUTE_MAP_UNEXPLORED_COLOR
+                                };
+                                let elbow = parent_pos.x + ROUTE_MAP_NODE_WIDTH + stub;
+                                let top = parent_pos.y.min(pos.y) + middle;
+                                let bottom = parent_pos.y.max(pos.y) + middle;
+                                map.spawn(line(
+                                    parent_pos.x + ROUTE_MAP_NODE_WIDTH,
+                                    parent_pos.y + middle,
+                                    stub,
+                                    ROUTE_MAP_LINE_WIDTH,
+                                    color,
+                                ));
+                                map.spawn(line(
+                                    elbow,
+                                    top,
+                                    ROUTE_MAP_LINE_WIDTH,
+                                    bottom - top + ROUTE_MAP_LINE_WIDTH,
+                                    color,
+                                ));
+                                map.spawn(line(
+                                    elbow,
+                                    pos.y + middle,
+                                    stub,
+                                    ROUTE_MAP_LINE_WIDTH,
+                                    color,
+                                ));
+                            }
+
+                            for (node, pos) in tree.iter().zip(&positions) {
+                                let visited = profile.is_visited(&node.route_id);
+                                let (background, label) = if !visited {
+                                    (ROUTE_MAP_UNEXPLORED_COLOR, "？".to_string())
+                                } else {
+                                    let mut label = node
+                                        .choice_label
+                                        .clone()
+                                        .unwrap_or_else(|| "はじまり".to_string());
+                                    if let Some(ending) = scenario_data
+                                        .get_node(&node.node_id)
+                                        .and_then(|n| n.ending.as_ref())
+                                    {
+                                        label = format!("{label}（{}）", ending.tag);
+                                    }
+                                    let background = if on_trail(&node.route_id) {
+                                        ROUTE_MAP_TRAIL_COLOR
+                                    } else {
+                                        BUTTON_NORMAL_COLOR
+                                    };
+                                    (background, label)
+                                };
+
+                                let mut entity = map.spawn((
+                                    ButtonBundle {
+                                        style: Style {
+                                            position_type: PositionType::Absolute,
+                                            left: Val::Px(pos.x),
+                                            top: Val::Px(pos.y),
+                                            width: Val::Px(ROUTE_MAP_NODE_WIDTH),
+                                            height: Val::Px(ROUTE_MAP_NODE_HEIGHT),
+                                            padding: UiRect::horizontal(Val::Px(4.0)),
+                                            align_items: AlignItems::Center,
+                                            overflow: Overflow::clip(),
+                                            ..default()
+                                        },
+                                        background_color: background.into(),
+                                        ..default()
+                                    },
+                                    RouteMapNodeButton(node.route_id.clone()),
+                                ));
+                                if !visited {
+                                    entity.insert(Disabled);
+                                }
+                                entity.with_children(|button| {
+                                    button.spawn(TextBundle::from_section(
+                                        label,
+                                        text_style(
+                                            12.0,
+                                            if visited {
+                                                TEXT_NORMAL_COLOR
+                                            } else {
+                                                TEXT_DISABLED_COLOR
+                                            },
+                                        ),
+                                    ));
+                                });
+                            }
+                        });
+                });
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn(TextBundle::from_section(
+                        "訪れた場面を選ぶとそこへ移動します（ホイールでスクロール、Shift で横）",
+                        text_style(CHOICE_FONT_SIZE, Color::srgb(0.8, 0.8, 0.8)),
+                    ));
+                    row.spawn((create_game_button(), RouteMapBackButton))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                "戻る",
+                                create_button_text_style(font.0.clone(), BUTTON_FONT_SIZE),
+                            ));
+                        });
+                });
+        });
+}
+
+/// ホイールでルートマップをスクロールする（Shift を押しながらで横）
+pub fn scroll_route_map(
+    mut wheel_events: EventReader<bevy::input::mouse::MouseWheel>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut content_query: Query<(&mut RouteMapContent, &mut Style)>,
+) {
+    let horizontal = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for event in wheel_events.read() {
+        let mut delta = match event.unit {
+            bevy::input::mouse::MouseScrollUnit::Line => {
+                Vec2::new(event.x, event.y) * ROUTE_MAP_ROW_HEIGHT
+            }
+            bevy::input::mouse::MouseScrollUnit::Pixel => Vec2::new(event.x, event.y),
+        };
+        if horizontal {
+            delta = Vec2::new(delta.y, delta.x);
+        }
+        for (mut content, mut style) in content_query.iter_mut() {
+            content.offset += delta;
+            content.clamp();
+            style.left = Val::Px(content.offset.x);
+            style.top = Val::Px(content.offset.y);
+        }
+    }
+}
+
+/// ギャラリーの横幅（マスの大きさはエンディング数に合わせて決める）
+const GALLERY_WIDTH: f32 = 1000.0;
+const GALLERY_GAP: f32 = 6.0;
+const GALLERY_CELL_MAX_HEIGHT: f32 = 64.0;
+const GALLERY_MAX_COLUMNS: usize = 16;
+
+/// エンディング数に応じた列数（64 なら 8×8 になるよう平方根で決める）
+fn gallery_columns(total: usize) -> usize {
+    ((total as f32).sqrt().ceil() as usize).clamp(1, GALLERY_MAX_COLUMNS)
+}
+
+/// エンディングをルートIDの格子で並べる
+///
+/// 到達済みのマスはエンディング名と背景を、未到達のマスは ??? を表示する。
+pub fn setup_gallery_ui(
+    mut commands: Commands,
+    font: Res<GameFont>,
+    asset_server: Res<AssetServer>,
+    scenario_data: Res<ScenarioData>,
+    profile: Res<Profile>,
+) {
+    let ending_ids = scenario_data.ending_ids();
+    let (unlocked, total) = profile.progress(&scenario_data);
+    let columns = gallery_columns(total);
+    let cell_width = GALLERY_WIDTH / columns as f32 - GALLERY_GAP;
+    let cell_height = (cell_width * 0.5).min(GALLERY_CELL_MAX_HEIGHT);
+    let text_style = |size: f32, color: Color| TextStyle {
+        font: font.0.clone(),
+        font_size: size,
+        color,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.85).into(),
+                ..default()
+            },
+            GalleryUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("ギャラリー　{unlocked}/{total}"),
+                text_style(BUTTON_FONT_SIZE, TEXT_NORMAL_COLOR),
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        display: Display::Grid,
+                        grid_template_columns: RepeatedGridTrack::px(columns as u16, cell_width),
+                        grid_auto_rows: vec![GridTrack::px(cell_height)],
+                        row_gap: Val::Px(GALLERY_GAP),
+                        column_gap: Val::Px(GALLERY_GAP),
+                        max_height: Val::Percent(75.0),
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|grid| {
+                    for route in &ending_ids {
+                        let ending = profile
+                            .get(route)
+                            .and_then(|_| scenario_data.current_for_route(route))
+                            .and_then(|current| scenario_data.get_node(&current.id))
+                            .filter(|node| node.ending.is_some());
+
+                        let mut cell = grid.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Column,
+                                    justify_content: JustifyContent::End,
+                                    ..default()
+                                },
+                                background_color: BUTTON_DISABLED_COLOR.into(),
+                                ..default()
+                            },
+                            GalleryEntryButton(route.clone()),
+                        ));
+
+                        let Some(node) = ending else {
+                            cell.insert(Disabled).with_children(|cell| {
+                                cell.spawn(TextBundle::from_section(
+                                    format!("{route}\n???"),
+                                    text_style(14.0, TEXT_DISABLED_COLOR),
+                                ));
+                            });
+                            continue;
+                        };
+
+                        let bg = node
+                            .bg
+                            .as_ref()
+                            .unwrap_or(&scenario_data.scenario.meta.default_background);
+                        if !bg.is_empty() {
+                            cell.insert(UiImage::new(asset_server.load(bg.clone())));
+                        }
+                        let tag = node.ending.as_ref().map(|e| e.tag.as_str());
+                        cell.with_children(|cell| {
+                            cell.spawn(NodeBundle {
+                                background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+                                ..default()
+                            })
+                            .with_children(|label| {
+                                label.spawn(TextBundle::from_section(
+                                    format!("{route}\n{}", tag.unwrap_or_default()),
+                                    text_style(14.0, TEXT_NORMAL_COLOR),
+                                ));
+                            });
+                        });
+                    }
+                });
+
+            parent.spawn((
+                TextBundle::from_section(
+                    "到達したエンディングを選ぶと最後の場面を見返せます",
+                    text_style(CHOICE_FONT_SIZE, Color::srgb(0.8, 0.8, 0.8)),
+                )
+                .with_style(Style {
+                    max_width: Val::Px(GALLERY_WIDTH),
+                    ..default()
+                }),
+                GalleryDetailText,
+            ));
+
+            parent
+                .spawn((create_game_button(), GalleryBackButton))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "戻る",
+                        create_button_text_style(font.0.clone(), BUTTON_FONT_SIZE),
+                    ));
+                });
+        });
+}
+
+/// 押せる（到達済みの）ギャラリーのマス
+pub type GalleryEntryQuery<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Interaction, &'static GalleryEntryButton),
+    (Changed<Interaction>, Without<Disabled>),
+>;
+
+/// カーソルを合わせた到達済みエンディングの本文を表示する
+pub fn update_gallery_detail(
+    entry_query: GalleryEntryQuery,
+    mut detail_query: Query<&mut Text, With<GalleryDetailText>>,
+    scenario_data: Res<ScenarioData>,
+) {
+    let Some(route) = entry_query
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+        .map(|(_, entry)| &entry.0)
+    else {
+        return;
+    };
+    let Some(node) = scenario_data
+        .current_for_route(route)
+        .and_then(|current| scenario_data.get_node(&current.id))
+    else {
+        return;
+    };
+    for mut text in detail_query.iter_mut() {
+        text.sections[0].value = node.full_text();
+    }
+}
+
+/// お知らせを表示しておく秒数
+const NOTICE_SECONDS: f32 = 5.0;
+
+/// 画面上部にお知らせを出す（新しいものが来たら差し替える）
+pub fn show_notices(
+    mut commands: Commands,
+    mut notice_events: EventReader<ShowNotice>,
+    font: Option<Res<GameFont>>,
+    notice_query: Query<Entity, With<NoticeUI>>,
+) {
+    let Some(message) = notice_events.read().last().map(|e| e.0.clone()) else {
+        return;
+    };
+
+    for entity in notice_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(20.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            NoticeUI(Timer::from_seconds(NOTICE_SECONDS, TimerMode::Once)),
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        max_width: Val::Px(900.0),
+                        padding: UiRect::axes(Val::Px(20.0), Val::Px(10.0)),
+                        ..default()
+                    },
+                    background_color: Color::srgba(0.4, 0.3, 0.1, 0.9).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        message,
+                        TextStyle {
+                            font: font.map(|f| f.0.clone()).unwrap_or_default(),
+                            font_size: CHOICE_FONT_SIZE,
+                            color: TEXT_NORMAL_COLOR,
+                        },
+                    ));
+                });
+        });
+}
+
+pub fn expire_notices(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut notice_query: Query<(Entity, &mut NoticeUI)>,
+) {
+    for (entity, mut notice) in notice_query.iter_mut() {
+        if notice.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// 起動に失敗したときのエラー画面
+///
+/// フォントの読み込み自体が失敗している可能性があるため、GameFont が使えない場合は
+/// Bevy の既定フォントと英語の文言で表示する。
+pub fn setup_error_ui(
+    mut commands: Commands,
+    boot_error: Option<Res<BootError>>,
+    font: Option<Res<GameFont>>,
+    fonts: Res<Assets<Font>>,
+) {
+    let Some(boot_error) = boot_error else {
+        error!(
+            key = "ui.error.missing",
+            "Entered error state without BootError"
+        );
+        return;
+    };
+    let failure = &boot_error.0;
+
+    let font = font
+        .map(|f| f.0.clone())
+        .filter(|handle| fonts.contains(handle));
+    let japanese = font.is_some();
+    let text_style = |size: f32, color: Color| TextStyle {
+        font: font.clone().unwrap_or_default(),
+        font_size: size,
+        color,
+    };
+    let (retry_label, quit_label) = if japanese {
+        ("再試行", "終了")
+    } else {
+        ("Retry", "Quit")
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(30.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.9).into(),
+                ..default()
+            },
+            ErrorUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                failure.heading(japanese),
+                text_style(GAME_TEXT_FONT_SIZE, Color::srgb(1.0, 0.5, 0.4)),
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(80.0),
+                        max_width: Val::Px(900.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    background_color: Color::srgba(0.2, 0.2, 0.2, 0.8).into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        failure.detail(),
+                        text_style(CHOICE_FONT_SIZE, Color::srgb(0.8, 0.8, 0.8)),
+                    ));
+                });
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn((create_game_button(), RetryButton))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                retry_label,
+                                text_style(BUTTON_FONT_SIZE, TEXT_NORMAL_COLOR),
+                            ));
+                        });
+
+                    parent
+                        .spawn((create_game_button(), QuitButton))
+                        .with_children(|parent| {
+                            parent.spawn(TextBundle::from_section(
+                                quit_label,
+                                text_style(BUTTON_FONT_SIZE, TEXT_NORMAL_COLOR),
+                            ));
+                        });
+                });
+        });
+}
+
+pub fn update_background(
+    mut background_query: Query<&mut Handle<Image>, With<BackgroundSprite>>,
+    asset_server: Res<AssetServer>,
+    scenario_data: Res<ScenarioData>,
+    current: Res<Current>,
+) {
+    if current.is_changed() || scenario_data.is_changed() {
+        if let Ok(mut background_handle) = background_query.get_single_mut() {
+            let new_bg = if let Some(node) = scenario_data.get_node(&current.id) {
+                node.bg
+                    .as_ref()
+                    .unwrap_or(&scenario_data.scenario.meta.default_background)
+            } else {
+                &scenario_data.scenario.meta.default_background
+            };
+
+            *background_handle = asset_server.load(new_bg);
+        }
+    }
+}
+
+type ButtonInteractionQuery<'w, 's> = Query<
     'w,
     's,
     (&'static Interaction, &'static mut BackgroundColor),
@@ -384,10 +2208,87 @@ pub fn button_interaction_system(mut interaction_query: ButtonInteractionQuery)
 #[derive(Component)]
 pub struct GameText;
 
+/// Playing の選択肢を並べた行（本文を出し終えるまで隠す）
+#[derive(Component)]
+pub struct ChoiceList;
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_route_map_layout() {
+        let scenario_data = ScenarioData::load_from_json(
+            r#"{
+              "meta": {"title": "Test", "depth": 2, "default_background": "", "rain_bgm": "", "font": ""},
+              "nodes": [
+                {"id": "R", "text": "root", "choices": [{"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}]},
+                {"id": "R1", "text": "r1", "choices": [{"label": "a", "to": "R11"}, {"label": "b", "to": "R10"}]},
+                {"id": "R0", "text": "r0", "ending": {"tag": "C"}},
+                {"id": "R11", "text": "r11", "ending": {"tag": "A"}},
+                {"id": "R10", "text": "r10", "ending": {"tag": "B"}}
+              ]
+            }"#,
+        )
+        .unwrap();
+        let tree = scenario_data.route_tree();
+        let rows: Vec<_> = route_map_layout(&tree)
+            .iter()
+            .map(|pos| (pos.x / ROUTE_MAP_COLUMN_WIDTH, pos.y / ROUTE_MAP_ROW_HEIGHT))
+            .collect();
+
+        // 葉は上から順に並び、親は子の真ん中に来る
+        assert_eq!(
+            rows,
+            vec![(0.0, 1.25), (1.0, 0.5), (2.0, 0.0), (2.0, 1.0), (1.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn test_stuck_node_offers_title_button() {
+        let scenario_data = ScenarioData::load_from_json(
+            r#"{
+              "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": "",
+                       "variables": {"key": false}},
+              "nodes": [
+                {"id": "R", "text": "root", "choices": [
+                  {"label": "a", "to": "R1", "condition": {"op": "eq", "var": "key", "value": true}},
+                  {"label": "b", "to": "R0", "condition": {"op": "eq", "var": "key", "value": true},
+                   "on_unmet": "disable"}
+                ]},
+                {"id": "R1", "text": "e", "ending": {"tag": "A"}},
+                {"id": "R0", "text": "e", "ending": {"tag": "B"}}
+              ]
+            }"#,
+        )
+        .unwrap();
+        let mut app = App::new();
+        app.insert_resource(GameFont(Handle::default()))
+            .insert_resource(PlayerSettings::default())
+            .insert_resource(scenario_data.initial_current())
+            .insert_resource(scenario_data)
+            .add_systems(Update, setup_playing_ui);
+        app.update();
+
+        let world = app.world_mut();
+        let disabled: Vec<_> = world
+            .query::<(&ChoiceButton, Has<Disabled>)>()
+            .iter(world)
+            .map(|(_, disabled)| disabled)
+            .collect();
+        assert_eq!(disabled, vec![true, true]);
+        assert_eq!(world.query::<&StuckTitleButton>().iter(world).count(), 1);
+    }
+
+    #[test]
+    fn test_gallery_columns() {
+        assert_eq!(gallery_columns(4), 2);
+        assert_eq!(gallery_columns(8), 3);
+        assert_eq!(gallery_columns(64), 8);
+        assert_eq!(gallery_columns(0), 1);
+        assert_eq!(gallery_columns(1000), GALLERY_MAX_COLUMNS);
+    }
+
     #[test]
     fn test_game_font_resource() {
         let font_handle: Handle<Font> = Handle::weak_from_u128(12345);
@@ -0,0 +1,812 @@
+use crate::route_id;
+use crate::scenario::{Condition, Effect, ScenarioData};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use thiserror::Error;
+
+/// シナリオ読み込みのエラー
+#[derive(Debug, Error)]
+pub enum ScenarioError {
+    #[error("Failed to parse scenario JSON: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Scenario validation failed:\n{0}")]
+    Invalid(ValidationReport),
+}
+
+/// 検証結果の重要度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// 動作はするが作者に知らせたいもの
+    Warning,
+    /// シナリオとして読み込めないもの
+    Error,
+}
+
+/// 検証で見つかった問題の種類
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IssueKind {
+    #[error("failed to parse scenario JSON: {message}")]
+    ParseError {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    #[error("scenario has no nodes")]
+    EmptyScenario,
+    #[error("start node '{start}' does not exist")]
+    StartNodeMissing { start: String },
+    #[error("duplicate node id")]
+    DuplicateId,
+    #[error("choice points to non-existent node '{to}'")]
+    DanglingReference { to: String },
+    #[error("node is not reachable from the start node")]
+    UnreachableNode,
+    #[error("node has no choices and no ending")]
+    LeafWithoutEnding,
+    #[error("node has an ending but also has choices")]
+    EndingWithChoices,
+    #[error("node is at depth {depth} but meta.depth is {max_depth}")]
+    IdDepthMismatch { depth: usize, max_depth: usize },
+    #[error("choice points to '{to}' but its route ID should be '{expected}'")]
+    RouteIdMismatch { to: String, expected: String },
+    #[error("every choice has a condition (the player is stuck if none of them is met)")]
+    AllChoicesConditional,
+    #[error("node has both text and pages (use one of them)")]
+    TextAndPages,
+    #[error("text_speed must be at least 1 character per second")]
+    ZeroTextSpeed,
+    #[error("node has {actual} choices (max {max})")]
+    TooManyBranches { actual: usize, max: usize },
+    #[error("unguarded loop: {} (add a condition to one of these choices)", cycle.join(" -> "))]
+    UnguardedLoop { cycle: Vec<String> },
+    #[error("variable '{var}' is not declared in meta")]
+    UndeclaredVariable { var: String },
+    #[error("variable '{var}' ({declared}) is used as {used_as}")]
+    VariableTypeMismatch {
+        var: String,
+        declared: &'static str,
+        used_as: &'static str,
+    },
+    #[error("{field} '{path}' does not exist in the asset directory")]
+    MissingAsset { field: &'static str, path: String },
+    #[error("{field} '{path}' is not a supported {expected} file")]
+    WrongAssetType {
+        field: &'static str,
+        path: String,
+        expected: &'static str,
+    },
+}
+
+impl IssueKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            IssueKind::UnreachableNode | IssueKind::AllChoicesConditional => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// 検証で見つかった問題1件（重要度と場所つき）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    /// 問題のあるノードのID（シナリオ全体の問題では None）
+    pub node: Option<String>,
+    /// 問題のある選択肢の添字
+    pub choice: Option<usize>,
+    #[serde(flatten)]
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "[{severity}]")?;
+        if let Some(node) = &self.node {
+            write!(f, " node '{node}'")?;
+        }
+        if let Some(choice) = self.choice {
+            write!(f, " choice {choice}")?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+/// シナリオ検証の全結果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    fn push(&mut self, node: Option<&str>, choice: Option<usize>, kind: IssueKind) {
+        self.issues.push(ValidationIssue {
+            severity: kind.severity(),
+            node: node.map(str::to_string),
+            choice,
+            kind,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// JSON として読めなかったことを1件の問題として表す（--json で報告するため）
+    pub fn from_parse_error(error: &serde_json::Error) -> Self {
+        let mut report = Self::default();
+        report.push(
+            None,
+            None,
+            IssueKind::ParseError {
+                message: error.to_string(),
+                line: error.line(),
+                column: error.column(),
+            },
+        );
+        report
+    }
+
+    /// 別の検証結果を後ろに連結する
+    pub fn merge(&mut self, other: ValidationReport) {
+        self.issues.extend(other.issues);
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter().filter(|i| i.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == Severity::Warning)
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl ScenarioData {
+    /// シナリオ全体を検証し、見つかった問題をすべて返す
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::default();
+
+        if self.scenario.nodes.is_empty() {
+            report.push(None, None, IssueKind::EmptyScenario);
+            return report;
+        }
+
+        self.validate_ids(&mut report);
+        self.validate_references(&mut report);
+        self.validate_branching(&mut report);
+        self.validate_variables(&mut report);
+        self.validate_endings(&mut report);
+        self.validate_text(&mut report);
+        report
+    }
+
+    /// シナリオが参照するアセットが asset_root 以下に、正しい種類で存在するかを確認する
+    pub fn validate_assets(&self, asset_root: &Path) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let meta = &self.scenario.meta;
+
+        let mut check = |node: Option<&str>, field: &'static str, kind: AssetKind, path: &str| {
+            if path.is_empty() {
+                return;
+            }
+            if let Some(issue) = check_asset(asset_root, field, kind, path) {
+                report.push(node, None, issue);
+            }
+        };
+
+        check(
+            None,
+            "default_background",
+            AssetKind::Image,
+            &meta.default_background,
+        );
+        check(None, "rain_bgm", AssetKind::Audio, &meta.rain_bgm);
+        check(None, "font", AssetKind::Font, &meta.font);
+        for node in &self.scenario.nodes {
+            if let Some(bg) = &node.bg {
+                check(Some(&node.id), "bg", AssetKind::Image, bg);
+            }
+        }
+        report
+    }
+
+    fn validate_ids(&self, report: &mut ValidationReport) {
+        let mut seen = HashSet::new();
+        for node in &self.scenario.nodes {
+            if !seen.insert(node.id.as_str()) {
+                report.push(Some(&node.id), None, IssueKind::DuplicateId);
+            }
+        }
+    }
+
+    fn validate_references(&self, report: &mut ValidationReport) {
+        for node in &self.scenario.nodes {
+            for (i, choice) in node.choices.iter().enumerate() {
+                if !self.nodes.contains_key(&choice.to) {
+                    report.push(
+                        Some(&node.id),
+                        Some(i),
+                        IssueKind::DanglingReference {
+                            to: choice.to.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        self.validate_loops(report);
+        self.validate_reachability(report);
+    }
+
+    /// 条件なしの選択肢だけで一周できるループを禁止する
+    ///
+    /// ループは、どこかの選択肢に条件を付けて抜け出せるようにしておく必要がある。
+    fn validate_loops(&self, report: &mut ValidationReport) {
+        let mut finished = HashSet::new();
+        let mut cycles = Vec::new();
+        for node in &self.scenario.nodes {
+            let mut path = Vec::new();
+            self.find_unguarded_cycles(&node.id, &mut path, &mut finished, &mut cycles);
+        }
+        for cycle in cycles {
+            let node = cycle[0].clone();
+            report.push(Some(&node), None, IssueKind::UnguardedLoop { cycle });
+        }
+    }
+
+    fn find_unguarded_cycles(
+        &self,
+        id: &str,
+        path: &mut Vec<String>,
+        finished: &mut HashSet<String>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        if let Some(pos) = path.iter().position(|p| p == id) {
+            let mut cycle = path[pos..].to_vec();
+            cycle.push(id.to_string());
+            cycles.push(cycle);
+            return;
+        }
+        if finished.contains(id) {
+            return;
+        }
+        let Some(node) = self.nodes.get(id) else {
+            return;
+        };
+
+        path.push(id.to_string());
+        for choice in node.choices.iter().filter(|c| c.condition.is_none()) {
+            self.find_unguarded_cycles(&choice.to, path, finished, cycles);
+        }
+        path.pop();
+        finished.insert(id.to_string());
+    }
+
+    fn validate_reachability(&self, report: &mut ValidationReport) {
+        let start = &self.scenario.meta.start;
+        if !self.nodes.contains_key(start) {
+            report.push(
+                None,
+                None,
+                IssueKind::StartNodeMissing {
+                    start: start.clone(),
+                },
+            );
+            return;
+        }
+
+        let reachable = self.reachable_ids();
+        for node in &self.scenario.nodes {
+            if !reachable.contains(&node.id) {
+                report.push(Some(&node.id), None, IssueKind::UnreachableNode);
+            }
+        }
+    }
+
+    fn validate_variables(&self, report: &mut ValidationReport) {
+        for node in &self.scenario.nodes {
+            for (i, choice) in node.choices.iter().enumerate() {
+                let mut uses = Vec::new();
+                if let Some(condition) = &choice.condition {
+                    collect_condition_uses(condition, &mut uses);
+                }
+                for effect in &choice.effects {
+                    uses.push(effect_use(effect));
+                }
+
+                for (var, used_as) in uses {
+                    if let Some(kind) = self.check_variable(var, used_as) {
+                        report.push(Some(&node.id), Some(i), kind);
+                    }
+                }
+            }
+
+            // 条件の無い選択肢が1つも無いと、どれも満たさないときに先へ進めなくなる
+            if !node.choices.is_empty() && node.choices.iter().all(|c| c.condition.is_some()) {
+                report.push(Some(&node.id), None, IssueKind::AllChoicesConditional);
+            }
+        }
+    }
+
+    /// 宣言の有無と型を確認する（used_as が None なら型は問わない）
+    fn check_variable(&self, var: &str, used_as: Option<&'static str>) -> Option<IssueKind> {
+        let Some(declared) = self.scenario.meta.variables.get(var) else {
+            return Some(IssueKind::UndeclaredVariable {
+                var: var.to_string(),
+            });
+        };
+        match used_as {
+            Some(used_as) if declared.type_name() != used_as => {
+                Some(IssueKind::VariableTypeMismatch {
+                    var: var.to_string(),
+                    declared: declared.type_name(),
+                    used_as,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// 分岐数と、選択肢の遷移先がルートIDのエンコードに沿っているかを確認する
+    fn validate_branching(&self, report: &mut ValidationReport) {
+        for node in &self.scenario.nodes {
+            let branching = node.choices.len();
+            if branching > route_id::MAX_BRANCHING {
+                report.push(
+                    Some(&node.id),
+                    None,
+                    IssueKind::TooManyBranches {
+                        actual: branching,
+                        max: route_id::MAX_BRANCHING,
+                    },
+                );
+                continue;
+            }
+
+            // ルートIDで命名されたノードのみ、階層と子のIDを検証する
+            // （別ルートのノードへ合流する選択肢は対象外）
+            let Some(depth) = route_id::depth(&node.id) else {
+                continue;
+            };
+            let max_depth = self.scenario.meta.depth;
+            if depth > max_depth {
+                report.push(
+                    Some(&node.id),
+                    None,
+                    IssueKind::IdDepthMismatch { depth, max_depth },
+                );
+            }
+            for (i, choice) in node.choices.iter().enumerate() {
+                let is_direct_child = choice.to.starts_with(&node.id)
+                    && route_id::depth(&choice.to) == Some(depth + 1);
+                if !is_direct_child {
+                    continue;
+                }
+                let expected = route_id::child_for_choice(&node.id, i, branching);
+                if choice.to != expected {
+                    report.push(
+                        Some(&node.id),
+                        Some(i),
+                        IssueKind::RouteIdMismatch {
+                            to: choice.to.clone(),
+                            expected,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// 選択肢のないノード（葉）には必ずエンディングが必要で、エンディングは葉でなければならない
+    fn validate_endings(&self, report: &mut ValidationReport) {
+        for node in &self.scenario.nodes {
+            match (node.choices.is_empty(), node.ending.is_some()) {
+                (true, false) => report.push(Some(&node.id), None, IssueKind::LeafWithoutEnding),
+                (false, true) => report.push(Some(&node.id), None, IssueKind::EndingWithChoices),
+                _ => {}
+            }
+        }
+    }
+
+    /// 本文の速さを 0 にすると文字が出ないまま選択肢も出ない。text と pages は片方だけ
+    fn validate_text(&self, report: &mut ValidationReport) {
+        for node in &self.scenario.nodes {
+            if !node.text.is_empty() && !node.pages.is_empty() {
+                report.push(Some(&node.id), None, IssueKind::TextAndPages);
+            }
+            if node.text_speed == Some(0) {
+                report.push(Some(&node.id), None, IssueKind::ZeroTextSpeed);
+            }
+        }
+    }
+}
+
+/// シナリオから参照されるアセットの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    Image,
+    Audio,
+    Font,
+}
+
+impl AssetKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            AssetKind::Image => "image",
+            AssetKind::Audio => "audio",
+            AssetKind::Font => "font",
+        }
+    }
+
+    /// Bevy の既定機能で読み込める拡張子
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            AssetKind::Image => &["png", "hdr", "ktx2"],
+            AssetKind::Audio => &["ogg", "oga", "spx"],
+            AssetKind::Font => &["ttf", "otf"],
+        }
+    }
+
+    /// ファイル先頭のシグネチャが種類に合っているか
+    fn matches_header(self, extension: &str, header: &[u8]) -> bool {
+        let expected: &[&[u8]] = match (self, extension) {
+            (AssetKind::Image, "png") => &[b"\x89PNG\r\n\x1a\n"],
+            (AssetKind::Image, "ktx2") => &[b"\xabKTX 20\xbb"],
+            (AssetKind::Image, "hdr") => &[b"#?RADIANCE", b"#?RGBE"],
+            (AssetKind::Audio, _) => &[b"OggS"],
+            (AssetKind::Font, _) => &[b"\x00\x01\x00\x00", b"OTTO", b"true"],
+            _ => return true,
+        };
+        expected.iter().any(|magic| header.starts_with(magic))
+    }
+}
+
+fn check_asset(
+    asset_root: &Path,
+    field: &'static str,
+    kind: AssetKind,
+    path: &str,
+) -> Option<IssueKind> {
+    let full_path = asset_root.join(path);
+    let wrong_type = || IssueKind::WrongAssetType {
+        field,
+        path: path.to_string(),
+        expected: kind.name(),
+    };
+
+    if !full_path.exists() {
+        return Some(IssueKind::MissingAsset {
+            field,
+            path: path.to_string(),
+        });
+    }
+    if !full_path.is_file() {
+        return Some(wrong_type());
+    }
+
+    let extension = full_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    if !kind.extensions().contains(&extension.as_str()) {
+        return Some(wrong_type());
+    }
+
+    let mut header = [0u8; 16];
+    let read = std::fs::File::open(&full_path)
+        .and_then(|mut f| f.read(&mut header))
+        .unwrap_or(0);
+    if !kind.matches_header(&extension, &header[..read]) {
+        return Some(wrong_type());
+    }
+    None
+}
+
+fn collect_condition_uses<'a>(
+    condition: &'a Condition,
+    uses: &mut Vec<(&'a str, Option<&'static str>)>,
+) {
+    match condition {
+        Condition::Eq { var, value } | Condition::Ne { var, value } => {
+            uses.push((var, Some(value.type_name())))
+        }
+        Condition::Gt { var, .. }
+        | Condition::Gte { var, .. }
+        | Condition::Lt { var, .. }
+        | Condition::Lte { var, .. } => uses.push((var, Some("int"))),
+        Condition::All { of } | Condition::Any { of } => {
+            for c in of {
+                collect_condition_uses(c, uses);
+            }
+        }
+        Condition::Not { of } => collect_condition_uses(of, uses),
+    }
+}
+
+fn effect_use(effect: &Effect) -> (&str, Option<&'static str>) {
+    match effect {
+        Effect::Set { var, value } => (var, Some(value.type_name())),
+        Effect::Inc { var, .. } => (var, Some("int")),
+        Effect::Clear { var } => (var, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_report(json: &str) -> ValidationReport {
+        match ScenarioData::load_from_json(json) {
+            Ok(scenario_data) => scenario_data.validate(),
+            Err(ScenarioError::Invalid(report)) => report,
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+
+    fn has_issue(report: &ValidationReport, node: Option<&str>, kind: &IssueKind) -> bool {
+        report
+            .issues
+            .iter()
+            .any(|i| i.node.as_deref() == node && &i.kind == kind)
+    }
+
+    #[test]
+    fn test_report_collects_every_issue() {
+        let report = load_report(
+            r#"{
+              "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+              "nodes": [
+                {"id": "R", "text": "root", "choices": [
+                  {"label": "a", "to": "R1"}, {"label": "b", "to": "MISSING"}
+                ]},
+                {"id": "R1", "text": "stuck", "pages": ["p1", "p2"]},
+                {"id": "R1", "text": "dup", "ending": {"tag": "A"}},
+                {"id": "R11", "text": "deep", "ending": {"tag": "B"}, "text_speed": 0},
+                {"id": "orphan", "text": "o", "ending": {"tag": "C"}, "choices": [
+                  {"label": "a", "to": "R"}
+                ]}
+              ]
+            }"#,
+        );
+
+        assert!(report.has_errors());
+        assert!(has_issue(&report, Some("R1"), &IssueKind::DuplicateId));
+        assert!(has_issue(
+            &report,
+            Some("R"),
+            &IssueKind::DanglingReference {
+                to: "MISSING".to_string()
+            }
+        ));
+        assert!(has_issue(
+            &report,
+            Some("R1"),
+            &IssueKind::LeafWithoutEnding
+        ));
+        assert!(has_issue(
+            &report,
+            Some("orphan"),
+            &IssueKind::EndingWithChoices
+        ));
+        assert!(has_issue(
+            &report,
+            Some("R11"),
+            &IssueKind::IdDepthMismatch {
+                depth: 2,
+                max_depth: 1
+            }
+        ));
+        assert!(has_issue(
+            &report,
+            Some("orphan"),
+            &IssueKind::UnreachableNode
+        ));
+        assert!(has_issue(&report, Some("R11"), &IssueKind::ZeroTextSpeed));
+        assert!(has_issue(&report, Some("R1"), &IssueKind::TextAndPages));
+
+        // 場所と選択肢の添字も持っている
+        let dangling = report
+            .issues
+            .iter()
+            .find(|i| matches!(i.kind, IssueKind::DanglingReference { .. }))
+            .unwrap();
+        assert_eq!(dangling.choice, Some(1));
+        assert_eq!(dangling.severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_empty_scenario() {
+        let report = load_report(
+            r#"{
+              "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+              "nodes": []
+            }"#,
+        );
+
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].kind, IssueKind::EmptyScenario);
+        assert_eq!(report.issues[0].node, None);
+    }
+
+    #[test]
+    fn test_warnings_do_not_fail_loading() {
+        let json = r#"{
+          "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": ""},
+          "nodes": [
+            {"id": "R", "text": "root", "ending": {"tag": "A"}},
+            {"id": "extra", "text": "x", "ending": {"tag": "B"}}
+          ]
+        }"#;
+
+        let scenario_data = ScenarioData::load_from_json(json).unwrap();
+        let report = scenario_data.validate();
+        assert!(!report.has_errors());
+        assert_eq!(report.warnings().count(), 1);
+        assert_eq!(
+            report.to_string(),
+            "[warning] node 'extra': node is not reachable from the start node"
+        );
+    }
+
+    #[test]
+    fn test_variable_issues_have_choice_location() {
+        let report = load_report(
+            r#"{
+              "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": "",
+                       "variables": {"flag": false}},
+              "nodes": [
+                {"id": "R", "text": "root", "choices": [
+                  {"label": "a", "to": "R1", "effects": [{"op": "inc", "var": "flag"}]},
+                  {"label": "b", "to": "R0", "condition": {"op": "eq", "var": "nope", "value": 1}}
+                ]},
+                {"id": "R1", "text": "e", "ending": {"tag": "A"}},
+                {"id": "R0", "text": "e", "ending": {"tag": "B"}}
+              ]
+            }"#,
+        );
+
+        let errors: Vec<_> = report.errors().collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].choice, Some(0));
+        assert_eq!(
+            errors[0].kind,
+            IssueKind::VariableTypeMismatch {
+                var: "flag".to_string(),
+                declared: "bool",
+                used_as: "int"
+            }
+        );
+        assert_eq!(errors[1].choice, Some(1));
+        assert_eq!(
+            errors[1].kind,
+            IssueKind::UndeclaredVariable {
+                var: "nope".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_all_conditional_choices_warn() {
+        let json = r#"{
+          "meta": {"title": "Test", "depth": 1, "default_background": "", "rain_bgm": "", "font": "",
+                   "variables": {"key": false}},
+          "nodes": [
+            {"id": "R", "text": "root", "choices": [
+              {"label": "a", "to": "R1", "condition": {"op": "eq", "var": "key", "value": true}},
+              {"label": "b", "to": "R0", "condition": {"op": "eq", "var": "key", "value": true},
+               "on_unmet": "disable"}
+            ]},
+            {"id": "R1", "text": "e", "ending": {"tag": "A"}},
+            {"id": "R0", "text": "e", "ending": {"tag": "B"}}
+          ]
+        }"#;
+
+        // 読み込めるが作者には知らせる
+        let scenario_data = ScenarioData::load_from_json(json).unwrap();
+        let report = scenario_data.validate();
+        assert!(!report.has_errors());
+        assert!(has_issue(
+            &report,
+            Some("R"),
+            &IssueKind::AllChoicesConditional
+        ));
+        assert!(!scenario_data.has_available_choice(&scenario_data.initial_current()));
+    }
+
+    fn asset_scenario() -> ScenarioData {
+        ScenarioData::load_from_json(
+            r#"{
+              "meta": {"title": "Test", "depth": 1, "default_background": "images/bg.png",
+                       "rain_bgm": "audio/rain.mp3", "font": "fonts/font.ttf"},
+              "nodes": [
+                {"id": "R", "text": "root", "choices": [{"label": "a", "to": "R1"}, {"label": "b", "to": "R0"}]},
+                {"id": "R1", "text": "e", "bg": "images/fake.png", "ending": {"tag": "A"}},
+                {"id": "R0", "text": "e", "bg": "images/nope.png", "ending": {"tag": "B"}}
+              ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("images")).unwrap();
+        std::fs::create_dir_all(root.join("audio")).unwrap();
+        std::fs::create_dir_all(root.join("fonts/font.ttf")).unwrap();
+        std::fs::write(root.join("images/bg.png"), b"\x89PNG\r\n\x1a\n....").unwrap();
+        std::fs::write(root.join("images/fake.png"), b"GIF89a").unwrap();
+        std::fs::write(root.join("audio/rain.mp3"), b"ID3").unwrap();
+
+        let report = asset_scenario().validate_assets(root);
+
+        let wrong = |field, path: &str, expected| IssueKind::WrongAssetType {
+            field,
+            path: path.to_string(),
+            expected,
+        };
+        assert_eq!(report.issues.len(), 4);
+        assert!(has_issue(
+            &report,
+            None,
+            &wrong("rain_bgm", "audio/rain.mp3", "audio")
+        ));
+        // ファイルではなくディレクトリ
+        assert!(has_issue(
+            &report,
+            None,
+            &wrong("font", "fonts/font.ttf", "font")
+        ));
+        // 拡張子は合っているが中身が違う
+        assert!(has_issue(
+            &report,
+            Some("R1"),
+            &wrong("bg", "images/fake.png", "image")
+        ));
+        assert!(has_issue(
+            &report,
+            Some("R0"),
+            &IssueKind::MissingAsset {
+                field: "bg",
+                path: "images/nope.png".to_string()
+            }
+        ));
+    }
+
+    #[test]
+    fn test_bundled_assets_are_valid() {
+        let json =
+            std::fs::read_to_string(crate::app_impl::asset_dir().join("scenario.json")).unwrap();
+        let scenario_data = ScenarioData::load_from_json(&json).unwrap();
+
+        let report = scenario_data.validate_assets(&crate::app_impl::asset_dir());
+        assert!(report.is_empty(), "{report}");
+    }
+}
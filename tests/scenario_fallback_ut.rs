@@ -27,12 +27,14 @@ mod tests {
                 {
                     "id": "R1", 
                     "text": "Node R1 text",
-                    "choices": []
+                    "choices": [],
+                    "ending": {"tag": "R1 END"}
                 },
                 {
                     "id": "R0",
                     "text": "Node R0 text", 
-                    "choices": []
+                    "choices": [],
+                    "ending": {"tag": "R0 END"}
                 }
             ]
         }"#;
@@ -75,17 +77,25 @@ mod tests {
                 {
                     "id": "START",
                     "text": "Start node text",
-                    "choices": []
+                    "choices": [],
+                    "ending": {"tag": "START END"}
                 },
                 {
                     "id": "OTHER",
                     "text": "Other node text",
-                    "choices": []
+                    "choices": [],
+                    "ending": {"tag": "OTHER END"}
                 }
             ]
         }"#;
 
-        let scenario_data = ScenarioData::load_from_json(scenario_json_without_root).unwrap();
+        // 開始ノードが無いシナリオは進められないので読み込めない
+        assert!(ScenarioData::load_from_json(scenario_json_without_root).is_err());
+
+        // 検証を通さずに読んだ場合でもフォールバックは動く
+        let scenario_data = ScenarioData::parse_json(scenario_json_without_root).unwrap();
+        let report = scenario_data.validate();
+        assert!(report.has_errors());
 
         let node = scenario_data.get_node_or_fallback("NONEXISTENT");
         // ルート'R'が存在しないので最初のノード'START'が返される